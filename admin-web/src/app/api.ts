@@ -22,6 +22,7 @@ export interface Connection {
   type: 'vscode' | 'qoder' | 'cli' | 'web' | 'other'
   connected_at: string
   last_activity: string
+  user?: string
 }
 
 export interface Tool {
@@ -39,6 +40,9 @@ export interface ExtensionModule {
 
 const API_BASE = process.env.NEXT_PUBLIC_API_URL || ''
 
+// Identity the server authorizes admin UI tool calls and listings against
+export const MCP_USER = process.env.NEXT_PUBLIC_MCP_USER || 'local:dev'
+
 export async function fetchServerStatus(): Promise<ServerStatus> {
   const response = await fetch(`${API_BASE}/api/status`)
   if (!response.ok) {
@@ -70,7 +74,9 @@ export async function toggleTool(toolName: string, enabled: boolean): Promise<vo
 }
 
 export async function fetchTools(): Promise<Tool[]> {
-  const response = await fetch(`${API_BASE}/api/tools`)
+  const response = await fetch(`${API_BASE}/api/tools`, {
+    headers: { 'x-mcp-user': MCP_USER },
+  })
   if (!response.ok) {
     throw new Error(`Failed to fetch tools: ${response.statusText}`)
   }
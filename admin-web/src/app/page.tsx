@@ -1,7 +1,7 @@
 'use client'
 
 import { useState, useEffect } from 'react'
-import { fetchServerStatus, toggleContextEngine, fetchTools, toggleTool, createTool, updateTool, deleteTool, virtualConnectorHealth, virtualConnect, virtualDisconnect, fetchExtensions, fetchPlugins, fetchConnectors, type ServerStatus, type Connection, type Tool, type Extension, type Plugin, type ConnectorInfo } from './api'
+import { fetchServerStatus, toggleContextEngine, fetchTools, toggleTool, createTool, updateTool, deleteTool, virtualConnectorHealth, virtualConnect, virtualDisconnect, fetchExtensions, fetchPlugins, fetchConnectors, type ServerStatus, type Connection, type Tool, type Extension, type Plugin, type ConnectorInfo, MCP_USER } from './api'
 import dynamic from 'next/dynamic'
 
 const PoliciesPage = dynamic(() => import('./policies/page'), { ssr: false })
@@ -856,7 +856,7 @@ Refresh this page after building to see your plugin.`)
       const packageJsonPath = pluginPath.replace(/\/$/, '') + '/package.json'
       const checkResponse = await fetch('/api/tools/execute', {
         method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', 'x-mcp-user': MCP_USER },
         body: JSON.stringify({
           tool: 'fs.read',
           input: { path: packageJsonPath },
@@ -1377,7 +1377,7 @@ function ExtensionsTab() {
       const packageJsonPath = extPath.replace(/\/$/, '') + '/package.json'
       const pkgResponse = await fetch('/api/tools/execute', {
         method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', 'x-mcp-user': MCP_USER },
         body: JSON.stringify({
           tool: 'fs.read',
           input: { path: packageJsonPath },
@@ -1426,7 +1426,7 @@ function ExtensionsTab() {
       const buildPath = extPath.replace(/\/$/, '') + '/dist/index.js'
       const buildResponse = await fetch('/api/tools/execute', {
         method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', 'x-mcp-user': MCP_USER },
         body: JSON.stringify({
           tool: 'fs.read',
           input: { path: buildPath },
@@ -1447,7 +1447,7 @@ function ExtensionsTab() {
       const manifestPath = `.mcp/tools/${extName}.json`
       const manifestResponse = await fetch('/api/tools/execute', {
         method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', 'x-mcp-user': MCP_USER },
         body: JSON.stringify({
           tool: 'fs.read',
           input: { path: manifestPath },
@@ -1511,7 +1511,7 @@ function ExtensionsTab() {
       const packageJsonPath = extPath.replace(/\/$/, '') + '/package.json'
       const pkgResponse = await fetch('/api/tools/execute', {
         method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', 'x-mcp-user': MCP_USER },
         body: JSON.stringify({
           tool: 'fs.read',
           input: { path: packageJsonPath },
@@ -2038,7 +2038,7 @@ function SettingsTab() {
         body: JSON.stringify(settings)
       })
       if (res.ok) {
-        setMessage({ type: 'success', text: 'Settings saved successfully. Restart server to apply changes.' })
+        setMessage({ type: 'success', text: 'Settings saved. The server is now listening on the new port.' })
       } else {
         setMessage({ type: 'error', text: 'Failed to save settings' })
       }
@@ -2122,7 +2122,7 @@ function SettingsTab() {
             {saving ? 'Saving...' : 'Save Settings'}
           </button>
           <p className="text-xs text-gray-500 mt-2">
-            ⚠️ Server restart required for changes to take effect
+            The server rebinds to the new port without a restart
           </p>
         </div>
       </div>
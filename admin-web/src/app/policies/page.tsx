@@ -1,11 +1,24 @@
 'use client'
 
 import { useState, useEffect } from 'react'
+import { MCP_USER } from '../api'
+
+interface FsRule {
+  path: string
+  mode?: 'read' | 'write' | 'delete'
+  deny?: string[]
+  exclude?: string[]
+}
+
+/** Plain strings grant full access; rules restrict mode and hide sub-paths */
+type FsAllowEntry = string | FsRule
+
+const entryPath = (entry: FsAllowEntry) => (typeof entry === 'string' ? entry : entry.path)
 
 interface Policies {
   roles: Record<string, string[]>
   users: Record<string, string>
-  fs_allowlist: string[]
+  fs_allowlist: FsAllowEntry[]
 }
 
 export default function PoliciesPage() {
@@ -50,11 +63,15 @@ export default function PoliciesPage() {
     try {
       const response = await fetch('/api/policies', {
         method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', 'x-mcp-user': MCP_USER },
         body: JSON.stringify(policies),
       })
 
-      if (!response.ok) throw new Error(`HTTP ${response.status}`)
+      if (!response.ok) {
+        // Validation failures list every problem found
+        const body = await response.json().catch(() => null)
+        throw new Error(body?.errors?.join('; ') ?? `HTTP ${response.status}`)
+      }
       
       setSuccess(true)
       setTimeout(() => setSuccess(false), 3000)
@@ -103,7 +120,7 @@ export default function PoliciesPage() {
   const addAllowPath = () => {
     if (!policies || !newAllowPath) return
     
-    if (!policies.fs_allowlist.includes(newAllowPath)) {
+    if (!policies.fs_allowlist.some(entry => entryPath(entry) === newAllowPath)) {
       setPolicies({
         ...policies,
         fs_allowlist: [...policies.fs_allowlist, newAllowPath]
@@ -116,7 +133,7 @@ export default function PoliciesPage() {
     if (!policies) return
     setPolicies({
       ...policies,
-      fs_allowlist: policies.fs_allowlist.filter(p => p !== path)
+      fs_allowlist: policies.fs_allowlist.filter(entry => entryPath(entry) !== path)
     })
   }
 
@@ -265,11 +282,20 @@ export default function PoliciesPage() {
           Only paths in this allowlist can be accessed by fs.* tools
         </p>
         <div className="space-y-2">
-          {policies.fs_allowlist.map((path) => (
-            <div key={path} className="flex items-center justify-between p-4 rounded bg-gray-700">
-              <code className="text-green-400">{path}</code>
+          {policies.fs_allowlist.map((entry) => (
+            <div key={entryPath(entry)} className="flex items-center justify-between p-4 rounded bg-gray-700">
+              <div>
+                <code className="text-green-400">{entryPath(entry)}</code>
+                {typeof entry !== 'string' && (
+                  <div className="text-xs text-gray-400 mt-1">
+                    {entry.mode ?? 'read'}
+                    {entry.deny?.length ? ` · deny: ${entry.deny.join(', ')}` : ''}
+                    {entry.exclude?.length ? ` · exclude: ${entry.exclude.join(', ')}` : ''}
+                  </div>
+                )}
+              </div>
                 <button
-                  onClick={() => removeAllowPath(path)}
+                  onClick={() => removeAllowPath(entryPath(entry))}
                   className="px-3 py-1 text-sm rounded bg-red-600 hover:bg-red-700 text-white transition-colors"
                 >
                 Remove
@@ -32,7 +32,7 @@ export default function SettingsPage() {
     setSaving(false);
 
     if (res.ok) {
-      setMsg("✓ Saved to .mcp/config.json. Restart server to apply new port.");
+      setMsg("✓ Saved to .mcp/config.json. The server is now listening on the new port.");
     } else {
       setMsg("❌ Save failed");
     }
@@ -106,8 +106,7 @@ export default function SettingsPage() {
             </h3>
             <ul className="text-sm text-gray-600 dark:text-gray-400 space-y-1">
               <li>• Changes are saved immediately to <code className="bg-gray-100 dark:bg-gray-700 px-1 py-0.5 rounded">.mcp/config.json</code></li>
-              <li>• <strong>Server restart required</strong> to bind to the new port</li>
-              <li>• Run: <code className="bg-gray-100 dark:bg-gray-700 px-1 py-0.5 rounded">./start-services.sh</code> to restart</li>
+              <li>• The server <strong>rebinds to the new port</strong> immediately; reload the Admin UI from the new address</li>
               <li>• All services (Admin UI + API) run on a single port</li>
             </ul>
           </div>
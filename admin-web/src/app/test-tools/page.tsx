@@ -1,6 +1,7 @@
 'use client'
 
 import { useState } from 'react'
+import { MCP_USER } from '../api'
 
 export default function TestToolsPage() {
   const [selectedTool, setSelectedTool] = useState('fs.read')
@@ -27,7 +28,7 @@ export default function TestToolsPage() {
       const input = JSON.parse(toolInput)
       const response = await fetch('/api/tools/execute', {
         method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', 'x-mcp-user': MCP_USER },
         body: JSON.stringify({
           tool: selectedTool,
           input,
@@ -17,6 +17,8 @@ export interface ScrapeUrlInput {
   timeout?: number;
   format?: 'json' | 'html' | 'text';
   outputDir?: string;
+  /** Describe the fetch and the file that would be written, without doing either */
+  dry_run?: boolean;
 }
 
 export interface ScrapeSiteInput {
@@ -29,6 +31,8 @@ export interface ScrapeSiteInput {
   concurrency?: number;
   respectRobots?: boolean;
   outputDir?: string;
+  /** Describe the crawl and the file that would be written, without doing either */
+  dry_run?: boolean;
 }
 
 export interface ParseHtmlInput {
@@ -62,6 +66,21 @@ function generateIdempotencyKey(input: any): string {
   return crypto.createHash('sha256').update(normalized).digest('hex');
 }
 
+/**
+ * Plan returned instead of running when the executor asks for a dry run
+ */
+function dryRun(plan: Record<string, unknown>, idempotencyKey: string, startTime: number): ToolOutput {
+  return {
+    success: true,
+    data: { dry_run: true, plan },
+    metadata: {
+      timestamp: new Date().toISOString(),
+      idempotencyKey,
+      duration: Date.now() - startTime,
+    },
+  };
+}
+
 /**
  * Scrape single URL
  */
@@ -69,6 +88,19 @@ export async function scrapeUrl(input: ScrapeUrlInput): Promise<ToolOutput> {
   const startTime = Date.now();
   const idempotencyKey = generateIdempotencyKey(input);
 
+  if (input.dry_run) {
+    return dryRun(
+      {
+        fetch: [input.url],
+        write: input.outputDir
+          ? path.join(input.outputDir, `${crypto.createHash('md5').update(input.url).digest('hex')}.json`)
+          : null,
+      },
+      idempotencyKey,
+      startTime,
+    );
+  }
+
   try {
     const headers = {
       'User-Agent': input.userAgent || 'Mozilla/5.0 (compatible; NuronesMCP-Scraper/1.0)',
@@ -155,6 +187,24 @@ export async function scrapeSite(input: ScrapeSiteInput): Promise<ToolOutput> {
   const startTime = Date.now();
   const idempotencyKey = generateIdempotencyKey(input);
 
+  if (input.dry_run) {
+    return dryRun(
+      {
+        crawl: {
+          startUrl: input.startUrl,
+          maxPages: input.maxPages || 50,
+          maxDepth: input.maxDepth || 3,
+          sameDomain: input.sameDomain ?? true,
+        },
+        write: input.outputDir
+          ? path.join(input.outputDir, `crawl-${crypto.createHash('md5').update(input.startUrl).digest('hex')}.json`)
+          : null,
+      },
+      idempotencyKey,
+      startTime,
+    );
+  }
+
   try {
     const visited = new Set<string>();
     const queue: Array<{ url: string; depth: number }> = [{ url: input.startUrl, depth: 0 }];
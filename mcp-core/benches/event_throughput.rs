@@ -1,5 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
 use nurones_mcp::*;
+use nurones_mcp::event_bus::EventBus;
 use tokio::runtime::Runtime;
 
 fn benchmark_event_publish(c: &mut Criterion) {
@@ -0,0 +1,364 @@
+use crate::observability::Metrics;
+use crate::types::ContextFrame;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot};
+
+/// Decided requests kept for listing
+const MAX_HISTORY: usize = 1000;
+
+/// How parked calls wait for a human decision
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApprovalSettings {
+    /// Seconds a call waits for a decision before it fails
+    pub timeout_secs: u64,
+    /// Let callers decide on their own calls
+    pub allow_self_approval: bool,
+}
+
+impl Default for ApprovalSettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 300,
+            allow_self_approval: false,
+        }
+    }
+}
+
+impl ApprovalSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        if self.timeout_secs == 0 {
+            vec!["approval: timeout_secs must be positive".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+    /// Nobody decided within `timeout_secs`
+    Expired,
+    /// The caller went away before a decision
+    Cancelled,
+}
+
+impl ApprovalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalStatus::Pending => "pending",
+            ApprovalStatus::Approved => "approved",
+            ApprovalStatus::Rejected => "rejected",
+            ApprovalStatus::Expired => "expired",
+            ApprovalStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// A tool call held for approval, and its outcome once decided
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub id: String,
+    pub reason_trace_id: String,
+    pub actor: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    pub tool: String,
+    /// Redacted summary of the tool input
+    pub input: serde_json::Value,
+    pub context: ContextFrame,
+    /// Rules that asked for approval
+    pub rules: Vec<String>,
+    pub requested_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub status: ApprovalStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+impl ApprovalRequest {
+    pub fn new(
+        actor: String,
+        role: Option<String>,
+        tool: String,
+        input: serde_json::Value,
+        context: ContextFrame,
+        rules: Vec<String>,
+        timeout: Duration,
+    ) -> Self {
+        let requested_at = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            reason_trace_id: context.reason_trace_id.clone(),
+            actor,
+            role,
+            tool,
+            input,
+            context,
+            rules,
+            requested_at,
+            expires_at: requested_at + chrono::Duration::from_std(timeout).unwrap_or(chrono::Duration::MAX),
+            status: ApprovalStatus::Pending,
+            decided_by: None,
+            comment: None,
+            decided_at: None,
+        }
+    }
+
+    /// Outcome in words, for audit entries and error messages
+    pub fn summary(&self) -> String {
+        let mut summary = format!("approval {} {}", self.id, self.status.as_str());
+        if let Some(by) = &self.decided_by {
+            summary.push_str(&format!(" by {}", by));
+        }
+        if let Some(comment) = &self.comment {
+            summary.push_str(&format!(": {}", comment));
+        }
+        summary
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: HashMap<String, (ApprovalRequest, oneshot::Sender<ApprovalRequest>)>,
+    decided: VecDeque<ApprovalRequest>,
+}
+
+/// Calls waiting for a human decision. Every change is broadcast to subscribers.
+pub struct ApprovalQueue {
+    state: Mutex<QueueState>,
+    events: broadcast::Sender<ApprovalRequest>,
+    metrics: Arc<Metrics>,
+}
+
+impl ApprovalQueue {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            state: Mutex::new(QueueState::default()),
+            events,
+            metrics: Arc::new(Metrics::default()),
+        }
+    }
+
+    /// Count decisions in a shared metrics registry
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Park a call until it is decided or `timeout` passes; returns the final record.
+    /// Dropping the future withdraws the request as cancelled.
+    pub async fn request(&self, request: ApprovalRequest, timeout: Duration) -> ApprovalRequest {
+        let id = request.id.clone();
+        let (tx, mut rx) = oneshot::channel();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.pending.insert(id.clone(), (request.clone(), tx));
+        }
+        self.publish(request);
+
+        let mut withdraw = Withdraw {
+            queue: self,
+            id: &id,
+            status: ApprovalStatus::Cancelled,
+        };
+        if let Ok(Ok(decided)) = tokio::time::timeout(timeout, &mut rx).await {
+            withdraw.status = ApprovalStatus::Pending;
+            return decided;
+        }
+        // A decision may have landed between the timeout and taking the lock
+        let expired = self.finish(&id, ApprovalStatus::Expired, None, None);
+        withdraw.status = ApprovalStatus::Pending;
+        match expired {
+            Some(expired) => expired,
+            None => rx.try_recv().expect("decided requests are sent before leaving the queue"),
+        }
+    }
+
+    /// Approve or reject a pending call. `None` if it is not pending; an error if
+    /// `by` is the caller and self-approval is off.
+    pub fn decide(
+        &self,
+        id: &str,
+        approve: bool,
+        by: &str,
+        comment: Option<String>,
+        allow_self_approval: bool,
+    ) -> Result<Option<ApprovalRequest>> {
+        {
+            let state = self.state.lock().unwrap();
+            let Some((request, _)) = state.pending.get(id) else {
+                return Ok(None);
+            };
+            if !allow_self_approval && request.actor == by {
+                bail!("'{}' cannot decide on their own call", by);
+            }
+        }
+        let status = if approve {
+            ApprovalStatus::Approved
+        } else {
+            ApprovalStatus::Rejected
+        };
+        Ok(self.finish(id, status, Some(by.to_string()), comment))
+    }
+
+    /// Move a pending request to the history with `status`, waking its caller
+    fn finish(
+        &self,
+        id: &str,
+        status: ApprovalStatus,
+        by: Option<String>,
+        comment: Option<String>,
+    ) -> Option<ApprovalRequest> {
+        let decided = {
+            let mut state = self.state.lock().unwrap();
+            let (mut request, tx) = state.pending.remove(id)?;
+            request.status = status;
+            request.decided_by = by;
+            request.comment = comment;
+            request.decided_at = Some(Utc::now());
+            state.decided.push_back(request.clone());
+            if state.decided.len() > MAX_HISTORY {
+                state.decided.pop_front();
+            }
+            let _ = tx.send(request.clone());
+            request
+        };
+        tracing::info!("{} ({} for '{}', trace {})", decided.summary(), decided.tool, decided.actor, decided.reason_trace_id);
+        self.metrics.approval_decision(status.as_str());
+        self.publish(decided.clone());
+        Some(decided)
+    }
+
+    fn publish(&self, request: ApprovalRequest) {
+        // No subscribers is fine
+        let _ = self.events.send(request);
+    }
+
+    /// Pending requests first, then decided ones newest first
+    pub fn list(&self, status: Option<ApprovalStatus>, reason_trace_id: Option<&str>) -> Vec<ApprovalRequest> {
+        let state = self.state.lock().unwrap();
+        let mut pending: Vec<ApprovalRequest> = state.pending.values().map(|(r, _)| r.clone()).collect();
+        pending.sort_by_key(|r| r.requested_at);
+        pending
+            .into_iter()
+            .chain(state.decided.iter().rev().cloned())
+            .filter(|r| status.is_none_or(|s| r.status == s))
+            .filter(|r| reason_trace_id.is_none_or(|t| r.reason_trace_id == t))
+            .collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<ApprovalRequest> {
+        let state = self.state.lock().unwrap();
+        state
+            .pending
+            .get(id)
+            .map(|(r, _)| r.clone())
+            .or_else(|| state.decided.iter().find(|r| r.id == id).cloned())
+    }
+
+    /// Every new and decided request from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ApprovalRequest> {
+        self.events.subscribe()
+    }
+}
+
+impl Default for ApprovalQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Withdraws a request whose caller stopped waiting
+struct Withdraw<'a> {
+    queue: &'a ApprovalQueue,
+    id: &'a str,
+    status: ApprovalStatus,
+}
+
+impl Drop for Withdraw<'_> {
+    fn drop(&mut self) {
+        if self.status != ApprovalStatus::Pending {
+            self.queue.finish(self.id, self.status, None, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(actor: &str, timeout: Duration) -> ApprovalRequest {
+        ApprovalRequest::new(
+            actor.to_string(),
+            None,
+            "fs.delete".to_string(),
+            serde_json::json!({"path": "/workspace/a"}),
+            ContextFrame::default(),
+            vec!["caution-approval".to_string()],
+            timeout,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_approve_and_reject() {
+        let queue = Arc::new(ApprovalQueue::new());
+        let mut events = queue.subscribe();
+
+        let waiting = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.request(request("alice", Duration::from_secs(30)), Duration::from_secs(30)).await }
+        });
+        let pending = events.recv().await.unwrap();
+        assert_eq!(pending.status, ApprovalStatus::Pending);
+        assert_eq!(queue.list(Some(ApprovalStatus::Pending), None).len(), 1);
+
+        assert!(queue.decide(&pending.id, true, "alice", None, false).is_err());
+        let decided = queue
+            .decide(&pending.id, false, "bob", Some("not during the freeze".to_string()), false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(decided.status, ApprovalStatus::Rejected);
+
+        let outcome = waiting.await.unwrap();
+        assert_eq!(outcome.status, ApprovalStatus::Rejected);
+        assert_eq!(outcome.summary(), format!("approval {} rejected by bob: not during the freeze", outcome.id));
+        assert_eq!(events.recv().await.unwrap().status, ApprovalStatus::Rejected);
+        assert!(queue.decide(&pending.id, true, "bob", None, false).unwrap().is_none());
+        assert_eq!(queue.list(None, Some(&outcome.reason_trace_id)).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_cancel() {
+        let queue = ApprovalQueue::new();
+        let outcome = queue.request(request("alice", Duration::from_millis(20)), Duration::from_millis(20)).await;
+        assert_eq!(outcome.status, ApprovalStatus::Expired);
+        assert!(queue.list(Some(ApprovalStatus::Pending), None).is_empty());
+
+        let waiting = queue.request(request("alice", Duration::from_secs(30)), Duration::from_secs(30));
+        let _ = tokio::time::timeout(Duration::from_millis(10), waiting).await;
+        assert_eq!(queue.list(Some(ApprovalStatus::Cancelled), None).len(), 1);
+    }
+}
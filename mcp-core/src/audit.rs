@@ -0,0 +1,534 @@
+use crate::redaction::Redactor;
+use crate::types::ContextFrame;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// `prev_hash` of the first entry in a chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Input keys whose values never reach the audit log
+const SENSITIVE_KEYS: &[&str] = &[
+    "password", "passwd", "secret", "token", "api_key", "apikey", "authorization",
+    "cookie", "credential", "private_key",
+];
+
+/// Input keys carrying payloads; only their size is recorded
+const PAYLOAD_KEYS: &[&str] = &["content", "body", "data"];
+
+/// Longest string kept verbatim in an input summary
+const MAX_SUMMARY_STRING: usize = 256;
+
+/// File beside the log recording the newest entry, so a cut-off tail is detected
+const HEAD_FILE: &str = "head.json";
+
+/// Policy decision taken before the tool ran
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditDecision {
+    Allow,
+    Deny,
+}
+
+/// What to record for one tool call
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub actor: String,
+    pub role: Option<String>,
+    pub tool: String,
+    pub input: serde_json::Value,
+    pub context: ContextFrame,
+    pub decision: AuditDecision,
+    pub reason: Option<String>,
+    pub outcome: String,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// One line of the audit log. `hash` covers every other field, including `prev_hash`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub ts: DateTime<Utc>,
+    pub actor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    pub tool: String,
+    /// Redacted summary of the tool input
+    pub input: serde_json::Value,
+    pub context: ContextFrame,
+    pub decision: AuditDecision,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub outcome: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// SHA-256 over the entry serialized with an empty `hash`
+    pub fn compute_hash(&self) -> String {
+        let unsigned = AuditEntry {
+            hash: String::new(),
+            ..self.clone()
+        };
+        let bytes = serde_json::to_vec(&unsigned).expect("audit entries always serialize");
+        hex::encode(Sha256::digest(&bytes))
+    }
+}
+
+/// Result of walking the chain
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub entries: u64,
+    /// Hash of the last valid entry
+    pub head: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Sequence number and hash of the newest entry written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ChainHead {
+    seq: u64,
+    hash: String,
+}
+
+impl ChainHead {
+    fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(HEAD_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        Ok(Some(serde_json::from_str(&content).with_context(|| format!("Corrupt audit head {:?}", path))?))
+    }
+
+    fn save(&self, dir: &Path) -> Result<()> {
+        crate::policies::write_atomic(&dir.join(HEAD_FILE), &serde_json::to_vec(self)?)
+    }
+}
+
+struct ChainState {
+    seq: u64,
+    last_hash: String,
+    file: Option<File>,
+    file_index: u32,
+    file_size: u64,
+}
+
+/// Append-only audit log written as hash-chained JSONL, rotated by size.
+/// Files are named `audit-NNNNNN.jsonl`; the chain continues across files.
+pub struct AuditLog {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    state: Mutex<ChainState>,
+    /// Applied to inputs and errors before they are chained
+    redactor: Option<Arc<Redactor>>,
+}
+
+impl AuditLog {
+    /// Open (or create) the log in `dir`, resuming the chain from the newest file
+    pub fn open<P: AsRef<Path>>(dir: P, max_file_bytes: u64) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create audit directory {:?}", dir))?;
+
+        let files = log_files(&dir)?;
+        let (mut seq, mut last_hash, file_index) = match files.last() {
+            Some((index, path)) => match read_entries(path)?.pop() {
+                Some(last) => (last.seq, last.hash, *index),
+                None => (0, GENESIS_HASH.to_string(), *index),
+            },
+            None => (0, GENESIS_HASH.to_string(), 1),
+        };
+        match ChainHead::load(&dir)? {
+            // Entries were removed from the end: chain on from the head so the gap stays visible
+            Some(head) if head.seq > seq => {
+                tracing::error!("Audit log ends at seq {} but its head is at seq {}; entries are missing", seq, head.seq);
+                (seq, last_hash) = (head.seq, head.hash);
+            }
+            Some(_) => {}
+            None => ChainHead { seq, hash: last_hash.clone() }.save(&dir)?,
+        }
+
+        tracing::info!("Audit log at {:?} resumed at seq {}", dir, seq);
+        Ok(Self {
+            dir,
+            max_file_bytes,
+            state: Mutex::new(ChainState {
+                seq,
+                last_hash,
+                file: None,
+                file_index,
+                file_size: 0,
+            }),
+            redactor: None,
+        })
+    }
+
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Directory holding the log files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Chain a record onto the log and persist it, and the head, before returning.
+    /// This blocks on disk I/O; async callers should run it on the blocking pool.
+    pub fn append(&self, record: AuditRecord) -> Result<AuditEntry> {
+        let mut input = summarize_input(&record.input);
+        let mut error = record.error;
+        if let Some(redactor) = &self.redactor {
+            redactor.redact_value(&record.tool, &mut input);
+            error = error.map(|e| redactor.redact_str(&record.tool, &e));
+        }
+        let mut state = self.state.lock().unwrap();
+
+        let mut entry = AuditEntry {
+            seq: state.seq + 1,
+            ts: Utc::now(),
+            actor: record.actor,
+            role: record.role,
+            tool: record.tool,
+            input,
+            context: record.context,
+            decision: record.decision,
+            reason: record.reason,
+            outcome: record.outcome,
+            error,
+            duration_ms: record.duration_ms,
+            prev_hash: state.last_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+
+        if state.file.is_none() || state.file_size + line.len() as u64 > self.max_file_bytes {
+            self.rotate(&mut state, line.len() as u64)?;
+        }
+        let file = state.file.as_mut().expect("rotate opens a file");
+        file.write_all(&line)?;
+        file.sync_data()?;
+        ChainHead { seq: entry.seq, hash: entry.hash.clone() }.save(&self.dir)?;
+
+        state.file_size += line.len() as u64;
+        state.seq = entry.seq;
+        state.last_hash = entry.hash.clone();
+        Ok(entry)
+    }
+
+    /// Open the current file, moving to a new one if `pending` bytes would overflow it
+    fn rotate(&self, state: &mut ChainState, pending: u64) -> Result<()> {
+        let mut path = file_path(&self.dir, state.file_index);
+        let mut size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size + pending > self.max_file_bytes {
+            state.file_index += 1;
+            path = file_path(&self.dir, state.file_index);
+            size = 0;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open audit file {:?}", path))?;
+        state.file = Some(file);
+        state.file_size = size;
+        Ok(())
+    }
+
+    /// Walk every file in order, checking sequence numbers, links and hashes, and
+    /// that the chain reaches the recorded head
+    pub fn verify<P: AsRef<Path>>(dir: P) -> Result<AuditVerification> {
+        let mut expected_prev = GENESIS_HASH.to_string();
+        let mut entries = 0u64;
+        let head = ChainHead::load(dir.as_ref())?;
+        let mut head_reached = head.as_ref().is_none_or(|h| h.seq == 0);
+
+        for (_, path) in log_files(dir.as_ref())? {
+            let reader = BufReader::new(File::open(&path)?);
+            for line in reader.lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let broken = |error: String| AuditVerification {
+                    valid: false,
+                    entries,
+                    head: expected_prev.clone(),
+                    broken_at: Some(entries + 1),
+                    error: Some(error),
+                };
+
+                let entry: AuditEntry = match serde_json::from_str(&line) {
+                    Ok(entry) => entry,
+                    Err(e) => return Ok(broken(format!("{:?}: unreadable entry: {}", path, e))),
+                };
+                if entry.seq != entries + 1 {
+                    return Ok(broken(format!("expected seq {}, found {}", entries + 1, entry.seq)));
+                }
+                if entry.prev_hash != expected_prev {
+                    return Ok(broken(format!("seq {} does not link to its predecessor", entry.seq)));
+                }
+                if entry.compute_hash() != entry.hash {
+                    return Ok(broken(format!("seq {} was modified after it was written", entry.seq)));
+                }
+
+                if head.as_ref().is_some_and(|h| h.seq == entry.seq) {
+                    if head.as_ref().is_some_and(|h| h.hash != entry.hash) {
+                        return Ok(broken(format!("seq {} does not match the recorded head", entry.seq)));
+                    }
+                    head_reached = true;
+                }
+                expected_prev = entry.hash;
+                entries += 1;
+            }
+        }
+
+        let missing = match &head {
+            None if entries > 0 => Some(format!("{} is missing", HEAD_FILE)),
+            Some(head) if !head_reached => {
+                Some(format!("log ends at seq {} but the head records seq {}; entries were removed", entries, head.seq))
+            }
+            _ => None,
+        };
+        if let Some(error) = missing {
+            return Ok(AuditVerification {
+                valid: false,
+                entries,
+                head: expected_prev,
+                broken_at: Some(entries + 1),
+                error: Some(error),
+            });
+        }
+
+        Ok(AuditVerification {
+            valid: true,
+            entries,
+            head: expected_prev,
+            broken_at: None,
+            error: None,
+        })
+    }
+
+    /// Entries with `from <= ts < to`, in chain order
+    pub fn export<P: AsRef<Path>>(
+        dir: P,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<AuditEntry>> {
+        let mut out = Vec::new();
+        for (_, path) in log_files(dir.as_ref())? {
+            out.extend(read_entries(&path)?.into_iter().filter(|e| {
+                from.is_none_or(|from| e.ts >= from) && to.is_none_or(|to| e.ts < to)
+            }));
+        }
+        Ok(out)
+    }
+}
+
+/// Redacted copy of a tool input: secrets masked, payloads reduced to their size,
+/// long strings truncated
+pub fn summarize_input(input: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match input {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let lower = key.to_ascii_lowercase();
+                    let summary = if SENSITIVE_KEYS.iter().any(|k| lower.contains(k)) {
+                        Value::String("[REDACTED]".to_string())
+                    } else if PAYLOAD_KEYS.contains(&lower.as_str()) && !value.is_null() {
+                        let len = value.as_str().map_or_else(|| value.to_string().len(), str::len);
+                        Value::String(format!("<{} bytes>", len))
+                    } else {
+                        summarize_input(value)
+                    };
+                    (key.clone(), summary)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(summarize_input).collect()),
+        Value::String(s) if s.len() > MAX_SUMMARY_STRING => {
+            let mut end = MAX_SUMMARY_STRING;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            Value::String(format!("{}… ({} bytes)", &s[..end], s.len()))
+        }
+        other => other.clone(),
+    }
+}
+
+fn file_path(dir: &Path, index: u32) -> PathBuf {
+    dir.join(format!("audit-{:06}.jsonl", index))
+}
+
+/// Log files sorted by rotation index
+fn log_files(dir: &Path) -> Result<Vec<(u32, PathBuf)>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<(u32, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let index = name.strip_prefix("audit-")?.strip_suffix(".jsonl")?.parse().ok()?;
+            Some((index, entry.path()))
+        })
+        .collect();
+    files.sort_by_key(|(index, _)| *index);
+    Ok(files)
+}
+
+fn read_entries(path: &Path) -> Result<Vec<AuditEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(
+            serde_json::from_str(&line)
+                .with_context(|| format!("Corrupt audit entry in {:?}", path))?,
+        );
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(tool: &str) -> AuditRecord {
+        AuditRecord {
+            actor: "local:dev".to_string(),
+            role: Some("admin".to_string()),
+            tool: tool.to_string(),
+            input: serde_json::json!({"path": "/tmp/a.txt", "content": "hello", "api_key": "sk-123"}),
+            context: ContextFrame::default(),
+            decision: AuditDecision::Allow,
+            reason: None,
+            outcome: "success".to_string(),
+            error: None,
+            duration_ms: 3,
+        }
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("audit-test-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_chain_verifies_and_detects_tampering() {
+        let dir = temp_dir();
+        let log = AuditLog::open(&dir, 1024 * 1024).unwrap();
+        for tool in ["fs.read", "fs.write", "fs.list"] {
+            log.append(record(tool)).unwrap();
+        }
+
+        let report = AuditLog::verify(&dir).unwrap();
+        assert!(report.valid);
+        assert_eq!(report.entries, 3);
+
+        // Rewrite the second entry's outcome
+        let path = file_path(&dir, 1);
+        let content = fs::read_to_string(&path).unwrap();
+        let tampered: Vec<String> = content
+            .lines()
+            .enumerate()
+            .map(|(i, l)| if i == 1 { l.replace("\"success\"", "\"failure\"") } else { l.to_string() })
+            .collect();
+        fs::write(&path, tampered.join("\n") + "\n").unwrap();
+
+        let report = AuditLog::verify(&dir).unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.broken_at, Some(2));
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_truncated_tail_is_detected() {
+        let dir = temp_dir();
+        let log = AuditLog::open(&dir, 1024 * 1024).unwrap();
+        for tool in ["fs.read", "fs.write", "fs.delete"] {
+            log.append(record(tool)).unwrap();
+        }
+        drop(log);
+
+        // Drop the last entry; every remaining link is still intact
+        let path = file_path(&dir, 1);
+        let content = fs::read_to_string(&path).unwrap();
+        let kept: Vec<&str> = content.lines().take(2).collect();
+        fs::write(&path, kept.join("\n") + "\n").unwrap();
+
+        let report = AuditLog::verify(&dir).unwrap();
+        assert!(!report.valid);
+        assert_eq!((report.entries, report.broken_at), (2, Some(3)));
+
+        // Reopening chains on from the head instead of hiding the gap
+        let log = AuditLog::open(&dir, 1024 * 1024).unwrap();
+        assert_eq!(log.append(record("fs.read")).unwrap().seq, 4);
+        assert!(!AuditLog::verify(&dir).unwrap().valid);
+
+        fs::remove_file(dir.join(HEAD_FILE)).unwrap();
+        assert!(!AuditLog::verify(&dir).unwrap().valid, "a missing head is reported");
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_rotation_and_resume() {
+        let dir = temp_dir();
+        {
+            let log = AuditLog::open(&dir, 600).unwrap();
+            for _ in 0..5 {
+                log.append(record("fs.read")).unwrap();
+            }
+        }
+        assert!(log_files(&dir).unwrap().len() > 1);
+
+        // Reopening continues the same chain
+        let log = AuditLog::open(&dir, 600).unwrap();
+        assert_eq!(log.append(record("fs.read")).unwrap().seq, 6);
+
+        let report = AuditLog::verify(&dir).unwrap();
+        assert!(report.valid, "{:?}", report.error);
+        assert_eq!(report.entries, 6);
+        assert_eq!(AuditLog::export(&dir, None, None).unwrap().len(), 6);
+        assert!(AuditLog::export(&dir, Some(Utc::now()), None).unwrap().is_empty());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_input_summary_redacts() {
+        let summary = summarize_input(&serde_json::json!({
+            "path": "/tmp/a.txt",
+            "content": "hello",
+            "headers": {"Authorization": "Bearer abc"},
+            "note": "x".repeat(1000),
+        }));
+        assert_eq!(summary["path"], "/tmp/a.txt");
+        assert_eq!(summary["content"], "<5 bytes>");
+        assert_eq!(summary["headers"]["Authorization"], "[REDACTED]");
+        assert!(summary["note"].as_str().unwrap().ends_with("(1000 bytes)"));
+    }
+}
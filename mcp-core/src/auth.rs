@@ -0,0 +1,507 @@
+use crate::config::{AuthConfig, AuthProvider, JwtConfig};
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
+
+/// Authenticated caller. `subject` is looked up in `Policies.users`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Principal {
+    pub subject: String,
+    /// Provider that authenticated the request
+    pub method: AuthProvider,
+    /// Tenant the credential is bound to (token entry or JWT `tenant` claim)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+}
+
+/// What an authenticator made of a request
+#[derive(Debug)]
+pub enum AuthOutcome {
+    Authenticated(Principal),
+    /// Credentials present but invalid; stops the chain
+    Rejected(String),
+    /// No credentials this authenticator understands
+    Skipped,
+}
+
+/// Credential source for the HTTP API. `proxied` is set when the request carries
+/// forwarding headers, so `peer` is a proxy rather than the client. `origin` is
+/// the `Origin` of a cross-origin browser request, see [`cross_origin`].
+pub trait Authenticator: Send + Sync {
+    fn authenticate(&self, bearer: Option<&str>, peer: SocketAddr, proxied: bool, origin: Option<&str>) -> AuthOutcome;
+}
+
+/// Static bearer tokens from a secrets file
+pub struct StaticTokens {
+    /// SHA-256 of each token, so comparisons never touch the raw secret
+    tokens: Vec<([u8; 32], Principal)>,
+}
+
+#[derive(Deserialize)]
+struct TokensFile {
+    tokens: Vec<TokenEntry>,
+}
+
+#[derive(Deserialize)]
+struct TokenEntry {
+    user: String,
+    /// Plain token
+    #[serde(default)]
+    token: Option<String>,
+    /// Hex SHA-256 of the token, to keep plain secrets off disk
+    #[serde(default)]
+    sha256: Option<String>,
+    #[serde(default)]
+    tenant: Option<String>,
+}
+
+impl StaticTokens {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read tokens file {}", path))?;
+        let file: TokensFile = serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path))?;
+        let tokens = file
+            .tokens
+            .into_iter()
+            .map(|entry| {
+                let digest = match (&entry.token, &entry.sha256) {
+                    (Some(token), _) => Sha256::digest(token.as_bytes()).into(),
+                    (None, Some(hash)) => hex::decode(hash)
+                        .ok()
+                        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                        .with_context(|| format!("token for '{}': sha256 must be 64 hex digits", entry.user))?,
+                    (None, None) => bail!("token for '{}' needs `token` or `sha256`", entry.user),
+                };
+                let principal = Principal {
+                    subject: entry.user,
+                    method: AuthProvider::Token,
+                    tenant: entry.tenant,
+                };
+                Ok((digest, principal))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { tokens })
+    }
+}
+
+impl Authenticator for StaticTokens {
+    fn authenticate(&self, bearer: Option<&str>, _peer: SocketAddr, _proxied: bool, _origin: Option<&str>) -> AuthOutcome {
+        // JWTs belong to the jwt provider
+        let Some(token) = bearer.filter(|t| t.matches('.').count() != 2) else {
+            return AuthOutcome::Skipped;
+        };
+        let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        match self.tokens.iter().find(|(known, _)| constant_time_eq(known, &digest)) {
+            Some((_, principal)) => AuthOutcome::Authenticated(principal.clone()),
+            None => AuthOutcome::Rejected("unknown API token".to_string()),
+        }
+    }
+}
+
+/// HS256 JWTs with `sub` as the user and `tenant` as the bound tenant, checked
+/// for `exp`, `nbf`, `iss` and `aud`
+pub struct HmacJwt {
+    secret: Vec<u8>,
+    issuer: Option<String>,
+    audience: Option<String>,
+    leeway: i64,
+}
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+}
+
+#[derive(Deserialize)]
+struct JwtClaims {
+    sub: String,
+    exp: i64,
+    #[serde(default)]
+    nbf: Option<i64>,
+    #[serde(default)]
+    iss: Option<String>,
+    /// String or array of strings
+    #[serde(default)]
+    aud: Option<serde_json::Value>,
+    #[serde(default)]
+    tenant: Option<String>,
+}
+
+impl HmacJwt {
+    pub fn from_config(config: &JwtConfig) -> Result<Self> {
+        let secret = match (&config.secret_env, &config.secret_file) {
+            (Some(var), _) => std::env::var(var).with_context(|| format!("JWT secret variable {} is not set", var))?,
+            (None, Some(path)) => std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read JWT secret {}", path))?
+                .trim_end()
+                .to_string(),
+            (None, None) => bail!("auth.jwt needs secretEnv or secretFile"),
+        };
+        if secret.len() < 32 {
+            bail!("JWT secret must be at least 32 bytes");
+        }
+        Ok(Self::new(secret.into_bytes())
+            .with_issuer(config.issuer.clone())
+            .with_audience(config.audience.clone())
+            .with_leeway(config.leeway_secs))
+    }
+
+    pub fn new(secret: Vec<u8>) -> Self {
+        Self {
+            secret,
+            issuer: None,
+            audience: None,
+            leeway: 60,
+        }
+    }
+
+    pub fn with_issuer(mut self, issuer: Option<String>) -> Self {
+        self.issuer = issuer;
+        self
+    }
+
+    pub fn with_audience(mut self, audience: Option<String>) -> Self {
+        self.audience = audience;
+        self
+    }
+
+    pub fn with_leeway(mut self, secs: u64) -> Self {
+        self.leeway = secs as i64;
+        self
+    }
+
+    /// Principal of a valid token
+    pub fn verify(&self, token: &str) -> Result<Principal> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(payload), Some(signature), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            bail!("malformed JWT");
+        };
+
+        let header: JwtHeader = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header)?)?;
+        // Never let the token choose its own algorithm (`none`, RS256 with our secret, ...)
+        if header.alg != "HS256" {
+            bail!("unsupported JWT algorithm '{}'", header.alg);
+        }
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret)?;
+        mac.update(&token.as_bytes()[..header_payload_len(token)]);
+        mac.verify_slice(&URL_SAFE_NO_PAD.decode(signature)?)
+            .map_err(|_| anyhow::anyhow!("bad JWT signature"))?;
+
+        let claims: JwtClaims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload)?)
+            .context("JWT claims must include `sub` and `exp`")?;
+        let now = chrono::Utc::now().timestamp();
+        if claims.exp + self.leeway < now {
+            bail!("JWT expired");
+        }
+        if claims.nbf.is_some_and(|nbf| nbf - self.leeway > now) {
+            bail!("JWT not yet valid");
+        }
+        if let Some(issuer) = &self.issuer {
+            if claims.iss.as_ref() != Some(issuer) {
+                bail!("JWT issuer mismatch");
+            }
+        }
+        if let Some(audience) = &self.audience {
+            let listed = match &claims.aud {
+                Some(serde_json::Value::String(aud)) => aud == audience,
+                Some(serde_json::Value::Array(auds)) => auds.iter().any(|a| a.as_str() == Some(audience)),
+                _ => false,
+            };
+            if !listed {
+                bail!("JWT audience mismatch");
+            }
+        }
+        Ok(Principal {
+            subject: claims.sub,
+            method: AuthProvider::Jwt,
+            tenant: claims.tenant,
+        })
+    }
+}
+
+/// Length of `header.payload`, the signed part of a JWT
+fn header_payload_len(token: &str) -> usize {
+    token.rfind('.').unwrap_or(token.len())
+}
+
+impl Authenticator for HmacJwt {
+    fn authenticate(&self, bearer: Option<&str>, _peer: SocketAddr, _proxied: bool, _origin: Option<&str>) -> AuthOutcome {
+        let Some(token) = bearer.filter(|t| t.matches('.').count() == 2) else {
+            return AuthOutcome::Skipped;
+        };
+        match self.verify(token) {
+            Ok(principal) => AuthOutcome::Authenticated(principal),
+            Err(e) => AuthOutcome::Rejected(e.to_string()),
+        }
+    }
+}
+
+/// Loopback peers only, all acting as one configured user. Requests relayed by
+/// a local reverse proxy are refused, since every remote client would look local,
+/// and so are requests from web pages on origins not listed, which the browser
+/// would otherwise send on the operator's behalf.
+pub struct Localhost {
+    user: String,
+    origins: Vec<String>,
+}
+
+impl Localhost {
+    pub fn new(user: impl Into<String>) -> Self {
+        Self {
+            user: user.into(),
+            origins: Vec::new(),
+        }
+    }
+
+    pub fn with_origins(mut self, origins: Vec<String>) -> Self {
+        self.origins = origins;
+        self
+    }
+}
+
+impl Authenticator for Localhost {
+    fn authenticate(&self, bearer: Option<&str>, peer: SocketAddr, proxied: bool, origin: Option<&str>) -> AuthOutcome {
+        // Credentials no other provider accepted are refused, not ignored
+        if bearer.is_some() || !peer.ip().is_loopback() {
+            AuthOutcome::Skipped
+        } else if proxied {
+            AuthOutcome::Rejected("localhost authentication refuses proxied requests".to_string())
+        } else if let Some(origin) = origin.filter(|o| !self.origins.iter().any(|allowed| allowed == o)) {
+            AuthOutcome::Rejected(format!("localhost authentication refuses requests from origin '{}'", origin))
+        } else {
+            AuthOutcome::Authenticated(Principal {
+                subject: self.user.clone(),
+                method: AuthProvider::Localhost,
+                tenant: None,
+            })
+        }
+    }
+}
+
+/// Configured authenticator chain
+pub struct ApiAuth {
+    authenticators: Vec<Box<dyn Authenticator>>,
+    admin_role: String,
+    allowed_origins: Vec<String>,
+}
+
+impl ApiAuth {
+    pub fn from_config(config: &AuthConfig) -> Result<Self> {
+        let authenticators = config
+            .providers
+            .iter()
+            .map(|provider| -> Result<Box<dyn Authenticator>> {
+                Ok(match provider {
+                    AuthProvider::Token => Box::new(StaticTokens::load(&config.tokens_file)?),
+                    AuthProvider::Jwt => Box::new(HmacJwt::from_config(
+                        config.jwt.as_ref().context("auth.jwt is required for the jwt provider")?,
+                    )?),
+                    AuthProvider::Localhost => Box::new(
+                        Localhost::new(config.localhost_user.clone()).with_origins(config.allowed_origins.clone()),
+                    ),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            authenticators,
+            admin_role: config.admin_role.clone(),
+            allowed_origins: config.allowed_origins.clone(),
+        })
+    }
+
+    pub fn with_authenticator(mut self, authenticator: Box<dyn Authenticator>) -> Self {
+        self.authenticators.push(authenticator);
+        self
+    }
+
+    /// No providers configured: requests other than admin routes are let through anonymously
+    pub fn is_disabled(&self) -> bool {
+        self.authenticators.is_empty()
+    }
+
+    pub fn admin_role(&self) -> &str {
+        &self.admin_role
+    }
+
+    /// Cross-origin browser callers allowed by CORS
+    pub fn allowed_origins(&self) -> &[String] {
+        &self.allowed_origins
+    }
+
+    /// Principal for a request, or why it was refused
+    pub fn authenticate(
+        &self,
+        authorization: Option<&str>,
+        peer: SocketAddr,
+        proxied: bool,
+        origin: Option<&str>,
+    ) -> std::result::Result<Principal, String> {
+        let bearer = authorization.and_then(|value| {
+            value
+                .strip_prefix("Bearer ")
+                .or_else(|| value.strip_prefix("bearer "))
+                .map(str::trim)
+        });
+        for authenticator in &self.authenticators {
+            match authenticator.authenticate(bearer, peer, proxied, origin) {
+                AuthOutcome::Authenticated(principal) => return Ok(principal),
+                AuthOutcome::Rejected(reason) => return Err(reason),
+                AuthOutcome::Skipped => {}
+            }
+        }
+        Err(if bearer.is_some() {
+            "credentials not accepted".to_string()
+        } else {
+            "authentication required".to_string()
+        })
+    }
+}
+
+/// Routes that change server configuration or expose audit data
+pub fn is_admin_route(method: &str, path: &str) -> bool {
+    const ADMIN_PREFIXES: &[&str] = &["/api/policies", "/api/audit", "/api/settings", "/api/tenants", "/api/approvals"];
+    const ADMIN_ROUTES: &[&str] = &[
+        "/api/plugins/create",
+        "/api/extensions/create",
+        "/api/context-engine",
+        "/api/connector/virtual/connect",
+        "/api/connector/virtual/disconnect",
+    ];
+    ADMIN_PREFIXES
+        .iter()
+        .any(|prefix| path == *prefix || path.starts_with(&format!("{}/", prefix)))
+        || ADMIN_ROUTES.contains(&path)
+        // Tool management, but not listing or execution
+        || (path.starts_with("/api/tools") && method != "GET" && path != "/api/tools/execute")
+}
+
+/// `origin` if it names a site other than `host`, the request's own `Host`.
+/// Browsers send `Origin` on same-origin writes too, e.g. from the bundled admin UI.
+pub fn cross_origin<'a>(origin: Option<&'a str>, host: Option<&str>) -> Option<&'a str> {
+    let origin = origin?;
+    let authority = origin.split_once("://").map_or(origin, |(_, rest)| rest);
+    (host != Some(authority)).then_some(origin)
+}
+
+/// Routes served without credentials
+pub fn is_public_route(path: &str) -> bool {
+    path == "/api/health" || path == "/metrics" || !path.starts_with("/api/")
+}
+
+fn constant_time_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn sign(header: serde_json::Value, claims: serde_json::Value) -> String {
+        let signed = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET).unwrap();
+        mac.update(signed.as_bytes());
+        format!("{}.{}", signed, URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()))
+    }
+
+    fn remote() -> SocketAddr {
+        "203.0.113.7:40000".parse().unwrap()
+    }
+
+    #[test]
+    fn test_jwt_validation() {
+        let jwt = HmacJwt::new(SECRET.to_vec())
+            .with_issuer(Some("nurones".to_string()))
+            .with_audience(Some("mcp".to_string()));
+        let hs256 = serde_json::json!({"alg": "HS256", "typ": "JWT"});
+        let exp = chrono::Utc::now().timestamp() + 300;
+
+        let good = sign(hs256.clone(), serde_json::json!({"sub": "ci", "exp": exp, "iss": "nurones", "aud": ["mcp"]}));
+        assert_eq!(jwt.verify(&good).unwrap().subject, "ci");
+        let bound = sign(hs256.clone(), serde_json::json!({"sub": "ci", "exp": exp, "iss": "nurones", "aud": "mcp", "tenant": "acme"}));
+        assert_eq!(jwt.verify(&bound).unwrap().tenant.as_deref(), Some("acme"));
+
+        let expired = sign(hs256.clone(), serde_json::json!({"sub": "ci", "exp": exp - 3600, "iss": "nurones", "aud": "mcp"}));
+        assert!(jwt.verify(&expired).unwrap_err().to_string().contains("expired"));
+        let wrong_aud = sign(hs256.clone(), serde_json::json!({"sub": "ci", "exp": exp, "iss": "nurones", "aud": "other"}));
+        assert!(jwt.verify(&wrong_aud).is_err());
+        let wrong_iss = sign(hs256, serde_json::json!({"sub": "ci", "exp": exp, "iss": "evil", "aud": "mcp"}));
+        assert!(jwt.verify(&wrong_iss).is_err());
+
+        let none = sign(serde_json::json!({"alg": "none"}), serde_json::json!({"sub": "ci", "exp": exp}));
+        assert!(jwt.verify(&none).is_err());
+        let tampered = good.replacen('.', ".e30", 1);
+        assert!(jwt.verify(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_chain_and_tokens() {
+        let dir = std::env::temp_dir().join(format!("mcp-auth-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tokens_file = dir.join("tokens.json");
+        let hashed = hex::encode(Sha256::digest(b"ops-token"));
+        std::fs::write(
+            &tokens_file,
+            serde_json::json!({"tokens": [
+                {"user": "ci", "token": "ci-token"},
+                {"user": "ops", "sha256": hashed}
+            ]})
+            .to_string(),
+        )
+        .unwrap();
+
+        let config = AuthConfig {
+            providers: vec![AuthProvider::Token, AuthProvider::Localhost],
+            tokens_file: tokens_file.to_string_lossy().to_string(),
+            allowed_origins: vec!["https://admin.example".to_string()],
+            ..AuthConfig::default()
+        };
+        let auth = ApiAuth::from_config(&config).unwrap();
+
+        let loopback: SocketAddr = "127.0.0.1:1".parse().unwrap();
+        assert_eq!(auth.authenticate(Some("Bearer ci-token"), remote(), false, None).unwrap().subject, "ci");
+        assert_eq!(auth.authenticate(Some("Bearer ops-token"), remote(), false, None).unwrap().subject, "ops");
+        assert!(auth.authenticate(Some("Bearer nope"), remote(), false, None).is_err());
+        // A wrong token is refused even from loopback
+        assert!(auth.authenticate(Some("Bearer nope"), loopback, false, None).is_err());
+        assert!(auth.authenticate(None, remote(), false, None).is_err());
+        let local = auth.authenticate(None, loopback, false, None).unwrap();
+        assert_eq!((local.subject.as_str(), local.method), ("local:dev", AuthProvider::Localhost));
+        // Behind a local proxy every client is loopback, so only tokens still work
+        assert!(auth.authenticate(None, loopback, true, None).is_err());
+        assert_eq!(auth.authenticate(Some("Bearer ci-token"), loopback, true, None).unwrap().subject, "ci");
+        // Any page the operator opens could call a loopback API; only listed origins act as the local user
+        assert!(auth.authenticate(None, loopback, false, Some("https://evil.example")).is_err());
+        assert!(auth.authenticate(None, loopback, false, Some("https://admin.example")).is_ok());
+        assert_eq!(cross_origin(Some("http://localhost:50620"), Some("localhost:50620")), None);
+        assert_eq!(cross_origin(Some("https://evil.example"), Some("localhost:50620")), Some("https://evil.example"));
+        assert_eq!(cross_origin(None, Some("localhost:50620")), None);
+
+        assert!(ApiAuth::from_config(&AuthConfig::default()).unwrap().is_disabled());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_route_classes() {
+        assert!(is_admin_route("POST", "/api/policies"));
+        assert!(is_admin_route("GET", "/api/policies/revisions"));
+        assert!(is_admin_route("GET", "/api/tenants/acme"));
+        assert!(is_admin_route("POST", "/api/approvals/abc/approve"));
+        assert!(is_admin_route("POST", "/api/plugins/create"));
+        assert!(is_admin_route("DELETE", "/api/tools/fs.read"));
+        assert!(!is_admin_route("POST", "/api/tools/execute"));
+        assert!(!is_admin_route("GET", "/api/tools"));
+        assert!(!is_admin_route("GET", "/api/policiesx"));
+        assert!(is_public_route("/api/health") && is_public_route("/index.html"));
+        assert!(!is_public_route("/api/status"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::types::Stage;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -28,6 +29,15 @@ pub struct ServerConfig {
     pub context_engine: ContextEngineConfig,
     #[serde(default)]
     pub performance: PerformanceConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+    /// Tool manifest directory; the `--tools-dir` flag applies when unset
+    #[serde(rename = "toolsDir", default, skip_serializing_if = "Option::is_none")]
+    pub tools_dir: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,13 +62,16 @@ pub struct ObservabilityConfig {
     pub otel_exporter: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ContextEngineConfig {
     pub enabled: bool,
     #[serde(rename = "changeCapPctPerDay")]
     pub change_cap_pct_per_day: u8,
     #[serde(rename = "minConfidence")]
     pub min_confidence: f64,
+    /// Stages where adjustments are computed and reported but never applied
+    #[serde(rename = "shadowStages", default)]
+    pub shadow_stages: Vec<Stage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -85,6 +98,126 @@ impl Default for PerformanceConfig {
     }
 }
 
+/// Append-only audit log of tool executions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    #[serde(default = "default_audit_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_audit_dir")]
+    pub dir: String,
+    /// Size at which the current JSONL file is rotated
+    #[serde(rename = "maxFileBytes", default = "default_audit_max_file_bytes")]
+    pub max_file_bytes: u64,
+}
+
+fn default_audit_enabled() -> bool { true }
+fn default_audit_dir() -> String { ".mcp/audit".to_string() }
+fn default_audit_max_file_bytes() -> u64 { 10 * 1024 * 1024 }
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: default_audit_dir(),
+            max_file_bytes: default_audit_max_file_bytes(),
+        }
+    }
+}
+
+/// HTTP API authentication. With no providers the API is unauthenticated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Tried in order; the first to recognise the request's credentials wins
+    #[serde(default)]
+    pub providers: Vec<AuthProvider>,
+    /// JSON secrets file for the `token` provider: `{"tokens": [{"user", "token" | "sha256"}]}`
+    #[serde(rename = "tokensFile", default = "default_tokens_file")]
+    pub tokens_file: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwt: Option<JwtConfig>,
+    /// Identity of loopback callers under the `localhost` provider
+    #[serde(rename = "localhostUser", default = "default_localhost_user")]
+    pub localhost_user: String,
+    /// Role that admin routes (policies, settings, audit, plugin and tool management) require
+    #[serde(rename = "adminRole", default = "default_admin_role")]
+    pub admin_role: String,
+    /// Browser origins, besides the server's own, allowed to call the API (CORS)
+    /// and to act as `localhostUser`
+    #[serde(rename = "allowedOrigins", default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthProvider {
+    /// Static bearer tokens from `tokensFile`
+    Token,
+    /// HMAC-signed (HS256) bearer JWTs
+    Jwt,
+    /// Only loopback peers, acting as `localhostUser`
+    Localhost,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtConfig {
+    /// Environment variable holding the shared secret
+    #[serde(rename = "secretEnv", default, skip_serializing_if = "Option::is_none")]
+    pub secret_env: Option<String>,
+    /// File holding the shared secret, used when `secretEnv` is unset
+    #[serde(rename = "secretFile", default, skip_serializing_if = "Option::is_none")]
+    pub secret_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
+    /// Clock skew tolerated on `exp` and `nbf`
+    #[serde(rename = "leewaySecs", default = "default_jwt_leeway")]
+    pub leeway_secs: u64,
+}
+
+fn default_tokens_file() -> String { ".mcp/secrets/api-tokens.json".to_string() }
+fn default_localhost_user() -> String { "local:dev".to_string() }
+fn default_admin_role() -> String { "admin".to_string() }
+fn default_jwt_leeway() -> u64 { 60 }
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            providers: Vec::new(),
+            tokens_file: default_tokens_file(),
+            jwt: None,
+            localhost_user: default_localhost_user(),
+            admin_role: default_admin_role(),
+            allowed_origins: Vec::new(),
+        }
+    }
+}
+
+/// Secret store for tools. Secrets are looked up in the file, then the environment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretsConfig {
+    /// JSON object of secret name to value; a missing file is an empty store
+    #[serde(default = "default_secrets_file")]
+    pub file: String,
+    /// Environment variables served as secrets and never readable through `env.get`
+    #[serde(default = "default_secret_env")]
+    pub env: Vec<String>,
+}
+
+fn default_secrets_file() -> String { ".mcp/secrets/secrets.json".to_string() }
+fn default_secret_env() -> Vec<String> {
+    vec!["DATABASE_URL".to_string(), "OPENAI_API_KEY".to_string()]
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            file: default_secrets_file(),
+            env: default_secret_env(),
+        }
+    }
+}
+
 impl ServerConfig {
     /// Load configuration from file
     pub fn load(path: &str) -> anyhow::Result<Self> {
@@ -104,6 +237,18 @@ impl ServerConfig {
         if self.context_engine.change_cap_pct_per_day > 100 {
             anyhow::bail!("changeCapPctPerDay must be <= 100");
         }
+        if self.server.port == 0 {
+            anyhow::bail!("server.port must be set");
+        }
+        if self.auth.providers.contains(&AuthProvider::Jwt) {
+            match &self.auth.jwt {
+                Some(jwt) if jwt.secret_env.is_some() || jwt.secret_file.is_some() => {}
+                _ => anyhow::bail!("auth.jwt.secretEnv or auth.jwt.secretFile is required for the jwt provider"),
+            }
+        }
+        if self.audit.enabled && self.audit.max_file_bytes == 0 {
+            anyhow::bail!("audit.maxFileBytes must be greater than 0");
+        }
         Ok(())
     }
 }
@@ -129,8 +274,13 @@ mod tests {
                 enabled: true,
                 change_cap_pct_per_day: 10,
                 min_confidence: 0.6,
+                shadow_stages: vec![Stage::Prod],
             },
             performance: PerformanceConfig::default(),
+            audit: AuditConfig::default(),
+            auth: AuthConfig::default(),
+            secrets: SecretsConfig::default(),
+            tools_dir: None,
         };
         assert!(config.validate().is_ok());
     }
@@ -1,13 +1,24 @@
-use crate::types::ContextFrame;
+use crate::config::ContextEngineConfig;
+use crate::observability::Metrics;
+use crate::types::{ContextFrame, Stage};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
 /// Context Engine: Manages adaptive configuration and learning
 pub struct ContextEngine {
+    settings: RwLock<EngineSettings>,
+    metrics: Arc<RwLock<HashMap<String, MetricData>>>,
+    shadow: Arc<RwLock<HashMap<String, ShadowMetric>>>,
+    telemetry: Arc<Metrics>,
+}
+
+/// Tunables that can be swapped while the engine runs
+#[derive(Debug, Clone)]
+struct EngineSettings {
     enabled: bool,
     change_cap_pct: u8,
     min_confidence: f64,
-    metrics: Arc<RwLock<HashMap<String, MetricData>>>,
+    shadow_stages: Vec<Stage>,
 }
 
 #[derive(Debug, Clone)]
@@ -18,22 +29,85 @@ struct MetricData {
     consecutive_successes: u32,
 }
 
+impl MetricData {
+    fn new(value: f64) -> Self {
+        Self {
+            current_value: value,
+            baseline: value,
+            last_update: chrono::Utc::now(),
+            consecutive_successes: 0,
+        }
+    }
+}
+
+/// Shadow-mode bookkeeping: the engine's proposal alongside the live value
+#[derive(Debug, Clone)]
+struct ShadowMetric {
+    proposed: MetricData,
+    live_value: f64,
+}
+
+/// Snapshot of a shadow-mode recommendation for comparison against reality
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct ShadowSample {
+    /// Value actually in effect
+    pub live: f64,
+    /// Value the engine would have applied
+    pub proposed: f64,
+    /// Baseline the proposal was clamped against
+    pub baseline: f64,
+}
+
 impl ContextEngine {
     pub fn new(enabled: bool, change_cap_pct: u8, min_confidence: f64) -> Self {
         Self {
-            enabled,
-            change_cap_pct,
-            min_confidence,
+            settings: RwLock::new(EngineSettings {
+                enabled,
+                change_cap_pct,
+                min_confidence,
+                shadow_stages: Vec::new(),
+            }),
             metrics: Arc::new(RwLock::new(HashMap::new())),
+            shadow: Arc::new(RwLock::new(HashMap::new())),
+            telemetry: Arc::new(Metrics::default()),
         }
     }
 
+    /// Report adjustment counters into a shared metrics registry
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.telemetry = metrics;
+        self
+    }
+
+    /// Run the engine in shadow mode for the given stages: adjustments are
+    /// computed and recorded but never applied
+    pub fn with_shadow_stages(mut self, stages: Vec<Stage>) -> Self {
+        self.settings.get_mut().unwrap().shadow_stages = stages;
+        self
+    }
+
+    /// Swap in new settings; learned baselines are kept
+    pub fn configure(&self, config: &ContextEngineConfig) {
+        *self.settings.write().unwrap() = EngineSettings {
+            enabled: config.enabled,
+            change_cap_pct: config.change_cap_pct_per_day,
+            min_confidence: config.min_confidence,
+            shadow_stages: config.shadow_stages.clone(),
+        };
+    }
+
+    /// Check if the engine only observes (never applies) for a stage
+    pub fn is_shadow(&self, stage: Stage) -> bool {
+        self.settings.read().unwrap().shadow_stages.contains(&stage)
+    }
+
     /// Check if autotune is permitted for given context
     pub fn can_autotune(&self, ctx: &ContextFrame) -> bool {
-        if !self.enabled {
+        let settings = self.settings.read().unwrap();
+        if !settings.enabled {
             return false;
         }
-        ctx.can_autotune() && ctx.context_confidence.unwrap_or(0.0) >= self.min_confidence
+        ctx.can_autotune() && ctx.context_confidence.unwrap_or(0.0) >= settings.min_confidence
     }
 
     /// Apply adaptive adjustment within safety boundaries
@@ -42,17 +116,44 @@ impl ContextEngine {
             return current;
         }
 
+        if self.is_shadow(ctx.stage) {
+            let mut shadow = self.shadow.write().unwrap();
+            let entry = shadow.entry(key.to_string()).or_insert(ShadowMetric {
+                proposed: MetricData::new(current),
+                live_value: current,
+            });
+            let proposed = self.clamp(&mut entry.proposed, current);
+            entry.live_value = current;
+
+            tracing::info!(
+                key = key,
+                live = current,
+                proposed = proposed,
+                baseline = entry.proposed.baseline,
+                stage = ?ctx.stage,
+                reason_trace_id = %ctx.reason_trace_id,
+                "Context engine shadow proposal (not applied)"
+            );
+
+            self.telemetry.engine_adjustment(key, "shadow");
+
+            // Shadow mode never changes live behaviour
+            return current;
+        }
+
         let mut metrics = self.metrics.write().unwrap();
-        let metric = metrics.entry(key.to_string()).or_insert(MetricData {
-            current_value: current,
-            baseline: current,
-            last_update: chrono::Utc::now(),
-            consecutive_successes: 0,
-        });
+        let metric = metrics.entry(key.to_string()).or_insert(MetricData::new(current));
+        let adjusted = self.clamp(metric, current);
+        let mode = if adjusted == current { "applied" } else { "clamped" };
+        self.telemetry.engine_adjustment(key, mode);
+        adjusted
+    }
 
+    /// Clamp a proposed value to the daily change cap around the baseline
+    fn clamp(&self, metric: &mut MetricData, proposed: f64) -> f64 {
         // Calculate max allowed change (±10% per day default)
-        let max_change = metric.baseline * (self.change_cap_pct as f64 / 100.0);
-        let proposed = current;
+        let change_cap_pct = self.settings.read().unwrap().change_cap_pct;
+        let max_change = metric.baseline * (change_cap_pct as f64 / 100.0);
         
         // Clamp to safety boundary
         let adjusted = if proposed > metric.baseline + max_change {
@@ -73,18 +174,33 @@ impl ContextEngine {
     pub fn record_success(&self, key: &str) {
         let mut metrics = self.metrics.write().unwrap();
         if let Some(metric) = metrics.get_mut(key) {
-            metric.consecutive_successes += 1;
-            
-            // After 2 consecutive successes, update baseline
-            if metric.consecutive_successes >= 2 {
-                metric.baseline = metric.current_value;
-                metric.consecutive_successes = 0;
-            }
+            Self::advance_baseline(metric);
+        }
+        drop(metrics);
+
+        let mut shadow = self.shadow.write().unwrap();
+        if let Some(entry) = shadow.get_mut(key) {
+            Self::advance_baseline(&mut entry.proposed);
+        }
+    }
+
+    fn advance_baseline(metric: &mut MetricData) {
+        metric.consecutive_successes += 1;
+        
+        // After 2 consecutive successes, update baseline
+        if metric.consecutive_successes >= 2 {
+            metric.baseline = metric.current_value;
+            metric.consecutive_successes = 0;
         }
     }
 
     /// Rollback to last stable baseline
     pub fn rollback(&self, key: &str) -> Option<f64> {
+        if let Some(entry) = self.shadow.write().unwrap().get_mut(key) {
+            entry.proposed.current_value = entry.proposed.baseline;
+            entry.proposed.consecutive_successes = 0;
+        }
+
         let mut metrics = self.metrics.write().unwrap();
         if let Some(metric) = metrics.get_mut(key) {
             metric.current_value = metric.baseline;
@@ -103,6 +219,24 @@ impl ContextEngine {
             .map(|(k, v)| (k.clone(), (v.current_value, v.baseline)))
             .collect()
     }
+
+    /// Get shadow-mode proposals next to the live values they would replace
+    pub fn shadow_snapshot(&self) -> HashMap<String, ShadowSample> {
+        let shadow = self.shadow.read().unwrap();
+        shadow
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.clone(),
+                    ShadowSample {
+                        live: v.live_value,
+                        proposed: v.proposed.current_value,
+                        baseline: v.proposed.baseline,
+                    },
+                )
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -114,8 +248,10 @@ mod tests {
     fn test_autotune_safety() {
         let engine = ContextEngine::new(true, 10, 0.6);
         
-        let mut ctx = ContextFrame::default();
-        ctx.context_confidence = Some(0.7);
+        let mut ctx = ContextFrame {
+            context_confidence: Some(0.7),
+            ..Default::default()
+        };
         assert!(engine.can_autotune(&ctx));
 
         ctx.risk_level = RiskLevel::Block;
@@ -147,4 +283,29 @@ mod tests {
         let baseline = engine.rollback("test");
         assert_eq!(baseline, Some(100.0));
     }
+
+    #[test]
+    fn test_shadow_mode_never_applies() {
+        let engine = ContextEngine::new(true, 10, 0.6).with_shadow_stages(vec![Stage::Prod]);
+        let prod = ContextFrame {
+            stage: Stage::Prod,
+            ..ContextFrame::default()
+        };
+
+        assert_eq!(engine.adjust_metric("latency", 100.0, &prod), 100.0);
+        assert_eq!(engine.adjust_metric("latency", 120.0, &prod), 120.0);
+
+        let sample = engine.shadow_snapshot()["latency"];
+        assert_eq!(sample.live, 120.0);
+        assert_eq!(sample.proposed, 110.0);
+        assert_eq!(sample.baseline, 100.0);
+
+        // Live metrics untouched by shadow proposals
+        assert!(engine.snapshot().is_empty());
+
+        // Other stages still apply adjustments
+        let dev = ContextFrame::default();
+        engine.adjust_metric("latency", 100.0, &dev);
+        assert_eq!(engine.adjust_metric("latency", 120.0, &dev), 110.0);
+    }
 }
@@ -20,6 +20,10 @@ pub struct ContextFrame {
     pub budgets: Option<serde_json::Value>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub flags: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracestate: Option<String>,
 }
 
 impl ContextFrame {
@@ -47,6 +51,11 @@ impl ContextFrame {
                 anyhow::bail!("context_confidence must be between 0.0 and 1.0");
             }
         }
+        if let Some(tp) = &self.traceparent {
+            if !crate::types::is_valid_traceparent(tp) {
+                anyhow::bail!("traceparent must be a W3C trace context");
+            }
+        }
         Ok(())
     }
 }
@@ -102,6 +111,8 @@ mod tests {
             ts: chrono::Utc::now().to_rfc3339(),
             budgets: None,
             flags: None,
+            traceparent: None,
+            tracestate: None,
         };
         assert!(valid.validate().is_ok());
 
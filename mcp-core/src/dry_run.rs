@@ -0,0 +1,326 @@
+use crate::fs_tools::{prepare_mkdir, prepare_move, read_existing, WriteMode, WriteRequest};
+use crate::security::{FsMode, Sandbox};
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Unchanged lines shown around each change in a diff
+const DIFF_CONTEXT: usize = 3;
+/// Line pairs compared at most; larger rewrites are summarised without a diff
+const MAX_DIFF_CELLS: usize = 4_000_000;
+/// Entries listed in a delete plan
+const MAX_PLAN_ENTRIES: usize = 1000;
+
+/// Filesystem tools planned here rather than by the tool itself
+pub const PLANNED_TOOLS: &[&str] = &["fs.write", "fs.delete", "fs.move", "fs.mkdir"];
+
+/// Plan a call to one of `PLANNED_TOOLS`. Blocking.
+pub fn plan(tool_id: &str, sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    match tool_id {
+        "fs.write" => plan_write(sandbox, input),
+        "fs.delete" => plan_delete(sandbox, input),
+        "fs.move" => plan_move(sandbox, input),
+        "fs.mkdir" => plan_mkdir(sandbox, input),
+        _ => bail!("no dry-run plan for '{}'", tool_id),
+    }
+}
+
+/// What `fs.write` would do: the action, sizes, and a unified diff
+pub fn plan_write(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let request = WriteRequest::parse(input)?;
+    let resolved = sandbox.check(request.path, FsMode::Write)?;
+    let before = read_existing(sandbox, request.path, &resolved)?;
+    request.check_precondition(before.as_deref())?;
+
+    let after = match (&before, request.mode) {
+        (Some(bytes), WriteMode::Append) => [bytes.as_slice(), request.content.as_bytes()].concat(),
+        _ => request.content.as_bytes().to_vec(),
+    };
+    let action = match (&before, request.mode) {
+        (None, _) => "create",
+        (Some(bytes), _) if *bytes == after => "unchanged",
+        (Some(_), WriteMode::Append) => "append",
+        (Some(_), _) => "overwrite",
+    };
+    let diff = std::str::from_utf8(before.as_deref().unwrap_or_default())
+        .ok()
+        .zip(std::str::from_utf8(&after).ok())
+        .map(|(before, after)| unified_diff(&resolved.to_string_lossy(), before, after));
+
+    Ok(json!({
+        "action": action,
+        "path": resolved.to_string_lossy(),
+        "bytes_before": before.as_ref().map(Vec::len),
+        "bytes_after": after.len(),
+        // Absent when the current file is not UTF-8 text
+        "diff": diff,
+    }))
+}
+
+/// What `fs.delete` would remove; directories need `recursive`
+pub fn plan_delete(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let path = input
+        .get("path")
+        .and_then(|v| v.as_str())
+        .context("fs.delete requires 'path' parameter")?;
+    let recursive = input.get("recursive").and_then(|v| v.as_bool()).unwrap_or(false);
+    // A symlink is deleted itself, never its target
+    let resolved = sandbox.check_entry(path, FsMode::Delete)?;
+    let metadata = std::fs::symlink_metadata(&resolved).with_context(|| format!("'{}' does not exist", path))?;
+    if sandbox.roots().any(|root| root == resolved) {
+        bail!("'{}' is an allowlist root and cannot be deleted", path);
+    }
+
+    let mut plan = DeletePlan::default();
+    if metadata.is_dir() {
+        if !recursive && std::fs::read_dir(&resolved)?.next().is_some() {
+            bail!("'{}' is a non-empty directory; set 'recursive' to delete it", path);
+        }
+        plan.walk(sandbox, &resolved)?;
+    } else {
+        plan.add_file(&resolved, metadata.len());
+    }
+
+    Ok(json!({
+        "action": "delete",
+        "path": resolved.to_string_lossy(),
+        "kind": if metadata.is_symlink() {
+            "symlink"
+        } else if metadata.is_dir() {
+            "directory"
+        } else {
+            "file"
+        },
+        "files": plan.files,
+        "directories": plan.directories,
+        "file_count": plan.file_count,
+        "bytes": plan.bytes,
+        // Hidden or undeletable entries make the real delete fail rather than skip them
+        "blocked": plan.blocked,
+        "truncated": plan.file_count + plan.directory_count > MAX_PLAN_ENTRIES,
+    }))
+}
+
+/// What `fs.move` would do
+pub fn plan_move(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let (from, to, replaces) = prepare_move(sandbox, input)?;
+    Ok(json!({
+        "action": "move",
+        "path": from.to_string_lossy(),
+        "destination": to.to_string_lossy(),
+        "replaces": replaces,
+    }))
+}
+
+/// What `fs.mkdir` would do
+pub fn plan_mkdir(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let (resolved, exists) = prepare_mkdir(sandbox, input)?;
+    Ok(json!({
+        "action": if exists { "unchanged" } else { "create" },
+        "path": resolved.to_string_lossy(),
+    }))
+}
+
+#[derive(Default)]
+struct DeletePlan {
+    files: Vec<String>,
+    directories: Vec<String>,
+    blocked: Vec<String>,
+    file_count: usize,
+    directory_count: usize,
+    bytes: u64,
+}
+
+impl DeletePlan {
+    fn listed(&self) -> usize {
+        self.files.len() + self.directories.len()
+    }
+
+    fn add_file(&mut self, path: &Path, len: u64) {
+        self.file_count += 1;
+        self.bytes += len;
+        if self.listed() < MAX_PLAN_ENTRIES {
+            self.files.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    /// Depth-first, children before their directory, without following symlinks
+    fn walk(&mut self, sandbox: &Sandbox, dir: &Path) -> Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if !sandbox.permits_within(&path, FsMode::Delete) {
+                self.blocked.push(path.to_string_lossy().to_string());
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                self.walk(sandbox, &path)?;
+            } else {
+                self.add_file(&path, metadata.len());
+            }
+        }
+        self.directory_count += 1;
+        if self.listed() < MAX_PLAN_ENTRIES {
+            self.directories.push(dir.to_string_lossy().to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Unified diff of two texts by line; empty when they are equal
+pub fn unified_diff(path: &str, before: &str, after: &str) -> String {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+    let Some(ops) = diff_ops(&a, &b) else {
+        return format!(
+            "--- {path}\n+++ {path}\n@@ -1,{} +1,{} @@ (too large to compare line by line)\n",
+            a.len(),
+            b.len()
+        );
+    };
+    if ops.iter().all(|(op, _, _)| *op == Op::Equal) {
+        return String::new();
+    }
+
+    let mut out = format!("--- {path}\n+++ {path}\n");
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != Op::Equal).collect();
+    let mut k = 0;
+    while k < changes.len() {
+        let start = changes[k].saturating_sub(DIFF_CONTEXT);
+        let mut last = changes[k];
+        while k + 1 < changes.len() && changes[k + 1] <= last + 2 * DIFF_CONTEXT {
+            k += 1;
+            last = changes[k];
+        }
+        let end = (last + DIFF_CONTEXT + 1).min(ops.len());
+        let hunk = &ops[start..end];
+
+        let a_len = hunk.iter().filter(|(op, _, _)| *op != Op::Insert).count();
+        let b_len = hunk.iter().filter(|(op, _, _)| *op != Op::Delete).count();
+        let (a_start, b_start) = (hunk[0].1, hunk[0].2);
+        // An empty side is numbered by the line before it
+        let line = |start: usize, len: usize| if len == 0 { start } else { start + 1 };
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            line(a_start, a_len),
+            a_len,
+            line(b_start, b_len),
+            b_len
+        ));
+        for &(op, i, j) in hunk {
+            match op {
+                Op::Equal => out.push_str(&format!(" {}\n", a[i])),
+                Op::Delete => out.push_str(&format!("-{}\n", a[i])),
+                Op::Insert => out.push_str(&format!("+{}\n", b[j])),
+            }
+        }
+        k += 1;
+    }
+    out
+}
+
+/// Edit script as `(op, line in a, line in b)`; `None` when the inputs are too large
+fn diff_ops(a: &[&str], b: &[&str]) -> Option<Vec<(Op, usize, usize)>> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let (n, m) = (mid_a.len(), mid_b.len());
+    if (n + 1).saturating_mul(m + 1) > MAX_DIFF_CELLS {
+        return None;
+    }
+
+    // lcs[i * (m + 1) + j]: longest common subsequence of mid_a[i..] and mid_b[j..]
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i * (m + 1) + j] = if mid_a[i] == mid_b[j] {
+                lcs[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<(Op, usize, usize)> = (0..prefix).map(|i| (Op::Equal, i, i)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && mid_a[i] == mid_b[j] {
+            ops.push((Op::Equal, prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1]) {
+            ops.push((Op::Delete, prefix + i, prefix + j));
+            i += 1;
+        } else {
+            ops.push((Op::Insert, prefix + i, prefix + j));
+            j += 1;
+        }
+    }
+    ops.extend((0..suffix).map(|s| (Op::Equal, prefix + n + s, prefix + m + s)));
+    Some(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let before = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let after = "a\nb\nc\nD\ne\nf\ng\nh\ni\nj\nk\n";
+        assert_eq!(
+            unified_diff("/w/x", before, after),
+            "--- /w/x\n+++ /w/x\n\
+             @@ -1,7 +1,7 @@\n a\n b\n c\n-d\n+D\n e\n f\n g\n\
+             @@ -8,3 +8,4 @@\n h\n i\n j\n+k\n"
+        );
+        assert_eq!(unified_diff("/w/x", before, before), "");
+        assert_eq!(unified_diff("/w/new", "", "x\n"), "--- /w/new\n+++ /w/new\n@@ -0,0 +1,1 @@\n+x\n");
+    }
+
+    #[test]
+    fn test_plans_leave_files_alone() {
+        let dir = std::env::temp_dir().join(format!("mcp-dry-run-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(dir.join("sub/b.txt"), "bb").unwrap();
+        let sandbox = Sandbox::new(&[dir.to_string_lossy().to_string()]);
+        let file = dir.join("a.txt").to_string_lossy().to_string();
+
+        let plan = plan_write(&sandbox, &json!({"path": file, "content": "one\n2\n"})).unwrap();
+        assert_eq!(plan["action"], "overwrite");
+        assert!(plan["diff"].as_str().unwrap().contains("-two\n+2\n"));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "one\ntwo\n");
+
+        let root = dir.to_string_lossy().to_string();
+        assert!(plan_delete(&sandbox, &json!({"path": root})).is_err());
+        let plan = plan_write(&sandbox, &json!({"path": file, "content": "three\n", "mode": "append"})).unwrap();
+        assert_eq!(plan["action"], "append");
+        assert_eq!(plan["bytes_after"], 14);
+        assert!(plan_write(&sandbox, &json!({"path": file, "content": "", "mode": "create"})).is_err());
+
+        assert!(plan_delete(&sandbox, &json!({"path": root, "recursive": true})).is_err());
+        let sub = dir.join("sub").to_string_lossy().to_string();
+        let plan = plan_delete(&sandbox, &json!({"path": sub, "recursive": true})).unwrap();
+        assert_eq!(plan["file_count"], 1);
+        assert_eq!(plan["bytes"], 2);
+        assert_eq!(plan["directories"].as_array().unwrap().len(), 1);
+        assert!(dir.join("sub/b.txt").exists());
+
+        assert!(plan_write(&sandbox, &json!({"path": "/etc/passwd", "content": ""})).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,470 @@
+use anyhow::{bail, Context, Result};
+use ipnet::IpNet;
+use reqwest::{Method, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+/// Loopback, private, link-local, shared, multicast and other reserved ranges
+const NON_PUBLIC: &[&str] = &[
+    "0.0.0.0/8",
+    "10.0.0.0/8",
+    "100.64.0.0/10",
+    "127.0.0.0/8",
+    "169.254.0.0/16",
+    "172.16.0.0/12",
+    "192.0.0.0/24",
+    "192.168.0.0/16",
+    "198.18.0.0/15",
+    "224.0.0.0/4",
+    "240.0.0.0/4",
+    "::/128",
+    "::1/128",
+    "64:ff9b::/96",
+    "fc00::/7",
+    "fe80::/10",
+    "ff00::/8",
+];
+
+/// Request headers that are not forwarded when a redirect leaves the host
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "cookie", "proxy-authorization"];
+
+/// Outbound network policy for `http.request` and `fetch.url`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EgressPolicy {
+    #[serde(flatten)]
+    pub rules: EgressRules,
+    /// Settings replaced for a `tenant_id`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tenants: HashMap<String, EgressOverride>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EgressRules {
+    /// URL schemes tools may request
+    pub schemes: Vec<String>,
+    /// Host patterns (`api.example.com`, `*.example.com`); when set, other hosts are refused
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allow_hosts: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deny_hosts: Vec<String>,
+    /// Ranges reachable even though `block_private` would refuse them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allow_cidrs: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deny_cidrs: Vec<String>,
+    /// Refuse hosts resolving to loopback, private, link-local or reserved addresses
+    pub block_private: bool,
+    pub max_response_bytes: u64,
+    pub max_redirects: usize,
+}
+
+/// Per-tenant replacements; unset fields keep the global value
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EgressOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schemes: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_hosts: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deny_hosts: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_cidrs: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deny_cidrs: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_private: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_response_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<usize>,
+}
+
+/// Response read within the policy limits
+#[derive(Debug)]
+pub struct EgressResponse {
+    pub status: u16,
+    /// URL after redirects
+    pub url: Url,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+    pub redirects: usize,
+}
+
+impl Default for EgressRules {
+    fn default() -> Self {
+        Self {
+            schemes: vec!["http".to_string(), "https".to_string()],
+            allow_hosts: Vec::new(),
+            deny_hosts: Vec::new(),
+            allow_cidrs: Vec::new(),
+            deny_cidrs: Vec::new(),
+            block_private: true,
+            max_response_bytes: 10 * 1024 * 1024,
+            max_redirects: 5,
+        }
+    }
+}
+
+impl EgressPolicy {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Rules in effect for a tenant
+    pub fn for_tenant(&self, tenant_id: &str) -> EgressRules {
+        let mut rules = self.rules.clone();
+        let Some(o) = self.tenants.get(tenant_id) else {
+            return rules;
+        };
+        if let Some(v) = &o.schemes {
+            rules.schemes = v.clone();
+        }
+        if let Some(v) = &o.allow_hosts {
+            rules.allow_hosts = v.clone();
+        }
+        if let Some(v) = &o.deny_hosts {
+            rules.deny_hosts = v.clone();
+        }
+        if let Some(v) = &o.allow_cidrs {
+            rules.allow_cidrs = v.clone();
+        }
+        if let Some(v) = &o.deny_cidrs {
+            rules.deny_cidrs = v.clone();
+        }
+        rules.block_private = o.block_private.unwrap_or(rules.block_private);
+        rules.max_response_bytes = o.max_response_bytes.unwrap_or(rules.max_response_bytes);
+        rules.max_redirects = o.max_redirects.unwrap_or(rules.max_redirects);
+        rules
+    }
+
+    /// Invalid schemes, host patterns and CIDRs, globally and per tenant
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors: Vec<String> = self.rules.validate().into_iter().map(|e| format!("egress: {}", e)).collect();
+        for tenant in self.tenants.keys() {
+            for e in self.for_tenant(tenant).validate() {
+                errors.push(format!("egress tenant '{}': {}", tenant, e));
+            }
+        }
+        errors
+    }
+}
+
+impl EgressRules {
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for scheme in self.schemes.iter().filter(|s| !matches!(s.as_str(), "http" | "https")) {
+            errors.push(format!("unsupported scheme '{}'", scheme));
+        }
+        for host in self.allow_hosts.iter().chain(&self.deny_hosts) {
+            if !is_valid_host_pattern(host) {
+                errors.push(format!("invalid host pattern '{}'", host));
+            }
+        }
+        for cidr in self.allow_cidrs.iter().chain(&self.deny_cidrs) {
+            if parse_net(cidr).is_none() {
+                errors.push(format!("invalid CIDR '{}'", cidr));
+            }
+        }
+        errors
+    }
+
+    /// Scheme and host checks, before resolution
+    pub fn check_url(&self, url: &Url) -> Result<()> {
+        if !self.schemes.iter().any(|s| s.eq_ignore_ascii_case(url.scheme())) {
+            bail!("egress denied: scheme '{}' is not allowed", url.scheme());
+        }
+        let host = host_of(url)?;
+        if let Some(pattern) = self.deny_hosts.iter().find(|p| host_matches(p, &host)) {
+            bail!("egress denied: host {} matches deny_hosts '{}'", host, pattern);
+        }
+        if !self.allow_hosts.is_empty() && !self.allow_hosts.iter().any(|p| host_matches(p, &host)) {
+            bail!("egress denied: host {} is not in allow_hosts", host);
+        }
+        Ok(())
+    }
+
+    /// Address check, after resolution
+    pub fn check_addr(&self, ip: IpAddr) -> Result<()> {
+        let ip = ip.to_canonical();
+        if let Some(net) = self.deny_cidrs.iter().filter_map(|c| parse_net(c)).find(|n| n.contains(&ip)) {
+            bail!("egress denied: {} is in deny_cidrs {}", ip, net);
+        }
+        if self.allow_cidrs.iter().filter_map(|c| parse_net(c)).any(|n| n.contains(&ip)) {
+            return Ok(());
+        }
+        if self.block_private && is_non_public(ip) {
+            bail!("egress denied: {} is a private or reserved address", ip);
+        }
+        Ok(())
+    }
+
+    /// Resolve the URL's host and check every address it resolves to
+    pub async fn resolve(&self, url: &Url) -> Result<Vec<SocketAddr>> {
+        self.check_url(url)?;
+        let host = host_of(url)?;
+        let port = url.port_or_known_default().context("URL has no port")?;
+        let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host.as_str(), port))
+                .await
+                .with_context(|| format!("cannot resolve {}", host))?
+                .collect(),
+        };
+        if addrs.is_empty() {
+            bail!("cannot resolve {}", host);
+        }
+        for addr in &addrs {
+            self.check_addr(addr.ip())?;
+        }
+        Ok(addrs)
+    }
+
+    /// Send a request under this policy. Redirects are followed here rather than
+    /// by the client so every hop is checked, and each connection is pinned to
+    /// the addresses that were checked so a second DNS answer cannot differ.
+    /// `secret_headers` name headers carrying expanded secrets; like credential
+    /// headers, they are dropped when a redirect leaves the host.
+    pub async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(String, String)],
+        secret_headers: &[String],
+        body: Option<&serde_json::Value>,
+    ) -> Result<EgressResponse> {
+        let mut url = Url::parse(url).with_context(|| format!("invalid URL '{}'", url))?;
+        let mut method = method;
+        let mut body = body;
+        let mut headers = headers.to_vec();
+        let mut redirects = 0;
+        loop {
+            let addrs = self.resolve(&url).await?;
+            let client = reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .no_proxy()
+                .resolve_to_addrs(&host_of(&url)?, &addrs)
+                .build()?;
+            let mut request = client.request(method.clone(), url.clone());
+            for (key, value) in &headers {
+                request = request.header(key, value);
+            }
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+            let mut response = request.send().await?;
+
+            let status = response.status();
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok());
+            if let (true, Some(location)) = (status.is_redirection(), location) {
+                if redirects >= self.max_redirects {
+                    bail!("egress denied: more than {} redirects", self.max_redirects);
+                }
+                let next = url.join(location).with_context(|| format!("invalid redirect '{}'", location))?;
+                if next.host_str() != url.host_str() {
+                    strip_credentials(&mut headers, secret_headers);
+                }
+                if status == StatusCode::SEE_OTHER
+                    || (matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND) && method == Method::POST)
+                {
+                    method = Method::GET;
+                    body = None;
+                }
+                url = next;
+                redirects += 1;
+                continue;
+            }
+
+            if response.content_length().is_some_and(|len| len > self.max_response_bytes) {
+                bail!("egress denied: response exceeds {} bytes", self.max_response_bytes);
+            }
+            let response_headers = response
+                .headers()
+                .iter()
+                .filter_map(|(k, v)| v.to_str().ok().map(|val| (k.to_string(), val.to_string())))
+                .collect();
+            let mut data = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                if (data.len() + chunk.len()) as u64 > self.max_response_bytes {
+                    bail!("egress denied: response exceeds {} bytes", self.max_response_bytes);
+                }
+                data.extend_from_slice(&chunk);
+            }
+            return Ok(EgressResponse {
+                status: status.as_u16(),
+                url,
+                headers: response_headers,
+                body: data,
+                redirects,
+            });
+        }
+    }
+}
+
+/// Drop credential headers and those in `secret_headers` before a cross-host hop
+fn strip_credentials(headers: &mut Vec<(String, String)>, secret_headers: &[String]) {
+    headers.retain(|(key, _)| {
+        !CREDENTIAL_HEADERS.contains(&key.to_ascii_lowercase().as_str())
+            && !secret_headers.iter().any(|h| h.eq_ignore_ascii_case(key))
+    });
+}
+
+/// `host` or `*.domain` (the domain itself and any subdomain), case-insensitive
+pub fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let host = host.to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host == domain || host.ends_with(&format!(".{}", domain)),
+        None => host == pattern,
+    }
+}
+
+/// Wildcards are only allowed as a leading `*.`
+pub fn is_valid_host_pattern(pattern: &str) -> bool {
+    !pattern.is_empty() && pattern.strip_prefix("*.").unwrap_or(pattern).chars().all(|c| c != '*')
+}
+
+fn host_of(url: &Url) -> Result<String> {
+    let host = url.host_str().context("egress denied: URL has no host")?;
+    Ok(host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase())
+}
+
+fn parse_net(cidr: &str) -> Option<IpNet> {
+    cidr.parse::<IpNet>()
+        .or_else(|_| cidr.parse::<IpAddr>().map(IpNet::from))
+        .ok()
+}
+
+fn is_non_public(ip: IpAddr) -> bool {
+    NON_PUBLIC.iter().filter_map(|c| parse_net(c)).any(|n| n.contains(&ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_url_and_address_checks() {
+        let rules = EgressRules {
+            deny_hosts: vec!["*.internal".to_string()],
+            allow_cidrs: vec!["10.1.0.0/16".to_string()],
+            deny_cidrs: vec!["203.0.113.7".to_string()],
+            ..EgressRules::default()
+        };
+        let url = |s: &str| Url::parse(s).unwrap();
+        assert!(rules.check_url(&url("https://example.com/")).is_ok());
+        assert!(rules.check_url(&url("ftp://example.com/")).is_err());
+        assert!(rules.check_url(&url("http://db.internal/")).is_err());
+
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert!(rules.check_addr(ip("93.184.216.34")).is_ok());
+        for blocked in ["127.0.0.1", "169.254.169.254", "192.168.1.1", "::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(rules.check_addr(ip(blocked)).is_err(), "{} should be blocked", blocked);
+        }
+        assert!(rules.check_addr(ip("10.1.2.3")).is_ok());
+        assert!(rules.check_addr(ip("10.2.0.1")).is_err());
+        assert!(rules.check_addr(ip("203.0.113.7")).is_err());
+
+        let only = EgressRules {
+            allow_hosts: vec!["*.example.com".to_string()],
+            ..EgressRules::default()
+        };
+        assert!(only.check_url(&url("https://api.example.com/")).is_ok());
+        assert!(only.check_url(&url("https://example.org/")).is_err());
+    }
+
+    #[test]
+    fn test_tenant_overrides_and_validation() {
+        let policy: EgressPolicy = serde_json::from_value(serde_json::json!({
+            "allow_hosts": ["*.example.com"],
+            "tenants": {"lab": {"block_private": false, "max_redirects": 0, "allow_hosts": []}}
+        }))
+        .unwrap();
+        assert!(policy.for_tenant("default").block_private);
+        let lab = policy.for_tenant("lab");
+        assert!(!lab.block_private && lab.allow_hosts.is_empty());
+        assert_eq!(lab.max_redirects, 0);
+        assert_eq!(lab.max_response_bytes, EgressRules::default().max_response_bytes);
+        assert!(policy.validate().is_empty());
+        assert!(EgressPolicy::default().is_default());
+
+        let bad: EgressPolicy = serde_json::from_value(serde_json::json!({
+            "schemes": ["file"], "deny_cidrs": ["10.0.0.0/33"],
+            "tenants": {"t": {"allow_hosts": ["a.*.com"]}}
+        }))
+        .unwrap();
+        let errors = bad.validate();
+        assert!(errors.contains(&"egress: unsupported scheme 'file'".to_string()));
+        assert!(errors.contains(&"egress: invalid CIDR '10.0.0.0/33'".to_string()));
+        assert!(errors.contains(&"egress tenant 't': invalid host pattern 'a.*.com'".to_string()));
+    }
+
+    /// Serves each canned response to one connection, in order
+    async fn serve(responses: Vec<String>) -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_send_limits() {
+        let ok = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello".to_string();
+
+        // Loopback is refused unless allowed explicitly
+        let port = serve(vec![ok.clone()]).await;
+        let url = format!("http://127.0.0.1:{}/", port);
+        let err = EgressRules::default().send(Method::GET, &url, &[], &[], None).await.unwrap_err();
+        assert!(err.to_string().contains("private"));
+
+        let local = EgressRules {
+            allow_cidrs: vec!["127.0.0.0/8".to_string()],
+            max_redirects: 1,
+            ..EgressRules::default()
+        };
+        let redirect = |to: &str| {
+            format!("HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", to)
+        };
+        let port = serve(vec![redirect("/next"), ok.clone()]).await;
+        let response = local.send(Method::GET, &format!("http://127.0.0.1:{}/", port), &[], &[], None).await.unwrap();
+        assert_eq!((response.status, response.redirects), (200, 1));
+        assert_eq!(response.body, b"hello");
+        assert_eq!(response.url.path(), "/next");
+
+        // Redirect limit, and redirects into blocked ranges
+        let port = serve(vec![redirect("/a"), redirect("/b")]).await;
+        let err = local.send(Method::GET, &format!("http://127.0.0.1:{}/", port), &[], &[], None).await.unwrap_err();
+        assert!(err.to_string().contains("redirects"));
+        let port = serve(vec![redirect("http://169.254.169.254/latest/meta-data")]).await;
+        let err = local.send(Method::GET, &format!("http://127.0.0.1:{}/", port), &[], &[], None).await.unwrap_err();
+        assert!(err.to_string().contains("169.254.169.254"));
+
+        let mut headers = vec![
+            ("Authorization".to_string(), "Bearer t".to_string()),
+            ("X-Api-Key".to_string(), "k".to_string()),
+            ("Accept".to_string(), "*/*".to_string()),
+        ];
+        strip_credentials(&mut headers, &["x-api-key".to_string()]);
+        assert_eq!(headers, [("Accept".to_string(), "*/*".to_string())]);
+
+        let small = EgressRules {
+            max_response_bytes: 4,
+            ..local
+        };
+        let port = serve(vec![ok]).await;
+        let err = small.send(Method::GET, &format!("http://127.0.0.1:{}/", port), &[], &[], None).await.unwrap_err();
+        assert!(err.to_string().contains("exceeds 4 bytes"));
+    }
+}
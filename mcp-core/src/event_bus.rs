@@ -1,3 +1,4 @@
+use crate::observability::Metrics;
 use crate::types::{ContextFrame, EventMetadata, EventResponse};
 use async_trait::async_trait;
 use std::collections::HashMap;
@@ -9,14 +10,17 @@ use tokio::sync::mpsc::{channel, Sender};
 const QUEUE_CAPACITY: usize = 4096;
 const BATCH_SIZE: usize = 64;
 const WATERMARK_THRESHOLD: f64 = 0.75;
+/// Events kept in memory; the oldest are dropped, with their correlation ids, beyond this
+const MAX_STORED_EVENTS: usize = 10_000;
 
-/// Event Bus: Context-aware, idempotent event routing with rollback safety and performance optimization
+/// Event Bus: Context-aware, idempotent event routing with rollback safety and performance optimization.
+/// Streams and correlation ids are scoped to the event's `tenant_id`.
 #[async_trait]
 pub trait EventBus: Send + Sync {
     async fn publish(&self, event: Event) -> anyhow::Result<EventResponse>;
     async fn publish_batch(&self, events: Vec<Event>) -> anyhow::Result<Vec<EventResponse>>;
     async fn subscribe(&self, event_type: &str, handler: EventHandler) -> anyhow::Result<()>;
-    async fn check_duplicate(&self, correlation_id: &str) -> anyhow::Result<Option<String>>;
+    async fn check_duplicate(&self, tenant_id: &str, correlation_id: &str) -> anyhow::Result<Option<String>>;
     fn queue_depth(&self) -> usize;
 }
 
@@ -35,9 +39,11 @@ pub type EventHandler = Arc<dyn Fn(Event) -> anyhow::Result<()> + Send + Sync>;
 pub struct InMemoryEventBus {
     events: Arc<RwLock<Vec<StoredEvent>>>,
     handlers: Arc<RwLock<HashMap<String, Vec<EventHandler>>>>,
-    seen_correlations: Arc<RwLock<HashMap<String, String>>>,
+    /// Event id by `(tenant_id, correlation_id)`
+    seen_correlations: Arc<RwLock<HashMap<(String, String), String>>>,
     queue_tx: Option<Sender<Event>>,
     pending_batch: Arc<RwLock<Vec<Event>>>,
+    metrics: Arc<Metrics>,
 }
 
 #[derive(Debug, Clone)]
@@ -51,11 +57,16 @@ struct StoredEvent {
     data: serde_json::Value,
     #[allow(dead_code)]
     metadata: EventMetadata,
-    #[allow(dead_code)]
     context: ContextFrame,
     timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+impl Default for InMemoryEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl InMemoryEventBus {
     pub fn new() -> Self {
         Self {
@@ -64,6 +75,7 @@ impl InMemoryEventBus {
             seen_correlations: Arc::new(RwLock::new(HashMap::new())),
             queue_tx: None,
             pending_batch: Arc::new(RwLock::new(Vec::with_capacity(BATCH_SIZE))),
+            metrics: Arc::new(Metrics::default()),
         }
     }
 
@@ -76,9 +88,16 @@ impl InMemoryEventBus {
             seen_correlations: Arc::new(RwLock::new(HashMap::new())),
             queue_tx: Some(tx),
             pending_batch: Arc::new(RwLock::new(Vec::with_capacity(BATCH_SIZE))),
+            metrics: Arc::new(Metrics::default()),
         }
     }
 
+    /// Report publish and duplicate counters into a shared metrics registry
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
     /// Check queue watermark for backpressure
     fn check_watermark(&self) -> bool {
         if let Some(tx) = &self.queue_tx {
@@ -166,18 +185,23 @@ impl EventBus for InMemoryEventBus {
         Ok(())
     }
 
-    async fn check_duplicate(&self, correlation_id: &str) -> anyhow::Result<Option<String>> {
+    async fn check_duplicate(&self, tenant_id: &str, correlation_id: &str) -> anyhow::Result<Option<String>> {
         let correlations = self.seen_correlations.read().await;
-        Ok(correlations.get(correlation_id).cloned())
+        Ok(correlations
+            .get(&(tenant_id.to_string(), correlation_id.to_string()))
+            .cloned())
     }
 }
 
 impl InMemoryEventBus {
     /// Internal publish method for actual event storage
     async fn publish_internal(&self, event: Event) -> anyhow::Result<EventResponse> {
-        // Check for duplicate
-        if let Some(existing_id) = self.check_duplicate(&event.metadata.correlation_id).await? {
+        let tenant_id = event.context.tenant_id.clone();
+
+        // Check for duplicate within the tenant
+        if let Some(existing_id) = self.check_duplicate(&tenant_id, &event.metadata.correlation_id).await? {
             tracing::debug!("Duplicate event detected: {}", event.metadata.correlation_id);
+            self.metrics.event_duplicate(&event.event_type, &tenant_id);
             // Return existing event ID (idempotency)
             let events = self.events.read().await;
             if let Some(stored) = events.iter().find(|e| e.id == existing_id) {
@@ -200,7 +224,7 @@ impl InMemoryEventBus {
         let mut events = self.events.write().await;
         let version = events
             .iter()
-            .filter(|e| e.stream_id == event.stream_id)
+            .filter(|e| e.stream_id == event.stream_id && e.context.tenant_id == tenant_id)
             .count() as u64 + 1;
 
         let stored = StoredEvent {
@@ -215,10 +239,17 @@ impl InMemoryEventBus {
         };
 
         events.push(stored);
+        self.metrics.event_published(&event.event_type, &tenant_id);
 
-        // Record correlation ID
+        // Record correlation ID, forgetting those of events past the retention limit
         let mut correlations = self.seen_correlations.write().await;
-        correlations.insert(event.metadata.correlation_id.clone(), event_id.clone());
+        if events.len() > MAX_STORED_EVENTS {
+            let excess = events.len() - MAX_STORED_EVENTS;
+            let dropped: std::collections::HashSet<String> = events.drain(..excess).map(|e| e.id).collect();
+            correlations.retain(|_, id| !dropped.contains(id));
+        }
+        drop(events);
+        correlations.insert((tenant_id, event.metadata.correlation_id.clone()), event_id.clone());
 
         // Trigger handlers
         let handlers = self.handlers.read().await;
@@ -288,4 +319,31 @@ mod tests {
         
         assert_eq!(response1.event_id, response2.event_id);
     }
+
+    #[tokio::test]
+    async fn test_tenant_partitioning() {
+        let bus = InMemoryEventBus::new();
+        let event = |tenant: &str| Event {
+            stream_id: "orders".to_string(),
+            event_type: "order.created".to_string(),
+            data: serde_json::json!({}),
+            metadata: EventMetadata {
+                correlation_id: "shared-001".to_string(),
+                causation_id: None,
+                user_id: None,
+            },
+            context: ContextFrame {
+                tenant_id: tenant.to_string(),
+                ..ContextFrame::default()
+            },
+        };
+
+        let acme = bus.publish(event("acme")).await.unwrap();
+        let globex = bus.publish(event("globex")).await.unwrap();
+        // Same correlation id and stream in another tenant is a new event at version 1
+        assert_ne!(acme.event_id, globex.event_id);
+        assert_eq!(globex.version, 1);
+        assert!(bus.check_duplicate("acme", "shared-001").await.unwrap().is_some());
+        assert!(bus.check_duplicate("initech", "shared-001").await.unwrap().is_none());
+    }
 }
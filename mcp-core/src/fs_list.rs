@@ -0,0 +1,271 @@
+use crate::fs_tools::{bool_field, rfc3339};
+use crate::fs_walk::{glob_depth, glob_pattern, matches, split_wildcard, Walk, WalkEntry};
+use crate::security::{FsMode, Sandbox};
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::ops::ControlFlow;
+
+/// Entries per page unless `limit` says otherwise
+const DEFAULT_LIMIT: u64 = 500;
+/// Upper bound on `limit`
+const MAX_LIMIT: u64 = 5000;
+/// Entries one listing looks at before it stops and sets `scan_capped`
+const MAX_SCAN: usize = 100_000;
+
+#[derive(Clone, Copy, PartialEq)]
+enum SortBy {
+    Name,
+    Modified,
+    Size,
+}
+
+impl SortBy {
+    fn parse(value: Option<&str>) -> Result<Self> {
+        match value.unwrap_or("name") {
+            "name" => Ok(SortBy::Name),
+            "modified" | "mtime" => Ok(SortBy::Modified),
+            "size" => Ok(SortBy::Size),
+            other => bail!("unknown sort '{}' (expected name, modified or size)", other),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortBy::Name => "name",
+            SortBy::Modified => "modified",
+            SortBy::Size => "size",
+        }
+    }
+
+    fn key(&self, entry: &WalkEntry) -> u64 {
+        match self {
+            SortBy::Name => 0,
+            SortBy::Modified => entry
+                .metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_millis() as u64),
+            SortBy::Size => entry.metadata.len(),
+        }
+    }
+}
+
+/// Where a page ends: the sort key and relative path of its last entry.
+/// Later pages start after it even if entries were added or removed meanwhile.
+/// Kept readable (`size:desc:42:src/lib.rs`) so output redaction leaves it alone.
+struct Cursor {
+    key: u64,
+    path: String,
+}
+
+impl Cursor {
+    fn encode(&self, sort: SortBy, descending: bool) -> String {
+        let order = if descending { "desc" } else { "asc" };
+        format!("{}:{}:{}:{}", sort.as_str(), order, self.key, self.path)
+    }
+
+    fn decode(cursor: &str, sort: SortBy, descending: bool) -> Result<Self> {
+        let mut parts = cursor.splitn(4, ':');
+        let (Some(sort_by), Some(order), Some(key), Some(path)) = (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            bail!("invalid cursor '{}'", cursor);
+        };
+        if sort_by != sort.as_str() || (order == "desc") != descending {
+            bail!("cursor belongs to a listing with a different sort order");
+        }
+        Ok(Self {
+            key: key.parse().with_context(|| format!("invalid cursor '{}'", cursor))?,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// `fs.list`: a directory, its subtree to `max_depth`, or the paths matching a
+/// wildcard `path`, sorted and paged
+pub fn list(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let path = input.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+    let (base, path_glob) = match split_wildcard(path) {
+        Some((base, glob)) => (base, Some(glob)),
+        None => (path.to_string(), None),
+    };
+    let resolved = sandbox.check(&base, FsMode::Read)?;
+    if !resolved.is_dir() {
+        bail!("'{}' is not a directory", base);
+    }
+
+    let recursive = bool_field(input, "recursive", false);
+    let depth_limit = match input.get("max_depth").and_then(|v| v.as_u64()) {
+        Some(depth) => depth.max(1) as usize,
+        None if recursive => usize::MAX,
+        None => 1,
+    };
+    let max_depth = match &path_glob {
+        Some(glob) if input.get("max_depth").is_some() => glob_depth(glob).min(depth_limit),
+        Some(glob) => glob_depth(glob),
+        None => depth_limit,
+    };
+    let path_pattern = path_glob
+        .as_deref()
+        .map(glob::Pattern::new)
+        .transpose()
+        .with_context(|| format!("invalid wildcard '{}'", path))?;
+    let pattern = input.get("pattern").and_then(|v| v.as_str()).map(glob_pattern).transpose()?;
+    let exclude: Vec<String> = match input.get("exclude") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(value) => serde_json::from_value(value.clone()).context("fs.list 'exclude' must be a list of globs")?,
+    };
+    let sort = SortBy::parse(input.get("sort").and_then(|v| v.as_str()))?;
+    let descending = match input.get("order").and_then(|v| v.as_str()).unwrap_or("asc") {
+        "asc" => false,
+        "desc" => true,
+        other => bail!("unknown order '{}' (expected asc or desc)", other),
+    };
+    let limit = input.get("limit").and_then(|v| v.as_u64()).unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+    let after = match input.get("cursor").and_then(|v| v.as_str()) {
+        Some(cursor) => Some(Cursor::decode(cursor, sort, descending)?),
+        None => None,
+    };
+
+    // Subtrees honour .gitignore unless told otherwise; a single level shows everything
+    let gitignore = bool_field(input, "gitignore", max_depth > 1);
+    let walk = Walk::new(sandbox)
+        .with_max_depth(max_depth)
+        .with_gitignore(gitignore)
+        .with_globs(&[], &exclude)?;
+    let mut listed: Vec<(u64, WalkEntry)> = Vec::new();
+    let mut scanned = 0;
+    walk.run(&resolved, &mut |entry| {
+        scanned += 1;
+        if scanned > MAX_SCAN {
+            return ControlFlow::Break(());
+        }
+        let wanted = path_pattern.iter().chain(&pattern).all(|p| matches(p, &entry.relative));
+        if wanted {
+            listed.push((sort.key(&entry), entry));
+        }
+        ControlFlow::Continue(())
+    })?;
+    let scan_capped = scanned > MAX_SCAN;
+
+    listed.sort_by(|(a, x), (b, y)| (a, &x.relative).cmp(&(b, &y.relative)));
+    if descending {
+        listed.reverse();
+    }
+    let start = match &after {
+        Some(cursor) => listed.partition_point(|(key, entry)| {
+            let position = (key, &entry.relative).cmp(&(&cursor.key, &cursor.path));
+            if descending {
+                position.is_ge()
+            } else {
+                position.is_le()
+            }
+        }),
+        None => 0,
+    };
+    let page = &listed[start..(start + limit).min(listed.len())];
+    let next_cursor = (start + limit < listed.len()).then(|| {
+        let (key, entry) = &page[page.len() - 1];
+        Cursor { key: *key, path: entry.relative.clone() }.encode(sort, descending)
+    });
+
+    Ok(json!({
+        "path": path,
+        "resolved": resolved.to_string_lossy(),
+        "entries": page.iter().map(|(_, entry)| describe(entry)).collect::<Vec<_>>(),
+        "count": page.len(),
+        // Matching entries across all pages, as far as the scan got
+        "matched": listed.len(),
+        "next_cursor": next_cursor,
+        "truncated": next_cursor.is_some() || scan_capped,
+        // The walk stopped early, so later pages and sorting cover only part of the tree
+        "scan_capped": scan_capped,
+    }))
+}
+
+fn describe(entry: &WalkEntry) -> Value {
+    let metadata = &entry.metadata;
+    let kind = if metadata.file_type().is_symlink() {
+        "symlink"
+    } else if metadata.is_dir() {
+        "directory"
+    } else if metadata.is_file() {
+        "file"
+    } else {
+        "other"
+    };
+    let mut value = json!({
+        "name": entry.path.file_name().map(|n| n.to_string_lossy()),
+        "path": entry.path.to_string_lossy(),
+        "relative": entry.relative,
+        "type": kind,
+        "is_dir": metadata.is_dir(),
+        "size": metadata.len(),
+        "modified": rfc3339(metadata.modified()),
+        "depth": entry.depth,
+        "readonly": metadata.permissions().readonly(),
+    });
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        value["mode"] = json!(format!("{:04o}", metadata.permissions().mode() & 0o7777));
+    }
+    if kind == "symlink" {
+        value["target"] = json!(std::fs::read_link(&entry.path).ok().map(|t| t.to_string_lossy().to_string()));
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recursive_list_and_pages() {
+        let dir = std::env::temp_dir().join(format!("mcp-list-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src/net")).unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        for (file, size) in [("src/lib.rs", 3), ("src/net/mod.rs", 1), ("src/net/tcp.rs", 2), ("README.md", 4), ("target/x.rs", 0)] {
+            std::fs::write(dir.join(file), "x".repeat(size)).unwrap();
+        }
+        std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("README.md", dir.join("link")).unwrap();
+        let sandbox = Sandbox::new(&[dir.to_string_lossy().to_string()]);
+        let root = dir.to_string_lossy().to_string();
+        let names = |out: &Value| -> Vec<String> {
+            out["entries"].as_array().unwrap().iter().map(|e| e["relative"].as_str().unwrap().to_string()).collect()
+        };
+
+        let top = list(&sandbox, &json!({"path": root})).unwrap();
+        assert!(names(&top).contains(&"target".to_string()), "one level ignores .gitignore");
+
+        let rust = list(&sandbox, &json!({"path": format!("{}/**/*.rs", root)})).unwrap();
+        assert_eq!(names(&rust), ["src/lib.rs", "src/net/mod.rs", "src/net/tcp.rs"]);
+        let shallow = list(&sandbox, &json!({"path": root, "recursive": true, "max_depth": 2, "pattern": "*.rs"})).unwrap();
+        assert_eq!(names(&shallow), ["src/lib.rs"]);
+
+        // Largest first, two at a time
+        let mut seen = Vec::new();
+        let mut cursor = Value::Null;
+        loop {
+            let input = json!({"path": root, "recursive": true, "pattern": "*.rs", "sort": "size", "order": "desc", "limit": 2, "cursor": cursor});
+            let page = list(&sandbox, &input).unwrap();
+            seen.extend(names(&page));
+            cursor = page["next_cursor"].clone();
+            assert_eq!(page["truncated"], !cursor.is_null());
+            if cursor.is_null() {
+                break;
+            }
+        }
+        assert_eq!(seen, ["src/lib.rs", "src/net/tcp.rs", "src/net/mod.rs"]);
+
+        #[cfg(unix)]
+        {
+            let link = top["entries"].as_array().unwrap().iter().find(|e| e["name"] == "link").unwrap();
+            assert_eq!((link["type"].as_str(), link["target"].as_str()), (Some("symlink"), Some("README.md")));
+        }
+        assert!(list(&sandbox, &json!({"path": root, "cursor": "bogus"})).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,321 @@
+use crate::fs_tools::str_field;
+use crate::security::{expand_wildcard_path, OpenBeneath, Sandbox};
+use anyhow::{bail, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes returned by one read unless `max_bytes` says otherwise
+const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+/// Per-file default of a wildcard read
+const DEFAULT_WILDCARD_MAX_BYTES: u64 = 256 * 1024;
+/// Default `max_total_bytes` of a wildcard read
+const DEFAULT_MAX_TOTAL_BYTES: u64 = 4 * 1024 * 1024;
+/// Upper bound on `max_bytes` and `max_total_bytes`
+const MAX_BYTES: u64 = 16 * 1024 * 1024;
+/// Files read at most by one wildcard
+const MAX_WILDCARD_FILES: usize = 200;
+/// Bytes looked at to detect the MIME type
+const SNIFF_BYTES: usize = 512;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Encoding {
+    /// UTF-8 if the bytes are text, base64 otherwise
+    Auto,
+    /// UTF-8 with invalid sequences replaced
+    Utf8,
+    Base64,
+}
+
+#[derive(Clone, Copy)]
+enum Range {
+    Bytes { offset: u64, length: Option<u64> },
+    /// 1-based first line
+    Lines { start: u64, count: Option<u64> },
+}
+
+#[derive(Clone, Copy)]
+struct ReadOptions {
+    encoding: Encoding,
+    range: Range,
+    max_bytes: u64,
+}
+
+impl ReadOptions {
+    fn parse(input: &Value, default_max_bytes: u64) -> Result<Self> {
+        let number = |field: &str| input.get(field).and_then(|v| v.as_u64());
+        let encoding = match input.get("encoding").and_then(|v| v.as_str()).unwrap_or("auto") {
+            "auto" => Encoding::Auto,
+            "utf8" | "utf-8" => Encoding::Utf8,
+            "base64" => Encoding::Base64,
+            other => bail!("unknown encoding '{}' (expected auto, utf8 or base64)", other),
+        };
+        let bytes = number("offset").is_some() || number("length").is_some();
+        let lines = number("line_start").is_some() || number("line_count").is_some();
+        let range = match (bytes, lines) {
+            (true, true) => bail!("fs.read takes either 'offset'/'length' or 'line_start'/'line_count', not both"),
+            (_, true) => Range::Lines {
+                start: number("line_start").unwrap_or(1).max(1),
+                count: number("line_count"),
+            },
+            _ => Range::Bytes {
+                offset: number("offset").unwrap_or(0),
+                length: number("length"),
+            },
+        };
+        Ok(Self {
+            encoding,
+            range,
+            max_bytes: number("max_bytes").unwrap_or(default_max_bytes).clamp(1, MAX_BYTES),
+        })
+    }
+}
+
+/// `fs.read`: one file, or every file matching a wildcard `path`
+pub fn read(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let path = str_field(input, "fs.read", "path")?;
+    if path.contains('*') || path.contains('?') {
+        return read_many(sandbox, path, input);
+    }
+    read_file(sandbox, path, &ReadOptions::parse(input, DEFAULT_MAX_BYTES)?)
+}
+
+fn read_file(sandbox: &Sandbox, path: &str, options: &ReadOptions) -> Result<Value> {
+    // Opened beneath the allowlist root, so symlinks swapped in after the check are refused
+    let mut file = sandbox.open_beneath(path, &OpenBeneath::read())?;
+    let metadata = file.metadata()?;
+    if metadata.is_dir() {
+        bail!("'{}' is a directory", path);
+    }
+    let size = metadata.len();
+    let mut head = vec![0; SNIFF_BYTES];
+    let sniffed = file.read(&mut head)?;
+    head.truncate(sniffed);
+    file.seek(SeekFrom::Start(0))?;
+
+    let mut output = json!({ "path": path, "size": size, "mime": mime_type(Path::new(path), &head) });
+    let (mut bytes, offset, truncated) = match options.range {
+        Range::Bytes { offset, length } => {
+            file.seek(SeekFrom::Start(offset))?;
+            let wanted = length.unwrap_or(u64::MAX).min(size.saturating_sub(offset));
+            let mut bytes = Vec::new();
+            file.take(wanted.min(options.max_bytes)).read_to_end(&mut bytes)?;
+            (bytes, offset, wanted > options.max_bytes)
+        }
+        Range::Lines { start, count } => {
+            let (bytes, offset, lines, truncated) = read_lines(file, start, count, options.max_bytes)?;
+            output["line_start"] = json!(start);
+            output["line_count"] = json!(lines);
+            // A first line longer than `max_bytes` is paged by `next_offset` instead
+            if lines > 0 && offset + (bytes.len() as u64) < size {
+                output["next_line"] = json!(start + lines);
+            }
+            (bytes, offset, truncated)
+        }
+    };
+
+    // Never split a UTF-8 character at the end of a page; the next page starts with it
+    let mut text = std::str::from_utf8(&bytes);
+    if let Err(e) = text {
+        if options.encoding != Encoding::Base64 && e.error_len().is_none() && e.valid_up_to() > 0 {
+            bytes.truncate(e.valid_up_to());
+            text = std::str::from_utf8(&bytes);
+        }
+    }
+    let end = offset + bytes.len() as u64;
+    let next = (end < size).then_some(end);
+    let (encoding, mut content) = match (options.encoding, text) {
+        (Encoding::Auto, Ok(text)) if !text.contains('\0') => ("utf8", text.to_string()),
+        (Encoding::Utf8, _) => ("utf8", String::from_utf8_lossy(&bytes).into_owned()),
+        _ => ("base64", STANDARD.encode(&bytes)),
+    };
+    if truncated && encoding == "utf8" {
+        let resume = match output.get("next_line") {
+            Some(line) => format!("line_start {}", line),
+            None => format!("offset {}", end),
+        };
+        content.push_str(&format!(
+            "\n[truncated: {} of {} bytes shown; continue with {}]",
+            bytes.len(),
+            size,
+            resume
+        ));
+    }
+
+    output["encoding"] = json!(encoding);
+    output["content"] = json!(content);
+    output["bytes"] = json!(bytes.len());
+    output["offset"] = json!(offset);
+    output["next_offset"] = json!(next);
+    // `max_bytes` cut the requested range short
+    output["truncated"] = json!(truncated);
+    Ok(output)
+}
+
+/// Lines `start..start + count` (1-based) within `max_bytes`. Returns the bytes,
+/// their offset, the number of lines and whether `max_bytes` stopped the read.
+fn read_lines(file: std::fs::File, start: u64, count: Option<u64>, max_bytes: u64) -> Result<(Vec<u8>, u64, u64, bool)> {
+    let mut reader = BufReader::new(file);
+    let mut offset = 0;
+    // Skipped a buffer at a time, so a huge line is never held in memory
+    let mut line_number = 1;
+    while line_number < start {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        let used = match buf.iter().position(|&b| b == b'\n') {
+            Some(end) => {
+                line_number += 1;
+                end + 1
+            }
+            None => buf.len(),
+        };
+        reader.consume(used);
+        offset += used as u64;
+    }
+
+    let mut line = Vec::new();
+    let mut bytes = Vec::new();
+    let mut lines = 0;
+    while count.is_none_or(|count| lines < count) {
+        line.clear();
+        // One byte over the budget tells a line that fits from one that does not
+        let budget = max_bytes - bytes.len() as u64;
+        let read = (&mut reader).take(budget + 1).read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+        if read as u64 > budget {
+            // A first line longer than the budget is returned in part
+            if lines == 0 {
+                bytes.extend_from_slice(&line[..budget as usize]);
+            }
+            return Ok((bytes, offset, lines, true));
+        }
+        bytes.extend_from_slice(&line);
+        lines += 1;
+    }
+    Ok((bytes, offset, lines, false))
+}
+
+/// Read each file matching `pattern`, with `max_bytes` per file and `max_total_bytes` overall
+fn read_many(sandbox: &Sandbox, pattern: &str, input: &Value) -> Result<Value> {
+    let mut matched = expand_wildcard_path(pattern, sandbox)?;
+    matched.retain(|path| !path.is_dir());
+    let options = ReadOptions::parse(input, DEFAULT_WILDCARD_MAX_BYTES)?;
+    let max_total = input
+        .get("max_total_bytes")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_MAX_TOTAL_BYTES)
+        .clamp(1, MAX_BYTES);
+
+    let mut files = Vec::new();
+    let mut omitted = Vec::new();
+    let mut total = 0;
+    for path in &matched {
+        let path = path.to_string_lossy().to_string();
+        if files.len() == MAX_WILDCARD_FILES || total >= max_total {
+            omitted.push(path);
+            continue;
+        }
+        let name = Path::new(&path).file_name().map(|n| n.to_string_lossy().to_string());
+        let options = ReadOptions { max_bytes: options.max_bytes.min(max_total - total), ..options };
+        match read_file(sandbox, &path, &options) {
+            Ok(mut file) => {
+                total += file["bytes"].as_u64().unwrap_or(0);
+                file["name"] = json!(name);
+                files.push(file);
+            }
+            Err(e) => files.push(json!({ "path": path, "name": name, "error": format!("{:#}", e) })),
+        }
+    }
+
+    Ok(json!({
+        "pattern": pattern,
+        "matched_count": matched.len(),
+        "files": files,
+        "total_bytes": total,
+        // Matches left unread by the file or total size cap
+        "omitted": omitted,
+        "truncated": !omitted.is_empty() || files.iter().any(|f| f["truncated"] == true),
+    }))
+}
+
+/// From magic numbers, then the extension; text without a known type is `text/plain`
+fn mime_type(path: &Path, head: &[u8]) -> String {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\0asm", "application/wasm"),
+        (b"\x7fELF", "application/x-elf"),
+        (b"SQLite format 3\0", "application/vnd.sqlite3"),
+    ];
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return mime.to_string();
+    }
+    let text = !head.contains(&0)
+        && match std::str::from_utf8(head) {
+            Ok(_) => true,
+            Err(e) => e.error_len().is_none(),
+        };
+    let textual = |mime: &str| mime.starts_with("text/") || ["json", "xml", "javascript"].iter().any(|t| mime.ends_with(t));
+    match mime_guess::from_path(path).first_raw() {
+        // Extensions are ambiguous (`.ts` is also MPEG video), so text content wins over binary types
+        Some(mime) if !text || textual(mime) => mime.to_string(),
+        _ if text => "text/plain".to_string(),
+        _ => "application/octet-stream".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranged_and_binary_reads() {
+        let dir = std::env::temp_dir().join(format!("mcp-read-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(dir.join("app.log"), &log).unwrap();
+        std::fs::write(dir.join("pixel.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        std::fs::write(dir.join("euro.txt"), "€€").unwrap();
+        let sandbox = Sandbox::new(&[dir.to_string_lossy().to_string()]);
+        let file = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        let page = read(&sandbox, &json!({"path": file("app.log"), "line_start": 10, "line_count": 2})).unwrap();
+        assert_eq!(page["content"], "line 10\nline 11\n");
+        assert_eq!(page["next_line"], 12);
+        assert_eq!(page["mime"], "text/plain");
+        let page = read(&sandbox, &json!({"path": file("app.log"), "line_start": 99})).unwrap();
+        assert_eq!(page["content"], "line 99\nline 100\n");
+        assert!(page.get("next_line").is_none());
+
+        let page = read(&sandbox, &json!({"path": file("app.log"), "max_bytes": 14})).unwrap();
+        let marker = format!("line 1\nline 2\n\n[truncated: 14 of {} bytes shown; continue with offset 14]", log.len());
+        assert_eq!(page["content"], marker);
+        assert_eq!((page["truncated"].as_bool(), page["next_offset"].as_u64()), (Some(true), Some(14)));
+
+        let png = read(&sandbox, &json!({"path": file("pixel.png")})).unwrap();
+        assert_eq!((png["mime"].as_str(), png["encoding"].as_str()), (Some("image/png"), Some("base64")));
+        assert_eq!(STANDARD.decode(png["content"].as_str().unwrap()).unwrap(), std::fs::read(dir.join("pixel.png")).unwrap());
+
+        // A page boundary inside `€` (3 bytes) moves to the character start
+        let page = read(&sandbox, &json!({"path": file("euro.txt"), "offset": 0, "length": 4})).unwrap();
+        assert_eq!((page["content"].as_str(), page["next_offset"].as_u64()), (Some("€"), Some(3)));
+
+        let all = read(&sandbox, &json!({"path": format!("{}/*", dir.display()), "max_bytes": 8, "max_total_bytes": 20})).unwrap();
+        assert_eq!(all["matched_count"], 3);
+        assert!(all["total_bytes"].as_u64().unwrap() <= 20);
+        assert_eq!(all["truncated"], true);
+        assert!(read(&sandbox, &json!({"path": "/etc/passwd"})).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
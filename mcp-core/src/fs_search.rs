@@ -0,0 +1,245 @@
+use crate::fs_tools::{bool_field, str_field};
+use crate::fs_walk::Walk;
+use crate::security::{FsMode, OpenBeneath, Sandbox};
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+/// Upper bound on `max_results`
+const MAX_RESULTS: u64 = 1000;
+/// Upper bound on `max_matches_per_file`
+const MAX_PER_FILE: u64 = 1000;
+/// Upper bound on `max_file_size`, since matching files are read whole
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+/// Upper bound on `max_files`
+const MAX_FILES: u64 = 100_000;
+/// Upper bound on `context`
+const MAX_CONTEXT: u64 = 10;
+/// Characters kept of a matched or context line
+const MAX_LINE_CHARS: usize = 500;
+/// Bytes inspected for a NUL to tell binary files apart
+const BINARY_SNIFF: usize = 8192;
+
+/// Caps on one search, from the input with defaults
+struct Limits {
+    max_results: usize,
+    max_per_file: usize,
+    max_file_size: u64,
+    max_files: usize,
+    context: usize,
+}
+
+impl Limits {
+    fn from_input(input: &Value) -> Self {
+        let number = |field: &str, default: u64| input.get(field).and_then(|v| v.as_u64()).unwrap_or(default);
+        Self {
+            max_results: number("max_results", 100).clamp(1, MAX_RESULTS) as usize,
+            max_per_file: number("max_matches_per_file", 20).clamp(1, MAX_PER_FILE) as usize,
+            max_file_size: number("max_file_size", 1024 * 1024).min(MAX_FILE_SIZE),
+            max_files: number("max_files", 10_000).clamp(1, MAX_FILES) as usize,
+            context: number("context", 2).min(MAX_CONTEXT) as usize,
+        }
+    }
+}
+
+/// `fs.search`: lines matching a literal or regex pattern in files below `path`
+pub fn search(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let path = str_field(input, "fs.search", "path")?;
+    let pattern = str_field(input, "fs.search", "pattern")?;
+    if pattern.is_empty() {
+        bail!("fs.search 'pattern' must not be empty");
+    }
+    let source = if bool_field(input, "regex", false) {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    let regex = regex::RegexBuilder::new(&source)
+        .case_insensitive(!bool_field(input, "case_sensitive", true))
+        .size_limit(1 << 20)
+        .build()
+        .with_context(|| format!("invalid pattern '{}'", pattern))?;
+    let globs = |field: &str| -> Result<Vec<String>> {
+        match input.get(field) {
+            None | Some(Value::Null) => Ok(Vec::new()),
+            Some(value) => serde_json::from_value(value.clone())
+                .with_context(|| format!("fs.search '{}' must be a list of globs", field)),
+        }
+    };
+    let walk = Walk::new(sandbox)
+        .with_gitignore(bool_field(input, "gitignore", true))
+        .with_globs(&globs("include")?, &globs("exclude")?)?;
+
+    let resolved = sandbox.check(path, FsMode::Read)?;
+    let mut search = Search {
+        sandbox,
+        regex,
+        limits: Limits::from_input(input),
+        matches: Vec::new(),
+        files_searched: 0,
+        files_matched: 0,
+        too_large: 0,
+        binary: 0,
+        unreadable: 0,
+        truncated: false,
+    };
+    if resolved.is_file() {
+        let _ = search.file(&resolved);
+    } else {
+        walk.run(&resolved, &mut |entry| {
+            if entry.metadata.is_file() {
+                search.file(&entry.path)
+            } else {
+                ControlFlow::Continue(())
+            }
+        })?;
+    }
+
+    Ok(json!({
+        "pattern": pattern,
+        "path": resolved.to_string_lossy(),
+        "matches": search.matches,
+        "match_count": search.matches.len(),
+        "files_searched": search.files_searched,
+        "files_matched": search.files_matched,
+        "skipped": {
+            "too_large": search.too_large,
+            "binary": search.binary,
+            "unreadable": search.unreadable,
+        },
+        // A cap stopped the search; there may be more matches
+        "truncated": search.truncated,
+    }))
+}
+
+struct Search<'a> {
+    sandbox: &'a Sandbox,
+    regex: Regex,
+    limits: Limits,
+    matches: Vec<Value>,
+    files_searched: usize,
+    files_matched: usize,
+    too_large: usize,
+    binary: usize,
+    unreadable: usize,
+    truncated: bool,
+}
+
+impl Search<'_> {
+    fn file(&mut self, path: &Path) -> ControlFlow<()> {
+        if self.files_searched >= self.limits.max_files || self.matches.len() >= self.limits.max_results {
+            self.truncated = true;
+            return ControlFlow::Break(());
+        }
+        self.files_searched += 1;
+
+        // Opened beneath the allowlist root, so symlinks out of it are refused
+        let mut bytes = Vec::new();
+        let read = self
+            .sandbox
+            .open_beneath(&path.to_string_lossy(), &OpenBeneath::read())
+            .and_then(|file| Ok(file.take(self.limits.max_file_size + 1).read_to_end(&mut bytes)?));
+        if read.is_err() {
+            self.unreadable += 1;
+            return ControlFlow::Continue(());
+        }
+        if bytes.len() as u64 > self.limits.max_file_size {
+            self.too_large += 1;
+            return ControlFlow::Continue(());
+        }
+        if bytes[..bytes.len().min(BINARY_SNIFF)].contains(&0) {
+            self.binary += 1;
+            return ControlFlow::Continue(());
+        }
+
+        let text = String::from_utf8_lossy(&bytes);
+        let lines: Vec<&str> = text.lines().collect();
+        let context = self.limits.context;
+        let mut found = 0;
+        for (i, line) in lines.iter().enumerate() {
+            let Some(m) = self.regex.find(line) else { continue };
+            if found == self.limits.max_per_file || self.matches.len() == self.limits.max_results {
+                self.truncated = true;
+                break;
+            }
+            found += 1;
+            self.matches.push(json!({
+                "file": path.to_string_lossy(),
+                "line": i + 1,
+                "column": line[..m.start()].chars().count() + 1,
+                "text": clip(line),
+                "before": lines[i.saturating_sub(context)..i].iter().map(|l| clip(l)).collect::<Vec<_>>(),
+                "after": lines[i + 1..(i + 1 + context).min(lines.len())].iter().map(|l| clip(l)).collect::<Vec<_>>(),
+            }));
+        }
+        if found > 0 {
+            self.files_matched += 1;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+fn clip(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let dir = std::env::temp_dir().join(format!("mcp-search-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        std::fs::write(dir.join("src/main.rs"), "fn main() {\n    let todo = 1; // TODO: fix\n}\n").unwrap();
+        std::fs::write(dir.join("src/notes.txt"), "TODO one\nTODO two\nTODO three\n").unwrap();
+        std::fs::write(dir.join("target/out.rs"), "// TODO generated\n").unwrap();
+        std::fs::write(dir.join("blob.bin"), b"TODO\0\x01").unwrap();
+        std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+        let sandbox = Sandbox::new(&[dir.to_string_lossy().to_string()]);
+        let root = dir.to_string_lossy().to_string();
+
+        let out = search(&sandbox, &json!({"path": root, "pattern": "TODO", "include": ["*.rs"]})).unwrap();
+        assert_eq!(out["match_count"], 1);
+        let hit = &out["matches"][0];
+        assert_eq!((hit["line"].as_u64(), hit["column"].as_u64()), (Some(2), Some(22)));
+        assert_eq!(hit["before"], json!(["fn main() {"]));
+        assert_eq!(hit["after"], json!(["}"]));
+
+        let out = search(&sandbox, &json!({"path": root, "pattern": "todo", "case_sensitive": false})).unwrap();
+        assert_eq!(out["match_count"], 4, "target/ is gitignored");
+        assert_eq!(out["skipped"]["binary"], 1);
+
+        let out = search(&sandbox, &json!({"path": root, "pattern": "^TODO \\w+$", "regex": true, "max_results": 2})).unwrap();
+        assert_eq!(out["match_count"], 2);
+        assert_eq!(out["truncated"], true);
+
+        let out = search(&sandbox, &json!({"path": root, "pattern": "TODO", "gitignore": false, "exclude": ["src"]})).unwrap();
+        assert_eq!(out["files_matched"], 1);
+        assert!(search(&sandbox, &json!({"path": "/etc", "pattern": "root"})).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_limits_are_clamped() {
+        let limits = Limits::from_input(&json!({
+            "max_results": u64::MAX,
+            "max_matches_per_file": u64::MAX,
+            "max_file_size": u64::MAX,
+            "max_files": u64::MAX,
+            "context": u64::MAX,
+        }));
+        assert_eq!(limits.max_results, MAX_RESULTS as usize);
+        assert_eq!(limits.max_per_file, MAX_PER_FILE as usize);
+        assert_eq!(limits.max_file_size, MAX_FILE_SIZE);
+        assert_eq!(limits.max_files, MAX_FILES as usize);
+        assert_eq!(limits.context, MAX_CONTEXT as usize);
+    }
+}
@@ -0,0 +1,466 @@
+use crate::security::{FsMode, OpenBeneath, Sandbox};
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Filesystem tools with a native implementation, used when no WASI build of them can run
+pub const NATIVE_TOOLS: &[&str] = &["fs.read", "fs.list", "fs.write", "fs.delete", "fs.move", "fs.stat", "fs.mkdir", "fs.search"];
+
+pub fn is_native(tool_id: &str) -> bool {
+    NATIVE_TOOLS.contains(&tool_id)
+}
+
+/// Run a native filesystem tool. Blocking.
+pub fn run(tool_id: &str, sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    match tool_id {
+        "fs.read" => crate::fs_read::read(sandbox, input),
+        "fs.list" => crate::fs_list::list(sandbox, input),
+        "fs.write" => write(sandbox, input),
+        "fs.delete" => delete(sandbox, input),
+        "fs.move" => move_path(sandbox, input),
+        "fs.stat" => stat(sandbox, input),
+        "fs.mkdir" => mkdir(sandbox, input),
+        "fs.search" => crate::fs_search::search(sandbox, input),
+        _ => bail!("no native implementation of '{}'", tool_id),
+    }
+}
+
+pub(crate) fn str_field<'a>(input: &'a Value, tool_id: &str, field: &str) -> Result<&'a str> {
+    input
+        .get(field)
+        .and_then(|v| v.as_str())
+        .with_context(|| format!("{} requires '{}' parameter", tool_id, field))
+}
+
+pub(crate) fn bool_field(input: &Value, field: &str, default: bool) -> bool {
+    input.get(field).and_then(|v| v.as_bool()).unwrap_or(default)
+}
+
+/// A file timestamp, when the platform has it
+pub(crate) fn rfc3339(time: std::io::Result<std::time::SystemTime>) -> Option<String> {
+    time.ok().map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WriteMode {
+    /// Replace the file, creating it if needed
+    Overwrite,
+    /// Fail if the file exists
+    Create,
+    Append,
+}
+
+/// `fs.write` input
+pub struct WriteRequest<'a> {
+    pub path: &'a str,
+    pub content: &'a str,
+    pub mode: WriteMode,
+    /// Overwrite through a temporary file and a rename, so readers never see a partial file
+    pub atomic: bool,
+    /// Only write if the current file has this SHA-256 (hex)
+    pub expected_sha256: Option<&'a str>,
+}
+
+impl<'a> WriteRequest<'a> {
+    pub fn parse(input: &'a Value) -> Result<Self> {
+        let mode = match input.get("mode").and_then(|v| v.as_str()).unwrap_or("overwrite") {
+            "overwrite" => WriteMode::Overwrite,
+            "create" => WriteMode::Create,
+            "append" => WriteMode::Append,
+            other => bail!("unknown mode '{}' (expected overwrite, create or append)", other),
+        };
+        Ok(Self {
+            path: str_field(input, "fs.write", "path")?,
+            content: str_field(input, "fs.write", "content")?,
+            mode,
+            atomic: bool_field(input, "atomic", true),
+            expected_sha256: input.get("expected_sha256").and_then(|v| v.as_str()),
+        })
+    }
+
+    /// Refuse the write if the file's current state does not allow it
+    pub fn check_precondition(&self, existing: Option<&[u8]>) -> Result<()> {
+        if self.mode == WriteMode::Create && existing.is_some() {
+            bail!("'{}' already exists", self.path);
+        }
+        if let Some(expected) = self.expected_sha256 {
+            let Some(existing) = existing else {
+                bail!("'{}' does not exist, expected sha256 {}", self.path, expected);
+            };
+            let actual = sha256_hex(existing);
+            if !actual.eq_ignore_ascii_case(expected) {
+                bail!("'{}' has changed: sha256 is {}, expected {}", self.path, actual, expected);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Current contents of a file being written, or `None` if it does not exist yet
+pub fn read_existing(sandbox: &Sandbox, path: &str, resolved: &Path) -> Result<Option<Vec<u8>>> {
+    if resolved.is_dir() {
+        bail!("'{}' is a directory", path);
+    }
+    if !resolved.exists() {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    sandbox.open_beneath(path, &OpenBeneath::read())?.read_to_end(&mut bytes)?;
+    Ok(Some(bytes))
+}
+
+fn write(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let request = WriteRequest::parse(input)?;
+    let resolved = sandbox.check(request.path, FsMode::Write)?;
+    if request.expected_sha256.is_some() {
+        request.check_precondition(read_existing(sandbox, request.path, &resolved)?.as_deref())?;
+    } else if resolved.is_dir() {
+        bail!("'{}' is a directory", request.path);
+    }
+
+    let content = request.content.as_bytes();
+    let action = match request.mode {
+        WriteMode::Create => {
+            let mut file = sandbox
+                .open_beneath(request.path, &OpenBeneath::create_new())
+                .with_context(|| format!("'{}' could not be created", request.path))?;
+            file.write_all(content)?;
+            "created"
+        }
+        WriteMode::Append => {
+            let mut file = sandbox.open_beneath(request.path, &OpenBeneath::append())?;
+            file.write_all(content)?;
+            "appended"
+        }
+        WriteMode::Overwrite => {
+            let existed = resolved.exists();
+            if request.atomic {
+                replace_atomically(sandbox, &resolved, content)?;
+            } else {
+                sandbox.open_beneath(request.path, &OpenBeneath::write())?.write_all(content)?;
+            }
+            if existed {
+                "overwritten"
+            } else {
+                "created"
+            }
+        }
+    };
+
+    Ok(json!({
+        "path": resolved.to_string_lossy(),
+        "action": action,
+        "bytes": content.len(),
+        // Hash of the whole file; appends only know the part they wrote
+        "sha256": (request.mode != WriteMode::Append).then(|| sha256_hex(content)),
+    }))
+}
+
+/// Write a sibling temporary file, then rename it over `resolved`
+fn replace_atomically(sandbox: &Sandbox, resolved: &Path, content: &[u8]) -> Result<()> {
+    let name = resolved.file_name().context("path has no file name")?.to_string_lossy();
+    let temp = resolved.with_file_name(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4().simple()));
+    let result = (|| -> Result<()> {
+        let mut file = sandbox.open_beneath(&temp.to_string_lossy(), &OpenBeneath::create_new())?;
+        file.write_all(content)?;
+        if let Ok(metadata) = std::fs::metadata(resolved) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        std::fs::rename(&temp, resolved)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+fn delete(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let plan = crate::dry_run::plan_delete(sandbox, input)?;
+    let path = str_field(input, "fs.delete", "path")?;
+    if plan["blocked"].as_array().is_some_and(|b| !b.is_empty()) {
+        bail!("'{}' contains entries the allowlist hides or does not let be deleted; nothing was deleted", path);
+    }
+    let resolved = sandbox.check_entry(path, FsMode::Delete)?;
+    if plan["kind"] == "directory" {
+        std::fs::remove_dir_all(&resolved)?;
+    } else {
+        std::fs::remove_file(&resolved)?;
+    }
+    Ok(json!({
+        "path": resolved.to_string_lossy(),
+        "deleted": true,
+        "kind": plan["kind"],
+        "file_count": plan["file_count"],
+        "bytes": plan["bytes"],
+    }))
+}
+
+/// Checked source and destination of a move, and whether the destination exists.
+/// Symlinks at either end are renamed or replaced themselves, not their targets.
+pub fn prepare_move(sandbox: &Sandbox, input: &Value) -> Result<(PathBuf, PathBuf, bool)> {
+    let path = str_field(input, "fs.move", "path")?;
+    let destination = str_field(input, "fs.move", "destination")?;
+    let from = sandbox.check_entry(path, FsMode::Delete)?;
+    let to = sandbox.check_entry(destination, FsMode::Write)?;
+    let Ok(metadata) = std::fs::symlink_metadata(&from) else {
+        bail!("'{}' does not exist", path);
+    };
+    if sandbox.roots().any(|root| root == from) {
+        bail!("'{}' is an allowlist root", path);
+    }
+    // Denied, excluded or read-only entries would leave their rule behind at the destination
+    if metadata.is_dir() {
+        if let Some(blocked) = first_blocked(sandbox, &from, FsMode::Write)? {
+            bail!("'{}' contains '{}', which is denied or read-only; it cannot be moved", path, blocked.display());
+        }
+    }
+    if to.starts_with(&from) {
+        bail!("cannot move '{}' into itself", path);
+    }
+    let exists = std::fs::symlink_metadata(&to).is_ok();
+    if exists && !bool_field(input, "overwrite", false) {
+        bail!("'{}' exists; set 'overwrite' to replace it", destination);
+    }
+    Ok((from, to, exists))
+}
+
+/// First entry under `dir` that may not be taken along with `access`, see
+/// [`Sandbox::permits_within`]. Symlinks are not followed.
+fn first_blocked(sandbox: &Sandbox, dir: &Path, access: FsMode) -> Result<Option<PathBuf>> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if !sandbox.permits_within(&path, access) {
+            return Ok(Some(path));
+        }
+        if entry.file_type()?.is_dir() {
+            if let Some(blocked) = first_blocked(sandbox, &path, access)? {
+                return Ok(Some(blocked));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn move_path(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let (from, to, replaced) = prepare_move(sandbox, input)?;
+    match std::fs::rename(&from, &to) {
+        Ok(()) => {}
+        // Files can still move between filesystems by copying
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices && std::fs::symlink_metadata(&from)?.is_file() => {
+            std::fs::copy(&from, &to)?;
+            std::fs::remove_file(&from)?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(json!({
+        "path": from.to_string_lossy(),
+        "destination": to.to_string_lossy(),
+        "replaced": replaced,
+    }))
+}
+
+fn stat(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let path = str_field(input, "fs.stat", "path")?;
+    let resolved = sandbox.check(path, FsMode::Read)?;
+    let metadata = std::fs::metadata(&resolved).with_context(|| format!("'{}' does not exist", path))?;
+    let kind = if metadata.is_dir() {
+        "directory"
+    } else if metadata.is_file() {
+        "file"
+    } else {
+        "other"
+    };
+
+    let mut output = json!({
+        "path": path,
+        "resolved": resolved.to_string_lossy(),
+        "kind": kind,
+        "size": metadata.len(),
+        "modified": rfc3339(metadata.modified()),
+        "created": rfc3339(metadata.created()),
+        "readonly": metadata.permissions().readonly(),
+    });
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        output["mode"] = json!(format!("{:04o}", metadata.permissions().mode() & 0o7777));
+    }
+    if metadata.is_file() && bool_field(input, "hash", false) {
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut sandbox.open_beneath(path, &OpenBeneath::read())?, &mut hasher)?;
+        output["sha256"] = json!(hex::encode(hasher.finalize()));
+    }
+    Ok(output)
+}
+
+/// Checked directory to create, and whether it already exists
+pub fn prepare_mkdir(sandbox: &Sandbox, input: &Value) -> Result<(PathBuf, bool)> {
+    let path = str_field(input, "fs.mkdir", "path")?;
+    let resolved = sandbox.check(path, FsMode::Write)?;
+    if resolved.is_dir() {
+        return Ok((resolved, true));
+    }
+    if resolved.exists() {
+        bail!("'{}' exists and is not a directory", path);
+    }
+    if !bool_field(input, "parents", true) && !resolved.parent().is_some_and(Path::is_dir) {
+        bail!("parent of '{}' does not exist; set 'parents' to create it", path);
+    }
+    Ok((resolved, false))
+}
+
+fn mkdir(sandbox: &Sandbox, input: &Value) -> Result<Value> {
+    let (resolved, exists) = prepare_mkdir(sandbox, input)?;
+    if !exists {
+        std::fs::create_dir_all(&resolved)?;
+    }
+    Ok(json!({ "path": resolved.to_string_lossy(), "created": !exists }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> (PathBuf, Sandbox) {
+        let dir = std::env::temp_dir().join(format!("mcp-fs-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sandbox = Sandbox::new(&[dir.to_string_lossy().to_string()]);
+        (dir, sandbox)
+    }
+
+    #[test]
+    fn test_write_modes_and_precondition() {
+        let (dir, sandbox) = scratch("write");
+        let file = dir.join("a.txt").to_string_lossy().to_string();
+
+        let out = run("fs.write", &sandbox, &json!({"path": file, "content": "one\n", "mode": "create"})).unwrap();
+        assert_eq!(out["action"], "created");
+        let hash = out["sha256"].as_str().unwrap().to_string();
+        assert!(run("fs.write", &sandbox, &json!({"path": file, "content": "x", "mode": "create"})).is_err());
+
+        run("fs.write", &sandbox, &json!({"path": file, "content": "two\n", "mode": "append"})).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "one\ntwo\n");
+
+        // The file changed since `hash` was taken
+        let stale = json!({"path": file, "content": "three\n", "expected_sha256": hash});
+        assert!(run("fs.write", &sandbox, &stale).unwrap_err().to_string().contains("has changed"));
+        let current = sha256_hex(b"one\ntwo\n");
+        let fresh = json!({"path": file, "content": "three\n", "expected_sha256": current});
+        assert_eq!(run("fs.write", &sandbox, &fresh).unwrap()["action"], "overwritten");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "three\n");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1, "no temporary file left behind");
+
+        assert!(run("fs.write", &sandbox, &json!({"path": "/etc/mcp-test", "content": ""})).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_mkdir_move_stat_delete() {
+        let (dir, sandbox) = scratch("tree");
+        let root = dir.to_string_lossy().to_string();
+        let sub = format!("{}/a/b", root);
+
+        assert_eq!(run("fs.mkdir", &sandbox, &json!({"path": sub})).unwrap()["created"], true);
+        assert_eq!(run("fs.mkdir", &sandbox, &json!({"path": sub})).unwrap()["created"], false);
+        std::fs::write(format!("{}/f.txt", sub), "data").unwrap();
+
+        let stat = run("fs.stat", &sandbox, &json!({"path": format!("{}/f.txt", sub), "hash": true})).unwrap();
+        assert_eq!(stat["kind"], "file");
+        assert_eq!(stat["size"], 4);
+        assert_eq!(stat["sha256"], sha256_hex(b"data"));
+
+        let moved = format!("{}/g.txt", root);
+        run("fs.move", &sandbox, &json!({"path": format!("{}/f.txt", sub), "destination": moved})).unwrap();
+        assert!(std::path::Path::new(&moved).exists());
+        std::fs::write(format!("{}/h.txt", root), "").unwrap();
+        let clash = json!({"path": moved, "destination": format!("{}/h.txt", root)});
+        assert!(run("fs.move", &sandbox, &clash).is_err());
+        assert!(run("fs.move", &sandbox, &json!({"path": format!("{}/a", root), "destination": sub})).is_err());
+
+        std::fs::write(format!("{}/x.txt", sub), "x").unwrap();
+        assert!(run("fs.delete", &sandbox, &json!({"path": format!("{}/a", root)})).is_err());
+        let deleted = run("fs.delete", &sandbox, &json!({"path": format!("{}/a", root), "recursive": true})).unwrap();
+        assert_eq!(deleted["file_count"], 1);
+        assert!(!dir.join("a").exists());
+        assert!(run("fs.delete", &sandbox, &json!({"path": root, "recursive": true})).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_move_keeps_denied_entries_under_their_rule() {
+        let dir = std::env::temp_dir().join(format!("mcp-fs-deny-move-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("project/secrets")).unwrap();
+        std::fs::write(dir.join("project/secrets/key.pem"), "key").unwrap();
+        std::fs::write(dir.join("project/notes.txt"), "notes").unwrap();
+        let root = dir.to_string_lossy().to_string();
+        let rules: Vec<crate::security::FsRule> =
+            serde_json::from_value(json!([{"path": root, "mode": "delete", "deny": ["project/secrets"]}])).unwrap();
+        let sandbox = Sandbox::from_rules(&rules);
+
+        let input = json!({"path": format!("{}/project", root), "destination": format!("{}/moved", root)});
+        let err = run("fs.move", &sandbox, &input).unwrap_err();
+        assert!(err.to_string().contains("denied"), "{}", err);
+        assert!(dir.join("project/secrets/key.pem").exists());
+        assert!(!dir.join("moved").exists());
+
+        // Files beside the denied directory still move
+        let file = json!({"path": format!("{}/project/notes.txt", root), "destination": format!("{}/notes.txt", root)});
+        run("fs.move", &sandbox, &file).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_nested_read_only_entry_is_not_moved_or_deleted() {
+        let dir = std::env::temp_dir().join(format!("mcp-fs-nested-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("work/mount")).unwrap();
+        std::fs::write(dir.join("work/mount/data.txt"), "data").unwrap();
+        let root = dir.to_string_lossy().to_string();
+        let rules: Vec<crate::security::FsRule> = serde_json::from_value(json!([
+            {"path": root, "mode": "delete"},
+            {"path": format!("{}/work/mount", root), "mode": "read"},
+        ]))
+        .unwrap();
+        let sandbox = Sandbox::from_rules(&rules);
+
+        let moved = json!({"path": format!("{}/work", root), "destination": format!("{}/moved", root)});
+        assert!(run("fs.move", &sandbox, &moved).unwrap_err().to_string().contains("read-only"));
+        let deleted = json!({"path": format!("{}/work", root), "recursive": true});
+        let plan = crate::dry_run::plan_delete(&sandbox, &deleted).unwrap();
+        assert_eq!(plan["blocked"], json!([format!("{}/work/mount", root)]));
+        assert!(run("fs.delete", &sandbox, &deleted).unwrap_err().to_string().contains("nothing was deleted"));
+        assert!(dir.join("work/mount/data.txt").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_moved_and_deleted_themselves() {
+        let (dir, sandbox) = scratch("links");
+        let root = dir.to_string_lossy().to_string();
+        std::fs::create_dir_all(dir.join("data")).unwrap();
+        std::fs::write(dir.join("data/keep.txt"), "keep").unwrap();
+        std::os::unix::fs::symlink(dir.join("data"), dir.join("dir-link")).unwrap();
+        std::os::unix::fs::symlink(dir.join("data/keep.txt"), dir.join("file-link")).unwrap();
+
+        let moved = format!("{}/renamed-link", root);
+        run("fs.move", &sandbox, &json!({"path": format!("{}/file-link", root), "destination": moved})).unwrap();
+        assert!(std::fs::symlink_metadata(&moved).unwrap().is_symlink());
+        assert!(dir.join("data/keep.txt").exists(), "target stays where it was");
+
+        let deleted = run("fs.delete", &sandbox, &json!({"path": format!("{}/dir-link", root), "recursive": true})).unwrap();
+        assert_eq!(deleted["kind"], "symlink");
+        assert!(std::fs::symlink_metadata(dir.join("dir-link")).is_err());
+        run("fs.delete", &sandbox, &json!({"path": moved})).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("data/keep.txt")).unwrap(), "keep");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
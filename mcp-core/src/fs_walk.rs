@@ -0,0 +1,309 @@
+use crate::security::Sandbox;
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+/// `*` and `?` stop at `/`; only `**` crosses directories
+const MATCH: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A gitignore-style glob: patterns without a `/` match at any depth, others from the base
+pub fn glob_pattern(pattern: &str) -> Result<Pattern> {
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    let full = if anchored { pattern.to_string() } else { format!("**/{}", pattern) };
+    Pattern::new(&full).with_context(|| format!("invalid glob '{}'", pattern))
+}
+
+/// Whether a path relative to a walk root matches a glob
+pub fn matches(pattern: &Pattern, relative: &str) -> bool {
+    pattern.matches_with(relative, MATCH)
+}
+
+/// Split a wildcard path at its first component with `*`, `?` or `[`: the
+/// directory to walk and the glob for paths below it. `None` without wildcards.
+pub fn split_wildcard(path: &str) -> Option<(String, String)> {
+    let components: Vec<&str> = path.split('/').collect();
+    let first = components.iter().position(|c| c.contains(['*', '?', '[']))?;
+    let base = match components[..first].join("/") {
+        base if base.is_empty() && path.starts_with('/') => "/".to_string(),
+        base if base.is_empty() => ".".to_string(),
+        base => base,
+    };
+    Some((base, components[first..].join("/")))
+}
+
+/// Walk depth a glob from `split_wildcard` can reach
+pub fn glob_depth(glob: &str) -> usize {
+    if glob.contains("**") {
+        usize::MAX
+    } else {
+        glob.split('/').count()
+    }
+}
+
+struct IgnoreRule {
+    base: PathBuf,
+    pattern: Pattern,
+    negate: bool,
+    dir_only: bool,
+}
+
+/// `.gitignore` rules in effect for a directory: its own and its ancestors'
+#[derive(Default)]
+pub struct GitIgnore {
+    rules: Vec<IgnoreRule>,
+}
+
+impl GitIgnore {
+    /// Add `dir/.gitignore`, if there is one
+    pub fn load(&mut self, dir: &Path) {
+        if let Ok(text) = std::fs::read_to_string(dir.join(".gitignore")) {
+            self.add(dir, &text);
+        }
+    }
+
+    pub fn add(&mut self, base: &Path, text: &str) {
+        for line in text.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negate, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            // `\#` and `\!` escape a leading `#` or `!`
+            let line = line.strip_prefix('\\').unwrap_or(line);
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            match glob_pattern(line) {
+                Ok(pattern) => self.rules.push(IgnoreRule {
+                    base: base.to_path_buf(),
+                    pattern,
+                    negate,
+                    dir_only,
+                }),
+                Err(e) => tracing::debug!("Skipping .gitignore line in {}: {:#}", base.display(), e),
+            }
+        }
+    }
+
+    /// The last matching rule decides, so deeper files and later lines win
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            if let Ok(relative) = path.strip_prefix(&rule.base) {
+                if rule.pattern.matches_path_with(relative, MATCH) {
+                    ignored = !rule.negate;
+                }
+            }
+        }
+        ignored
+    }
+
+    fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.rules.truncate(len);
+    }
+}
+
+pub struct WalkEntry {
+    pub path: PathBuf,
+    /// Path below the walk root, `/`-separated
+    pub relative: String,
+    /// 1 for the root's children
+    pub depth: usize,
+    /// Not following symlinks
+    pub metadata: std::fs::Metadata,
+}
+
+impl WalkEntry {
+    pub fn is_dir(&self) -> bool {
+        self.metadata.is_dir()
+    }
+}
+
+/// Depth-first walk of a directory tree in name order. Denied and excluded
+/// allowlist entries, `.git` and (optionally) gitignored paths are skipped;
+/// symlinks are reported but never followed.
+pub struct Walk<'a> {
+    sandbox: &'a Sandbox,
+    max_depth: usize,
+    gitignore: bool,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl<'a> Walk<'a> {
+    pub fn new(sandbox: &'a Sandbox) -> Self {
+        Self {
+            sandbox,
+            max_depth: usize::MAX,
+            gitignore: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Levels below the root to visit; 1 lists only its children
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_gitignore(mut self, gitignore: bool) -> Self {
+        self.gitignore = gitignore;
+        self
+    }
+
+    /// Only report files matching an `include` glob (when given), and skip
+    /// files and directories matching an `exclude` glob
+    pub fn with_globs(mut self, include: &[String], exclude: &[String]) -> Result<Self> {
+        self.include = include.iter().map(|g| glob_pattern(g)).collect::<Result<_>>()?;
+        self.exclude = exclude.iter().map(|g| glob_pattern(g)).collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Visit everything below `root` (canonical) until `visit` breaks
+    pub fn run(&self, root: &Path, visit: &mut dyn FnMut(WalkEntry) -> ControlFlow<()>) -> Result<()> {
+        let mut ignore = GitIgnore::default();
+        if self.gitignore {
+            // Rules from directories between the allowlist root and the walk root still apply
+            if let Some(top) = self.sandbox.root_of(root) {
+                let mut ancestors: Vec<&Path> = root.ancestors().skip(1).take_while(|a| a.starts_with(top)).collect();
+                ancestors.reverse();
+                for ancestor in ancestors {
+                    ignore.load(ancestor);
+                }
+            }
+        }
+        let entries = std::fs::read_dir(root).with_context(|| format!("cannot read '{}'", root.display()))?;
+        // A break only ends the walk early
+        let _ = self.visit_dir(root, root, entries, 0, &mut ignore, visit);
+        Ok(())
+    }
+
+    fn visit_dir(
+        &self,
+        root: &Path,
+        dir: &Path,
+        entries: std::fs::ReadDir,
+        depth: usize,
+        ignore: &mut GitIgnore,
+        visit: &mut dyn FnMut(WalkEntry) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let mark = ignore.len();
+        if self.gitignore {
+            ignore.load(dir);
+        }
+        let mut entries: Vec<std::fs::DirEntry> = entries.filter_map(|e| e.ok()).collect();
+        entries.sort_by_key(|e| e.file_name());
+
+        let mut flow = ControlFlow::Continue(());
+        for entry in entries {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else { continue };
+            let is_dir = metadata.is_dir();
+            if self.sandbox.is_hidden(&path)
+                || (is_dir && entry.file_name() == ".git")
+                || (self.gitignore && ignore.is_ignored(&path, is_dir))
+            {
+                continue;
+            }
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            if self.exclude.iter().any(|p| matches(p, &relative)) {
+                continue;
+            }
+            if !is_dir && !self.include.is_empty() && !self.include.iter().any(|p| matches(p, &relative)) {
+                continue;
+            }
+
+            let child = is_dir.then(|| path.clone());
+            flow = visit(WalkEntry { path, relative, depth: depth + 1, metadata });
+            if flow.is_break() {
+                break;
+            }
+            if let Some(child) = child.filter(|_| depth + 1 < self.max_depth) {
+                // Unreadable subdirectories are skipped
+                if let Ok(children) = std::fs::read_dir(&child) {
+                    flow = self.visit_dir(root, &child, children, depth + 1, ignore, visit);
+                    if flow.is_break() {
+                        break;
+                    }
+                }
+            }
+        }
+        ignore.truncate(mark);
+        flow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gitignore_rules() {
+        let mut ignore = GitIgnore::default();
+        ignore.add(Path::new("/repo"), "# build output\ntarget/\n*.log\n!keep.log\n/docs/*.tmp\n");
+        ignore.add(Path::new("/repo/sub"), "keep.log\n");
+
+        assert!(ignore.is_ignored(Path::new("/repo/target"), true));
+        assert!(!ignore.is_ignored(Path::new("/repo/target"), false), "dir-only rule");
+        assert!(ignore.is_ignored(Path::new("/repo/a/b/c.log"), false));
+        assert!(!ignore.is_ignored(Path::new("/repo/keep.log"), false));
+        assert!(ignore.is_ignored(Path::new("/repo/sub/keep.log"), false), "deeper file wins");
+        assert!(ignore.is_ignored(Path::new("/repo/docs/x.tmp"), false));
+        assert!(!ignore.is_ignored(Path::new("/repo/a/docs/x.tmp"), false), "anchored rule");
+    }
+
+    #[test]
+    fn test_split_wildcard() {
+        assert_eq!(split_wildcard("/w/src/*.rs"), Some(("/w/src".to_string(), "*.rs".to_string())));
+        assert_eq!(split_wildcard("src/**/mod.rs"), Some(("src".to_string(), "**/mod.rs".to_string())));
+        assert_eq!(split_wildcard("*/a"), Some((".".to_string(), "*/a".to_string())));
+        assert_eq!(split_wildcard("/*"), Some(("/".to_string(), "*".to_string())));
+        assert_eq!(split_wildcard("/w/src"), None);
+        assert_eq!((glob_depth("*/a"), glob_depth("**/a")), (2, usize::MAX));
+    }
+
+    #[test]
+    fn test_walk() {
+        let dir = std::env::temp_dir().join(format!("mcp-walk-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src/deep")).unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        for file in ["src/a.rs", "src/deep/b.rs", "src/c.txt", "target/d.rs", "e.rs"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+        let sandbox = Sandbox::new(&[dir.to_string_lossy().to_string()]);
+
+        let collect = |walk: Walk| {
+            let mut seen = Vec::new();
+            walk.run(&dir, &mut |entry| {
+                seen.push(entry.relative);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+            seen
+        };
+        let rust = Walk::new(&sandbox).with_globs(&["*.rs".to_string()], &["src/deep".to_string()]).unwrap();
+        assert_eq!(collect(rust), ["e.rs", "src", "src/a.rs"]);
+        assert_eq!(collect(Walk::new(&sandbox).with_max_depth(1)), [".gitignore", "e.rs", "src"]);
+        assert!(collect(Walk::new(&sandbox).with_gitignore(false)).contains(&"target/d.rs".to_string()));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -6,11 +6,27 @@ pub mod context;
 pub mod event_bus;
 pub mod tool_executor;
 pub mod tool_wasi;
+pub mod dry_run;
+pub mod fs_tools;
+pub mod fs_walk;
+pub mod fs_read;
+pub mod fs_list;
+pub mod fs_search;
 pub mod observability;
 pub mod contracts;
 pub mod server_state;
 pub mod security;
+pub mod egress;
+pub mod process_sandbox;
+pub mod secrets;
+pub mod redaction;
 pub mod policies;
+pub mod tenants;
+pub mod approvals;
+pub mod policy_store;
+pub mod reload;
+pub mod audit;
+pub mod auth;
 pub mod connector_virtual;
 pub mod settings;
 
@@ -1,7 +1,7 @@
 use nurones_mcp::*;
 use clap::Parser;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 mod connector_virtual;
 mod settings;
@@ -49,8 +49,9 @@ async fn main() -> anyhow::Result<()> {
     config.validate()?;
 
     // Check context engine override
-    if let Some(engine_mode) = args.context_engine {
-        config.context_engine.enabled = engine_mode.to_lowercase() == "on";
+    let engine_override = args.context_engine.as_ref().map(|mode| mode.to_lowercase() == "on");
+    if let Some(enabled) = engine_override {
+        config.context_engine.enabled = enabled;
         tracing::info!(
             "Context Engine: {}",
             if config.context_engine.enabled {
@@ -61,15 +62,27 @@ async fn main() -> anyhow::Result<()> {
         );
     }
 
+    // Initialize observability
+    let observability = Arc::new(observability::ObservabilityService::new());
+    if !config.observability.otel_exporter.is_empty() {
+        if let Err(e) = observability::init_otlp_tracing(&config.observability.otel_exporter) {
+            tracing::warn!("OTLP trace export disabled: {}", e);
+        }
+    }
+
     // Initialize context engine
-    let _context_engine = context::ContextEngine::new(
-        config.context_engine.enabled,
-        config.context_engine.change_cap_pct_per_day,
-        config.context_engine.min_confidence,
+    let context_engine = Arc::new(
+        context::ContextEngine::new(
+            config.context_engine.enabled,
+            config.context_engine.change_cap_pct_per_day,
+            config.context_engine.min_confidence,
+        )
+        .with_shadow_stages(config.context_engine.shadow_stages.clone())
+        .with_metrics(observability.metrics()),
     );
 
-    // Initialize event bus
-    let _event_bus = event_bus::InMemoryEventBus::new();
+    // Initialize event bus, fed a `tool.executed` event per call by the executor
+    let event_bus = Arc::new(event_bus::InMemoryEventBus::new().with_metrics(observability.metrics()));
 
     // Load policies
     let policies_path = ".mcp/policies.json";
@@ -81,21 +94,55 @@ async fn main() -> anyhow::Result<()> {
         default_policies.save(policies_path)?;
         default_policies
     };
+    // Revision history starts from whatever document is live
+    let policy_store = Arc::new(policy_store::PolicyStore::new(policies_path));
+    if policy_store.latest()?.is_none() {
+        policy_store.commit(&policies, "system", Some("initial".to_string()))?;
+    }
     let policies = Arc::new(tokio::sync::RwLock::new(policies));
 
-    // Parse filesystem allowlist from args or policies
-    let fs_allowlist: Vec<String> = if args.fs_allowlist != "/workspace,/tmp" {
-        args.fs_allowlist.split(',').map(|s| s.trim().to_string()).collect()
-    } else {
-        policies.read().await.fs_allowlist.clone()
+    // Parse filesystem allowlist from args (full access) or policies (per-path modes)
+    let fs_override: Option<Vec<security::FsRule>> = (args.fs_allowlist != "/workspace,/tmp")
+        .then(|| args.fs_allowlist.split(',').map(|s| security::FsRule::full(s.trim())).collect());
+    let fs_rules = match &fs_override {
+        Some(rules) => rules.clone(),
+        None => policies.read().await.fs_rules(),
     };
 
-    // Initialize tool executor with allowlist
-    let tool_executor = tool_executor::InMemoryToolExecutor::with_allowlist(fs_allowlist.clone());
+    // Secret redaction for tool results, audit entries and logs
+    let redaction_config = policies.read().await.redaction.clone();
+    let redactor = Arc::new(redaction::Redactor::new(&redaction_config, observability.metrics())?);
+
+    // Initialize tool executor with allowlist, network and process policies
+    let (egress, process, env_policy, secret_scopes, tenancy) = {
+        let current = policies.read().await;
+        (
+            current.egress.clone(),
+            current.process.clone(),
+            current.env.clone(),
+            current.secret_scopes.clone(),
+            current.tenancy.clone(),
+        )
+    };
+    observability.metrics().set_tenants(tenancy.tenants.keys());
+    let secret_store = secrets::SecretChain::from_config(&config.secrets)?;
+    let tool_executor = tool_executor::InMemoryToolExecutor::new()
+        .with_fs_rules(&fs_rules)
+        .with_egress(egress)
+        .with_process(process)
+        .with_env_policy(env_policy)
+        .with_secret_scopes(secret_scopes)
+        .with_tenancy(tenancy)
+        .with_secrets(Arc::new(secret_store))
+        .with_redactor(redactor)
+        .with_metrics(observability.metrics())
+        .with_events(event_bus)
+        .with_context_engine(context_engine.clone());
     
     // Load tools from directory
-    tracing::info!("Loading tools from: {}", args.tools_dir);
-    if let Err(e) = tool_executor.load_tools(&args.tools_dir).await {
+    let tools_dir = config.tools_dir.clone().unwrap_or_else(|| args.tools_dir.clone());
+    tracing::info!("Loading tools from: {}", tools_dir);
+    if let Err(e) = tool_executor.load_tools(&tools_dir).await {
         tracing::warn!("Failed to load some tools: {}", e);
     }
 
@@ -108,72 +155,101 @@ async fn main() -> anyhow::Result<()> {
     // Set initial context engine status
     server_state.set_context_engine(config.context_engine.enabled).await;
     
-    // Register all tools in state
-    let tool_manifests = [
-        ("fs.read", "1.1.0", vec!["read"], "WASI"),
-        ("fs.write", "1.1.0", vec!["write"], "WASI"),
-        ("fs.list", "1.0.0", vec!["read"], "WASI"),
-        ("fs.delete", "1.0.0", vec!["write", "delete"], "WASI"),
-        ("fs.search", "1.0.0", vec!["read"], "WASI"),
-        ("db.query", "1.0.0", vec!["read", "db"], "Native"),
-        ("db.execute", "1.0.0", vec!["write", "db"], "Native"),
-        ("db.schema", "1.0.0", vec!["read", "db"], "Native"),
-        ("http.request", "1.0.0", vec!["network"], "Native"),
-        ("fetch.url", "1.0.0", vec!["network"], "Native"),
-        ("embedding.generate", "1.0.0", vec!["ai", "compute"], "Native"),
-        ("completion.stream", "1.0.0", vec!["ai", "compute"], "Native"),
-        ("process.execute", "1.0.0", vec!["execute", "system"], "Native"),
-        ("env.get", "1.0.0", vec!["read", "system"], "Native"),
-        ("telemetry.push", "1.0.0", vec!["emit"], "Native"),
-        ("scrape.url", "1.0.0", vec!["network", "read"], "Extension"),
-        ("scrape.site", "1.0.0", vec!["network", "read", "write"], "Extension"),
-        ("parse.html", "1.0.0", vec!["read"], "Extension"),
-        ("extract.links", "1.0.0", vec!["network", "read"], "Extension"),
-        ("session.compress", "1.0.0", vec!["read", "write", "ai"], "Extension"),
-    ];
-    
-    for (name, version, permissions, tool_type) in tool_manifests {
-        server_state.register_tool(
-            name.to_string(),
-            server_state::ToolStatus {
-                name: name.to_string(),
-                version: version.to_string(),
-                enabled: true,
-                permissions: permissions.iter().map(|s| s.to_string()).collect(),
-                tool_type: tool_type.to_string(),
-            },
-        ).await;
-    }
+    // The tool listing mirrors the loaded manifests
+    server_state.sync_tools(&tool_executor_for_api.manifests().await).await;
 
-    // Initialize observability
-    let _observability = observability::ObservabilityService::new();
+    // Open the audit log (hash-chained JSONL)
+    let audit_log = if config.audit.enabled {
+        match audit::AuditLog::open(&config.audit.dir, config.audit.max_file_bytes) {
+            Ok(log) => Some(Arc::new(log.with_redactor(tool_executor_for_api.redactor().clone()))),
+            Err(e) => {
+                tracing::warn!("Audit log disabled: {}", e);
+                None
+            }
+        }
+    } else {
+        tracing::info!("Audit log disabled by configuration");
+        None
+    };
 
     // Initialize virtual connector
     let virtual_connector = Arc::new(VirtualConnector::new());
 
+    // Listener port; a new value makes the API server rebind
+    let (port_tx, port_rx) = tokio::sync::watch::channel(config.server.port);
+    let port_tx = Arc::new(port_tx);
+    let port = config.server.port;
+
     // Prepare settings state
     let settings_state = SettingsState {
         cfg_path: args.config.clone(),
-        server_port: Arc::new(Mutex::new(config.server.port)),
+        server_port: port_tx.clone(),
     };
 
+    // Reload config.json and policies.json on change or SIGHUP
+    let reloader = Arc::new(
+        reload::Reloader::new(
+            args.config.clone(),
+            config.clone(),
+            policies.clone(),
+            policy_store,
+            tool_executor_for_api.clone(),
+            context_engine.clone(),
+            server_state.clone(),
+            port_tx,
+        )
+        .with_fs_override(fs_override)
+        .with_engine_override(engine_override)
+        .with_default_tools_dir(args.tools_dir.clone())
+        .with_metrics(observability.metrics()),
+    );
+    let _watcher = match reloader.watch() {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            tracing::warn!("Config file watching disabled: {}", e);
+            None
+        }
+    };
+    #[cfg(unix)]
+    reloader.reload_on_sighup()?;
+
+    let api_auth = Arc::new(auth::ApiAuth::from_config(&config.auth)?);
+    if api_auth.is_disabled() {
+        tracing::warn!(
+            "API authentication disabled: no auth.providers configured, admin routes are refused and every caller gets rbac.defaultRole '{}'",
+            config.rbac.default_role
+        );
+    } else {
+        tracing::info!("API authentication: {:?}", config.auth.providers);
+    }
+
     // Start unified API server on single port
-    let port = config.server.port;
     let state_for_server = server_state.clone();
     let executor_for_server = tool_executor_for_api.clone();
     let policies_for_server = policies.clone();
+    let reloader_for_server = reloader.clone();
     let vc_for_server = virtual_connector.clone();
+    let engine_for_server = context_engine.clone();
+    let observability_for_server = observability.clone();
+    let audit_for_server = audit_log.clone();
+    let default_role_for_server = config.rbac.default_role.clone();
     let transports_for_server: Vec<String> = config.transports.iter()
         .map(|t| format!("{:?}", t).to_lowercase())
         .collect();
     let otel_exporter_for_server = config.observability.otel_exporter.clone();
     tokio::spawn(async move {
         if let Err(e) = start_api_server(
-            port,
+            port_rx,
             state_for_server,
             executor_for_server,
             policies_for_server,
+            reloader_for_server,
+            api_auth,
             vc_for_server,
+            engine_for_server,
+            observability_for_server,
+            audit_for_server,
+            default_role_for_server,
             settings_state,
             transports_for_server,
             otel_exporter_for_server,
@@ -194,38 +270,55 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("    - Enabled: {}", config.context_engine.enabled);
     tracing::info!("    - Change Cap: {}%/day", config.context_engine.change_cap_pct_per_day);
     tracing::info!("    - Min Confidence: {}", config.context_engine.min_confidence);
+    tracing::info!("    - Shadow Stages: {:?}", config.context_engine.shadow_stages);
     tracing::info!("  Filesystem Allowlist: {}", args.fs_allowlist);
 
     // Keep server running
     tokio::signal::ctrl_c().await?;
     tracing::info!("Shutting down...");
 
+    // Flush spans still queued in the batch exporter
+    tokio::task::spawn_blocking(observability::shutdown_tracing).await?;
+
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn start_api_server(
-    port: u16,
+    mut port: tokio::sync::watch::Receiver<u16>,
     state: Arc<server_state::ServerState>,
     tool_executor: Arc<tool_executor::InMemoryToolExecutor>,
     policies: Arc<tokio::sync::RwLock<policies::Policies>>,
+    reloader: Arc<reload::Reloader>,
+    api_auth: Arc<auth::ApiAuth>,
     virtual_connector: Arc<VirtualConnector>,
+    context_engine: Arc<context::ContextEngine>,
+    observability: Arc<observability::ObservabilityService>,
+    audit_log: Option<Arc<audit::AuditLog>>,
+    default_role: String,
     settings_state: SettingsState,
     transports: Vec<String>,
     otel_exporter: String,
 ) -> anyhow::Result<()> {
     use axum::{
-        extract::{Path, State},
-        http::StatusCode,
+        extract::{ConnectInfo, Path, Query, Request, State},
+        http::{header, HeaderMap, HeaderValue, StatusCode},
+        middleware::{self, Next},
+        response::{IntoResponse, Response},
         routing::{get, post, patch},
         Json, Router,
     };
-    use tower_http::cors::{CorsLayer, Any};
+    use tower_http::cors::{AllowOrigin, CorsLayer, Any};
     use tower_http::services::ServeDir;
     use serde_json::json;
     use prometheus::{TextEncoder, Encoder};
 
-    // Initialize Prometheus metrics
-    let registry = prometheus::Registry::new();
+    let default_role = Arc::new(default_role);
+    let quotas = Arc::new(tenants::QuotaTracker::new(observability.metrics()));
+    let approval_queue = Arc::new(approvals::ApprovalQueue::new().with_metrics(observability.metrics()));
+
+    // Prometheus metrics share the observability registry with the hot-path counters
+    let registry = (*observability.registry()).clone();
     
     // Register custom metrics
     let connections_gauge = prometheus::IntGauge::new(
@@ -245,21 +338,34 @@ async fn start_api_server(
         "Context engine status (1=enabled, 0=disabled)"
     ).unwrap();
     registry.register(Box::new(context_engine_gauge.clone())).unwrap();
+
+    let shadow_gauge = prometheus::GaugeVec::new(
+        prometheus::Opts::new(
+            "mcp_context_engine_shadow_value",
+            "Context engine shadow-mode values (series=live|proposed|baseline)"
+        ),
+        &["key", "series"]
+    ).unwrap();
+    registry.register(Box::new(shadow_gauge.clone())).unwrap();
     
     // Clone state and metrics for the metrics endpoint
     let state_for_metrics = state.clone();
     let registry_clone = registry.clone();
 
+    type MetricsState = (
+        Arc<server_state::ServerState>,
+        prometheus::Registry,
+        prometheus::IntGauge,
+        prometheus::IntGauge,
+        prometheus::IntGauge,
+        Arc<context::ContextEngine>,
+        prometheus::GaugeVec,
+    );
+
     // Handler functions
     async fn get_metrics(
-        State((state, registry, connections_gauge, tools_gauge, context_engine_gauge)): 
-        State<(
-            Arc<server_state::ServerState>,
-            prometheus::Registry,
-            prometheus::IntGauge,
-            prometheus::IntGauge,
-            prometheus::IntGauge
-        )>
+        State((state, registry, connections_gauge, tools_gauge, context_engine_gauge, engine, shadow_gauge)): 
+        State<MetricsState>
     ) -> Result<String, StatusCode> {
         // Update metrics with current values
         let connections = state.get_connections().await;
@@ -270,6 +376,13 @@ async fn start_api_server(
         
         let context_engine = state.get_context_engine_status().await;
         context_engine_gauge.set(if context_engine { 1 } else { 0 });
+
+        // Shadow-mode proposals next to the live values they would replace
+        for (key, sample) in engine.shadow_snapshot() {
+            shadow_gauge.with_label_values(&[&key, "live"]).set(sample.live);
+            shadow_gauge.with_label_values(&[&key, "proposed"]).set(sample.proposed);
+            shadow_gauge.with_label_values(&[&key, "baseline"]).set(sample.baseline);
+        }
         
         // Encode and return metrics
         let encoder = TextEncoder::new();
@@ -281,9 +394,11 @@ async fn start_api_server(
         String::from_utf8(buffer)
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
     }
+    type StatusState = (Arc<server_state::ServerState>, Vec<String>, bool, bool, String);
+
     async fn get_status(
         State((server_state, transports, native_available, wasi_available, otel_exporter)):
-        State<(Arc<server_state::ServerState>, Vec<String>, bool, bool, String)>
+        State<StatusState>
     ) -> Json<serde_json::Value> {
         let connections = server_state.get_connections().await;
         let tools = server_state.get_tools().await;
@@ -307,8 +422,116 @@ async fn start_api_server(
         }))
     }
 
-    async fn get_tools(State(state): State<Arc<server_state::ServerState>>) -> Json<Vec<server_state::ToolStatus>> {
-        Json(state.get_tools().await)
+    type AuthState = (Arc<auth::ApiAuth>, Arc<tokio::sync::RwLock<policies::Policies>>);
+
+    /// Authenticate API calls and require the admin role on admin routes. The
+    /// principal replaces any client-supplied `x-mcp-user`, so RBAC, policies
+    /// and audit all see the authenticated identity.
+    async fn require_auth(
+        State((api_auth, policies)): State<AuthState>,
+        ConnectInfo(peer): ConnectInfo<std::net::SocketAddr>,
+        mut request: Request,
+        next: Next,
+    ) -> Response {
+        let path = request.uri().path().to_string();
+        if auth::is_public_route(&path) {
+            return next.run(request).await;
+        }
+        if api_auth.is_disabled() {
+            // Anonymous callers may use tools, but never reconfigure the server
+            if auth::is_admin_route(request.method().as_str(), &path) {
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json(json!({
+                        "success": false,
+                        "error": "admin routes need an authentication provider in auth.providers",
+                    })),
+                )
+                    .into_response();
+            }
+            return next.run(request).await;
+        }
+
+        let authorization = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok());
+        let proxied = ["x-forwarded-for", "forwarded", "x-real-ip"]
+            .iter()
+            .any(|name| request.headers().contains_key(*name));
+        let authenticated = {
+            let header_value = |name| request.headers().get(name).and_then(|v| v.to_str().ok());
+            let origin = auth::cross_origin(header_value(header::ORIGIN), header_value(header::HOST));
+            api_auth.authenticate(authorization, peer, proxied, origin)
+        };
+        let principal = match authenticated {
+            Ok(principal) => principal,
+            Err(reason) => {
+                tracing::warn!("Unauthenticated {} {} from {}: {}", request.method(), path, peer, reason);
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    [(header::WWW_AUTHENTICATE, "Bearer")],
+                    Json(json!({ "success": false, "error": reason })),
+                )
+                    .into_response();
+            }
+        };
+
+        if auth::is_admin_route(request.method().as_str(), &path) {
+            let role = policies.read().await.users.get(&principal.subject).cloned();
+            if role.as_deref() != Some(api_auth.admin_role()) {
+                tracing::warn!("{} denied admin route {} {}", principal.subject, request.method(), path);
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json(json!({
+                        "success": false,
+                        "error": format!("'{}' role required", api_auth.admin_role()),
+                    })),
+                )
+                    .into_response();
+            }
+        }
+
+        let Ok(user) = HeaderValue::from_str(&principal.subject) else {
+            return (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")]).into_response();
+        };
+        request.headers_mut().insert("x-mcp-user", user);
+        request.extensions_mut().insert(principal);
+        next.run(request).await
+    }
+
+    type ToolsState = (
+        Arc<server_state::ServerState>,
+        Arc<tokio::sync::RwLock<policies::Policies>>,
+        Arc<String>,
+    );
+
+    #[derive(serde::Deserialize)]
+    struct TenantQuery {
+        tenant: Option<String>,
+    }
+
+    /// Tools the caller (and `?tenant=`, if given) may use; the rest are hidden
+    async fn get_tools(
+        State((state, policies, default_role)): State<ToolsState>,
+        Query(query): Query<TenantQuery>,
+        principal: Option<axum::Extension<auth::Principal>>,
+    ) -> Json<Vec<server_state::ToolStatus>> {
+        let caller = resolve_caller(principal.as_ref());
+        let policies = policies.read().await;
+        let tools = state
+            .get_tools()
+            .await
+            .into_iter()
+            .filter(|tool| policies.may_use(caller.as_deref(), Some(default_role.as_str()), &tool.name))
+            .filter(|tool| {
+                query
+                    .tenant
+                    .as_deref()
+                    .is_none_or(|tenant| policies.tenancy.refusal(tenant, &tool.name).is_none())
+            })
+            .collect();
+        Json(tools)
     }
 
     async fn create_tool(
@@ -323,11 +546,6 @@ async fn start_api_server(
             .unwrap_or("Native");
         let enabled = payload.get("enabled").and_then(|v| v.as_bool())
             .unwrap_or(true);
-        let permissions = payload.get("permissions")
-            .and_then(|v| v.as_array())
-            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
-            .unwrap_or_else(Vec::new);
-
         // Check if tool already exists
         if state.get_tool(name).await.is_some() {
             return Err(StatusCode::CONFLICT);
@@ -337,7 +555,8 @@ async fn start_api_server(
             name: name.to_string(),
             version: version.to_string(),
             enabled,
-            permissions,
+            // Permissions only ever come from a loaded manifest
+            permissions: Vec::new(),
             tool_type: tool_type.to_string(),
         };
 
@@ -365,13 +584,6 @@ async fn start_api_server(
         if let Some(enabled) = payload.get("enabled").and_then(|v| v.as_bool()) {
             tool.enabled = enabled;
         }
-        if let Some(permissions_val) = payload.get("permissions") {
-            if let Some(arr) = permissions_val.as_array() {
-                tool.permissions = arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect();
-            }
-        }
 
         state.update_tool(&tool_name, tool).await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -533,7 +745,7 @@ async fn start_api_server(
                     })
                 }).collect::<Vec<_>>()
             })
-            .unwrap_or_else(Vec::new);
+            .unwrap_or_default();
 
         let connections = state.get_connections().await;
         
@@ -586,6 +798,7 @@ async fn start_api_server(
 
     async fn register_connection(
         State(state): State<Arc<server_state::ServerState>>,
+        principal: Option<axum::Extension<auth::Principal>>,
         Json(payload): Json<serde_json::Value>,
     ) -> Result<Json<serde_json::Value>, StatusCode> {
         if let (Some(id), Some(conn_type)) = (
@@ -593,6 +806,10 @@ async fn start_api_server(
             payload.get("type").and_then(|v| v.as_str()),
         ) {
             state.add_connection(id.to_string(), conn_type.to_string()).await;
+            // Record who opened the connection; a claimed `user` field is ignored
+            if let Some(user) = resolve_caller(principal.as_ref()) {
+                state.bind_user(id, user).await;
+            }
             tracing::info!("Connection registered: {} ({})", id, conn_type);
             Ok(Json(json!({ "success": true, "id": id })))
         } else {
@@ -617,13 +834,29 @@ async fn start_api_server(
         Json(json!({ "success": true }))
     }
 
+    type ExecuteState = (
+        Arc<tool_executor::InMemoryToolExecutor>,
+        Arc<observability::ObservabilityService>,
+        Arc<tokio::sync::RwLock<policies::Policies>>,
+        Option<Arc<audit::AuditLog>>,
+        Arc<String>,
+        Arc<tenants::QuotaTracker>,
+        Arc<approvals::ApprovalQueue>,
+    );
+
     async fn execute_tool(
-        State((_state, executor)): State<(Arc<server_state::ServerState>, Arc<tool_executor::InMemoryToolExecutor>)>,
+        State((executor, observability, policies, audit_log, default_role, quotas, approval_queue)): State<ExecuteState>,
+        principal: Option<axum::Extension<auth::Principal>>,
+        headers: HeaderMap,
         Json(payload): Json<serde_json::Value>,
-    ) -> Result<Json<serde_json::Value>, StatusCode> {
+    ) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
         use crate::tool_executor::ToolExecutor;
+        use opentelemetry::trace::FutureExt;
         
-        tracing::debug!("Received tool execution request: {}", serde_json::to_string(&payload).unwrap_or_default());
+        tracing::debug!(
+            "Received tool execution request: {}",
+            executor.redactor().redact_str("log", &payload.to_string())
+        );
         
         let tool_name = payload.get("tool").and_then(|v| v.as_str())
             .ok_or_else(|| {
@@ -638,56 +871,648 @@ async fn start_api_server(
                 StatusCode::BAD_REQUEST
             })?;
         
-        let context: ContextFrame = serde_json::from_value(context_data.clone())
+        let mut context: ContextFrame = serde_json::from_value(context_data.clone())
             .map_err(|e| {
                 tracing::error!("Failed to parse ContextFrame: {}", e);
                 StatusCode::BAD_REQUEST
             })?;
+
+        // Link the frame to the caller's trace: frame fields, then MCP `_meta`, then HTTP headers
+        if context.traceparent.is_none() {
+            let meta = payload.get("_meta");
+            let from_meta = |key: &str| meta.and_then(|m| m.get(key)).and_then(|v| v.as_str());
+            let from_header = |key: &str| headers.get(key).and_then(|v| v.to_str().ok());
+            let (traceparent, tracestate) = match from_meta(observability::TRACEPARENT) {
+                Some(tp) => (Some(tp), from_meta(observability::TRACESTATE)),
+                None => (
+                    from_header(observability::TRACEPARENT),
+                    from_header(observability::TRACESTATE),
+                ),
+            };
+            // Malformed headers start a fresh trace rather than failing the call
+            if let Some(tp) = traceparent.filter(|tp| types::is_valid_traceparent(tp)) {
+                context.traceparent = Some(tp.to_string());
+                context.tracestate = tracestate.map(str::to_string);
+            }
+        }
         
+        // `"dry_run": true` on the request is shorthand for the frame flag
+        if payload.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false) {
+            context
+                .flags
+                .get_or_insert(types::Flags { allow_autotune: false, read_only: false, dry_run: false })
+                .dry_run = true;
+        }
+        let dry_run = context.flags.as_ref().is_some_and(|f| f.dry_run);
+
         tracing::info!("Executing tool: {} via API", tool_name);
+
+        let trace_id = observability
+            .start_trace(&format!("mcp.tools/execute {}", tool_name), Some(context.clone()))
+            .await;
+        let cx = observability
+            .trace_context(&trace_id)
+            .await
+            .unwrap_or_else(opentelemetry::Context::current);
+        let meta = observability::inject_trace_context(&cx);
+
+        // Authorize before dispatch; anonymous callers get the configured default role
+        let caller = resolve_caller(principal.as_ref());
+        let actor = caller.clone().unwrap_or_else(|| "anonymous".to_string());
+        // Policies match the manifest's permission tags; a tool without one is refused
+        let manifest = executor.manifest(tool_name).await;
+        let tool_permissions = manifest.as_ref().map(|m| m.permissions.clone()).unwrap_or_default();
+        let mut decision = policies.read().await.evaluate(&policies::PolicyRequest {
+            user: &actor,
+            default_role: Some(default_role.as_str()),
+            tool: tool_name,
+            tool_permissions: &tool_permissions,
+            input: &input,
+            context: &context,
+        });
+        // The frame's tenant selects allowlists, tools, quotas and egress, so it must be the caller's own
+        let claimed_tenant = principal.as_ref().and_then(|p| p.tenant.as_deref());
+        if let Some(reason) = policies.read().await.tenancy.binding_refusal(&actor, claimed_tenant, &context.tenant_id) {
+            decision.refuse(format!("tenant:{}", context.tenant_id), reason);
+        }
+        if manifest.is_none() {
+            decision.refuse(format!("tool:{}", tool_name), "no manifest is loaded for this tool".to_string());
+        }
+
+        // Set once a human has decided, so every later entry records who let the call through
+        let mut approval: Option<approvals::ApprovalRequest> = None;
+        let audit = |outcome: &str,
+                     error: Option<String>,
+                     duration_ms: u64,
+                     input: &serde_json::Value,
+                     context: &ContextFrame,
+                     approval: Option<&approvals::ApprovalRequest>| {
+            let reason = match approval {
+                Some(approval) => format!("{}; {}", decision.explanation, approval.summary()),
+                None => decision.explanation.clone(),
+            };
+            if let Some(audit_log) = &audit_log {
+                let record = audit::AuditRecord {
+                    actor: actor.clone(),
+                    role: decision.role.clone(),
+                    tool: tool_name.to_string(),
+                    input: input.clone(),
+                    context: context.clone(),
+                    decision: if decision.allowed { audit::AuditDecision::Allow } else { audit::AuditDecision::Deny },
+                    reason: Some(reason),
+                    outcome: outcome.to_string(),
+                    error,
+                    duration_ms,
+                };
+                // Appending syncs to disk, so keep it off the async workers
+                let audit_log = audit_log.clone();
+                tokio::task::spawn_blocking(move || {
+                    if let Err(e) = audit_log.append(record) {
+                        tracing::error!("Failed to write audit entry: {}", e);
+                    }
+                });
+            }
+        };
+
+        if !decision.allowed {
+            tracing::warn!("Denied {} for '{}': {}", tool_name, actor, decision.explanation);
+            observability.end_trace(&trace_id, observability::TraceStatus::Error).await;
+            audit("denied", None, 0, &input, &context, None);
+            return Ok((
+                StatusCode::FORBIDDEN,
+                Json(json!({
+                    "success": false,
+                    "error": format!("Permission denied: {}", decision.explanation),
+                    "decision": decision,
+                    "execution_time": 0,
+                    "_meta": meta
+                })),
+            ));
+        }
+
+        // Park calls an approve rule matched until a human decides or the wait runs out;
+        // dry runs change nothing, so they go straight through
+        if decision.requires_approval && !dry_run {
+            let settings = policies.read().await.approval.clone();
+            let mut summary = audit::summarize_input(&input);
+            executor.redactor().redact_value("approval", &mut summary);
+            let request = approvals::ApprovalRequest::new(
+                actor.clone(),
+                decision.role.clone(),
+                tool_name.to_string(),
+                summary,
+                context.clone(),
+                decision.approval_rules(),
+                settings.timeout(),
+            );
+            tracing::info!("Holding {} for '{}' for approval ({})", tool_name, actor, request.id);
+            let decided = approval_queue.request(request, settings.timeout()).await;
+            if decided.status != approvals::ApprovalStatus::Approved {
+                let (status, outcome) = match decided.status {
+                    approvals::ApprovalStatus::Expired => (StatusCode::REQUEST_TIMEOUT, "expired"),
+                    _ => (StatusCode::FORBIDDEN, "rejected"),
+                };
+                observability.end_trace(&trace_id, observability::TraceStatus::Error).await;
+                audit(outcome, None, 0, &input, &context, Some(&decided));
+                return Ok((
+                    status,
+                    Json(json!({
+                        "success": false,
+                        "error": format!("Not approved: {}", decided.summary()),
+                        "approval": decided,
+                        "execution_time": 0,
+                        "_meta": meta
+                    })),
+                ));
+            }
+            approval = Some(decided);
+        }
+
+        // Only calls the policy admits count against the tenant's quotas
+        let tenant_quotas = policies.read().await.tenancy.resolve(&context.tenant_id).quotas;
+        let _permit = match quotas.acquire(&context.tenant_id, &tenant_quotas) {
+            Ok(permit) => permit,
+            Err(reason) => {
+                tracing::warn!("Throttled {} for '{}': {}", tool_name, actor, reason);
+                observability.end_trace(&trace_id, observability::TraceStatus::Error).await;
+                audit("throttled", Some(reason.clone()), 0, &input, &context, approval.as_ref());
+                return Ok((
+                    StatusCode::TOO_MANY_REQUESTS,
+                    Json(json!({
+                        "success": false,
+                        "error": format!("Quota exceeded: {}", reason),
+                        "execution_time": 0,
+                        "_meta": meta
+                    })),
+                ));
+            }
+        };
+
+        let started = std::time::Instant::now();
+        let audit_input = input.clone();
+        let audit_context = context.clone();
+        let caller = tool_executor::Caller {
+            user: actor.clone(),
+            role: decision.role.clone(),
+        };
+        let outcome = tool_executor::run_as(caller, executor.execute(tool_name, input, context).with_context(cx)).await;
+        let status = match &outcome {
+            Ok(result) if result.success => observability::TraceStatus::Ok,
+            _ => observability::TraceStatus::Error,
+        };
+        observability.end_trace(&trace_id, status).await;
+
+        let (outcome_label, error) = match &outcome {
+            Ok(result) if result.success => ("success", None),
+            Ok(result) => ("failure", result.error.clone()),
+            Err(e) => ("error", Some(e.to_string())),
+        };
+        audit(
+            outcome_label,
+            error,
+            started.elapsed().as_millis() as u64,
+            &audit_input,
+            &audit_context,
+            approval.as_ref(),
+        );
         
-        match executor.execute(tool_name, input, context).await {
+        match outcome {
             Ok(result) => {
-                Ok(Json(json!({
+                Ok((StatusCode::OK, Json(json!({
                     "success": result.success,
                     "output": result.output,
                     "error": result.error,
                     "execution_time": result.execution_time,
-                    "context_used": result.context_used
-                })))
+                    "context_used": result.context_used,
+                    "_meta": meta
+                }))))
             }
             Err(e) => {
                 tracing::error!("Tool execution failed: {}", e);
-                Ok(Json(json!({
+                Ok((StatusCode::OK, Json(json!({
                     "success": false,
                     "error": e.to_string(),
-                    "execution_time": 0
-                })))
+                    "execution_time": 0,
+                    "_meta": meta
+                }))))
             }
         }
     }
 
+    /// Caller identity: the authenticated principal only. Client-claimed identities
+    /// (`x-mcp-user`, a `user` field) are ignored, so without authentication every
+    /// caller is anonymous and gets `rbac.defaultRole`
+    fn resolve_caller(principal: Option<&axum::Extension<auth::Principal>>) -> Option<String> {
+        principal.map(|p| p.subject.clone())
+    }
+
+    #[derive(serde::Deserialize)]
+    struct AuditRange {
+        from: Option<chrono::DateTime<chrono::Utc>>,
+        to: Option<chrono::DateTime<chrono::Utc>>,
+        /// Only entries whose ContextFrame carries this `tenant_id`
+        tenant: Option<String>,
+    }
+
+    async fn verify_audit(
+        State(audit_log): State<Option<Arc<audit::AuditLog>>>,
+    ) -> Result<Json<audit::AuditVerification>, StatusCode> {
+        let dir = audit_log.ok_or(StatusCode::NOT_FOUND)?.dir().to_path_buf();
+        tokio::task::spawn_blocking(move || audit::AuditLog::verify(dir))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .map(Json)
+            .map_err(|e| {
+                tracing::error!("Audit verification failed: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })
+    }
+
+    async fn export_audit(
+        State(audit_log): State<Option<Arc<audit::AuditLog>>>,
+        Query(range): Query<AuditRange>,
+    ) -> Result<Json<Vec<audit::AuditEntry>>, StatusCode> {
+        let dir = audit_log.ok_or(StatusCode::NOT_FOUND)?.dir().to_path_buf();
+        let (from, to, tenant) = (range.from, range.to, range.tenant);
+        tokio::task::spawn_blocking(move || audit::AuditLog::export(dir, from, to))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .map(|mut entries| {
+                if let Some(tenant) = tenant {
+                    entries.retain(|e| e.context.tenant_id == tenant);
+                }
+                Json(entries)
+            })
+            .map_err(|e| {
+                tracing::error!("Audit export failed: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })
+    }
+
     async fn get_policies(
-        State(policies): State<Arc<tokio::sync::RwLock<policies::Policies>>>,
+        State((policies, _)): State<PolicyStoreState>,
     ) -> Json<policies::Policies> {
         Json(policies.read().await.clone())
     }
 
+    #[derive(serde::Deserialize)]
+    struct EvaluateRequest {
+        #[serde(default)]
+        user: Option<String>,
+        tool: String,
+        #[serde(default)]
+        input: serde_json::Value,
+        #[serde(default)]
+        context: Option<ContextFrame>,
+        /// Candidate document to evaluate instead of the saved one
+        #[serde(default)]
+        policies: Option<policies::Policies>,
+        /// Replay this many recent audit entries under both documents
+        #[serde(default)]
+        history: Option<usize>,
+    }
+
+    type EvaluateState = (
+        Arc<tool_executor::InMemoryToolExecutor>,
+        Arc<tokio::sync::RwLock<policies::Policies>>,
+        Option<Arc<audit::AuditLog>>,
+        Arc<String>,
+    );
+
+    /// Dry-run a policy decision without executing or saving anything
+    async fn evaluate_policies(
+        State((executor, policies, audit_log, default_role)): State<EvaluateState>,
+        Json(request): Json<EvaluateRequest>,
+    ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+        let current = policies.read().await.clone();
+        let user = request.user.as_deref().unwrap_or("anonymous");
+        let context = request.context.unwrap_or_default();
+        let permissions = executor
+            .manifest(&request.tool)
+            .await
+            .map(|m| m.permissions)
+            .ok_or((StatusCode::NOT_FOUND, format!("no manifest is loaded for '{}'", request.tool)))?;
+        let policy_request = policies::PolicyRequest {
+            user,
+            default_role: Some(default_role.as_str()),
+            tool: &request.tool,
+            tool_permissions: &permissions,
+            input: &request.input,
+            context: &context,
+        };
+
+        let mut response = json!({ "current": current.explain(&policy_request) });
+        let Some(candidate) = request.policies else {
+            if request.history.is_some() {
+                return Err((StatusCode::BAD_REQUEST, "history diff needs a candidate `policies` document".into()));
+            }
+            return Ok(Json(response));
+        };
+        response["candidate"] = json!(candidate.explain(&policy_request));
+
+        if let Some(limit) = request.history {
+            let audit_log = audit_log.ok_or((StatusCode::NOT_FOUND, "audit log disabled".to_string()))?;
+            let dir = audit_log.dir().to_path_buf();
+            let mut entries = tokio::task::spawn_blocking(move || audit::AuditLog::export(dir, None, None))
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            entries.drain(..entries.len().saturating_sub(limit));
+
+            let tools: std::collections::HashMap<String, Vec<String>> = executor
+                .manifests()
+                .await
+                .into_iter()
+                .map(|m| (m.name, m.permissions))
+                .collect();
+            let changes = current.diff_decisions(&candidate, &entries, Some(default_role.as_str()), |tool| {
+                tools.get(tool).cloned().unwrap_or_default()
+            });
+            response["history"] = json!({ "replayed": entries.len(), "changed": changes });
+        }
+        Ok(Json(response))
+    }
+
+    type PolicyStoreState = (
+        Arc<tokio::sync::RwLock<policies::Policies>>,
+        Arc<reload::Reloader>,
+    );
+
+    /// Validate, record a revision and atomically replace `.mcp/policies.json`
     async fn update_policies(
-        State(policies): State<Arc<tokio::sync::RwLock<policies::Policies>>>,
+        State((_, reloader)): State<PolicyStoreState>,
+        principal: Option<axum::Extension<auth::Principal>>,
         Json(new_policies): Json<policies::Policies>,
+    ) -> (StatusCode, Json<serde_json::Value>) {
+        save_policies(&reloader, principal.as_ref(), new_policies).await
+    }
+
+    async fn save_policies(
+        reloader: &reload::Reloader,
+        principal: Option<&axum::Extension<auth::Principal>>,
+        new_policies: policies::Policies,
+    ) -> (StatusCode, Json<serde_json::Value>) {
+        let validation = new_policies.validate();
+        if !validation.is_valid() {
+            tracing::warn!("Rejected policies update: {:?}", validation.errors);
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({ "success": false, "errors": validation.errors, "warnings": validation.warnings })),
+            );
+        }
+
+        let author = resolve_caller(principal).unwrap_or_else(|| "anonymous".to_string());
+        match reloader.commit_policies(new_policies, &author).await {
+            Ok(revision) => {
+                tracing::info!("Policies updated successfully");
+                (
+                    StatusCode::OK,
+                    Json(json!({ "success": true, "revision": revision.revision, "warnings": validation.warnings })),
+                )
+            }
+            Err(e) => {
+                tracing::error!("Failed to save policies: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "success": false, "errors": [e.to_string()] })))
+            }
+        }
+    }
+
+    type TenantsState = (
+        Arc<tokio::sync::RwLock<policies::Policies>>,
+        Arc<reload::Reloader>,
+        Arc<tenants::QuotaTracker>,
+    );
+
+    /// Configured tenants with their overlays and current quota usage
+    async fn list_tenants(
+        State((policies, _, quotas)): State<TenantsState>,
+    ) -> Json<serde_json::Value> {
+        let policies = policies.read().await;
+        let tenancy = &policies.tenancy;
+        let mut ids: Vec<&String> = tenancy.tenants.keys().collect();
+        ids.sort();
+        let tenants: Vec<serde_json::Value> = ids
+            .into_iter()
+            .map(|id| json!({ "id": id, "overlay": tenancy.tenants[id], "usage": quotas.usage(id) }))
+            .collect();
+        Json(json!({
+            "require_known": tenancy.require_known,
+            "defaults": tenancy.defaults,
+            "tenants": tenants
+        }))
+    }
+
+    /// Effective settings for one tenant, whether or not it has its own entry
+    async fn get_tenant(
+        State((policies, _, quotas)): State<TenantsState>,
+        Path(id): Path<String>,
     ) -> Result<Json<serde_json::Value>, StatusCode> {
-        // Save to disk
-        if let Err(e) = new_policies.save(".mcp/policies.json") {
-            tracing::error!("Failed to save policies: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        if !tenants::is_valid_tenant_id(&id) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let policies = policies.read().await;
+        Ok(Json(json!({
+            "id": id,
+            "configured": policies.tenancy.tenants.contains_key(&id),
+            "overlay": policies.tenancy.tenants.get(&id),
+            "effective": policies.tenancy.resolve(&id),
+            "fs_allowlist": policies.fs_rules_for(&id),
+            "usage": quotas.usage(&id)
+        })))
+    }
+
+    /// Create or replace a tenant's overlay as a new policy revision
+    async fn put_tenant(
+        State((policies, reloader, _)): State<TenantsState>,
+        Path(id): Path<String>,
+        principal: Option<axum::Extension<auth::Principal>>,
+        Json(overlay): Json<tenants::TenantOverlay>,
+    ) -> (StatusCode, Json<serde_json::Value>) {
+        let mut next = policies.read().await.clone();
+        next.tenancy.tenants.insert(id, overlay);
+        save_policies(&reloader, principal.as_ref(), next).await
+    }
+
+    async fn delete_tenant(
+        State((policies, reloader, _)): State<TenantsState>,
+        Path(id): Path<String>,
+        principal: Option<axum::Extension<auth::Principal>>,
+    ) -> (StatusCode, Json<serde_json::Value>) {
+        let mut next = policies.read().await.clone();
+        if next.tenancy.tenants.remove(&id).is_none() {
+            return (StatusCode::NOT_FOUND, Json(json!({ "success": false, "errors": ["no such tenant"] })));
+        }
+        save_policies(&reloader, principal.as_ref(), next).await
+    }
+
+    type ApprovalsState = (
+        Arc<tokio::sync::RwLock<policies::Policies>>,
+        Arc<approvals::ApprovalQueue>,
+    );
+
+    #[derive(serde::Deserialize)]
+    struct ApprovalQuery {
+        status: Option<approvals::ApprovalStatus>,
+        /// Only requests for this `reason_trace_id`
+        trace: Option<String>,
+    }
+
+    #[derive(Default, serde::Deserialize)]
+    struct ApprovalDecision {
+        #[serde(default)]
+        comment: Option<String>,
+    }
+
+    /// Pending requests, then decided ones newest first
+    async fn list_approvals(
+        State((_, approval_queue)): State<ApprovalsState>,
+        Query(query): Query<ApprovalQuery>,
+    ) -> Json<Vec<approvals::ApprovalRequest>> {
+        Json(approval_queue.list(query.status, query.trace.as_deref()))
+    }
+
+    async fn get_approval(
+        State((_, approval_queue)): State<ApprovalsState>,
+        Path(id): Path<String>,
+    ) -> Result<Json<approvals::ApprovalRequest>, StatusCode> {
+        approval_queue.get(&id).map(Json).ok_or(StatusCode::NOT_FOUND)
+    }
+
+    /// Server-sent events: every pending request on connect, then each new or decided one
+    async fn stream_approvals(
+        State((_, approval_queue)): State<ApprovalsState>,
+    ) -> axum::response::sse::Sse<
+        impl tokio_stream::Stream<Item = Result<axum::response::sse::Event, std::convert::Infallible>>,
+    > {
+        use axum::response::sse::{Event, KeepAlive, Sse};
+        use tokio_stream::StreamExt;
+
+        // Subscribe before the snapshot so nothing falls between the two
+        let updates = tokio_stream::wrappers::BroadcastStream::new(approval_queue.subscribe())
+            .filter_map(|update| update.ok());
+        let pending = approval_queue.list(Some(approvals::ApprovalStatus::Pending), None);
+        let events = tokio_stream::iter(pending).chain(updates).map(|request| {
+            Ok(Event::default()
+                .event(request.status.as_str())
+                .id(request.id.clone())
+                .json_data(&request)
+                .unwrap_or_default())
+        });
+        Sse::new(events).keep_alive(KeepAlive::default())
+    }
+
+    async fn approve_request(
+        state: State<ApprovalsState>,
+        Path(id): Path<String>,
+        principal: Option<axum::Extension<auth::Principal>>,
+        body: Option<Json<ApprovalDecision>>,
+    ) -> (StatusCode, Json<serde_json::Value>) {
+        decide_approval(state, id, true, principal, body.map(|b| b.0).unwrap_or_default()).await
+    }
+
+    async fn reject_request(
+        state: State<ApprovalsState>,
+        Path(id): Path<String>,
+        principal: Option<axum::Extension<auth::Principal>>,
+        body: Option<Json<ApprovalDecision>>,
+    ) -> (StatusCode, Json<serde_json::Value>) {
+        decide_approval(state, id, false, principal, body.map(|b| b.0).unwrap_or_default()).await
+    }
+
+    async fn decide_approval(
+        State((policies, approval_queue)): State<ApprovalsState>,
+        id: String,
+        approve: bool,
+        principal: Option<axum::Extension<auth::Principal>>,
+        body: ApprovalDecision,
+    ) -> (StatusCode, Json<serde_json::Value>) {
+        let by = resolve_caller(principal.as_ref()).unwrap_or_else(|| "anonymous".to_string());
+        let allow_self_approval = policies.read().await.approval.allow_self_approval;
+        match approval_queue.decide(&id, approve, &by, body.comment, allow_self_approval) {
+            Ok(Some(decided)) => (StatusCode::OK, Json(json!({ "success": true, "approval": decided }))),
+            Ok(None) => (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "success": false, "error": "no pending approval with that id" })),
+            ),
+            Err(e) => (StatusCode::FORBIDDEN, Json(json!({ "success": false, "error": e.to_string() }))),
+        }
+    }
+
+    async fn list_policy_revisions(
+        State((_, reloader)): State<PolicyStoreState>,
+    ) -> Result<Json<Vec<policy_store::RevisionSummary>>, StatusCode> {
+        reloader.policy_store().revisions().map(Json).map_err(|e| {
+            tracing::error!("Failed to list policy revisions: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+    }
+
+    async fn get_policy_revision(
+        State((_, reloader)): State<PolicyStoreState>,
+        Path(revision): Path<u64>,
+    ) -> Result<Json<policy_store::PolicyRevision>, StatusCode> {
+        match reloader.policy_store().revision(revision) {
+            Ok(Some(r)) => Ok(Json(r)),
+            Ok(None) => Err(StatusCode::NOT_FOUND),
+            Err(e) => {
+                tracing::error!("Failed to read policy revision {}: {}", revision, e);
+                Err(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    struct DiffQuery {
+        /// Revision to compare against; defaults to the one before
+        against: Option<u64>,
+    }
+
+    async fn diff_policy_revision(
+        State((_, reloader)): State<PolicyStoreState>,
+        Path(revision): Path<u64>,
+        Query(query): Query<DiffQuery>,
+    ) -> Result<Json<serde_json::Value>, StatusCode> {
+        let against = query.against.unwrap_or(revision.saturating_sub(1));
+        let load = |n: u64| match reloader.policy_store().revision(n) {
+            Ok(Some(r)) => serde_json::to_value(r.policies).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR),
+            Ok(None) => Err(StatusCode::NOT_FOUND),
+            Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+        };
+        let after = load(revision)?;
+        // Revision 1 diffs against an empty document
+        let before = if against == 0 { json!({}) } else { load(against)? };
+        Ok(Json(json!({
+            "from": against,
+            "to": revision,
+            "changes": policy_store::diff_json(&before, &after)
+        })))
+    }
+
+    async fn rollback_policies(
+        State((_, reloader)): State<PolicyStoreState>,
+        Path(revision): Path<u64>,
+        principal: Option<axum::Extension<auth::Principal>>,
+    ) -> (StatusCode, Json<serde_json::Value>) {
+        let author = resolve_caller(principal.as_ref()).unwrap_or_else(|| "anonymous".to_string());
+        match reloader.rollback_policies(revision, &author).await {
+            Ok(Some(rolled)) => {
+                tracing::info!("Policies rolled back to revision {} as revision {}", revision, rolled.revision);
+                (StatusCode::OK, Json(json!({ "success": true, "revision": rolled.revision })))
+            }
+            Ok(None) => (StatusCode::NOT_FOUND, Json(json!({ "success": false, "errors": ["no such revision"] }))),
+            Err(e) if e.is::<policies::PolicyValidation>() => {
+                let validation = e.downcast_ref::<policies::PolicyValidation>().unwrap();
+                tracing::warn!("Rejected rollback to revision {}: {:?}", revision, validation.errors);
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(json!({ "success": false, "errors": validation.errors, "warnings": validation.warnings })),
+                )
+            }
+            Err(e) => {
+                tracing::error!("Policy rollback failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "success": false, "errors": [e.to_string()] })))
+            }
         }
-        
-        // Update in-memory
-        *policies.write().await = new_policies;
-        tracing::info!("Policies updated successfully");
-        
-        Ok(Json(json!({ "success": true })))
     }
 
     // Virtual connector handlers
@@ -1327,12 +2152,33 @@ MIT
         registry_clone,
         connections_gauge,
         tools_gauge,
-        context_engine_gauge
+        context_engine_gauge,
+        context_engine,
+        shadow_gauge
     );
     
-    let executor_state = (state.clone(), tool_executor.clone());
-    let policies_state = policies.clone();
+    let executor_state = (
+        tool_executor.clone(),
+        observability.clone(),
+        policies.clone(),
+        audit_log.clone(),
+        default_role.clone(),
+        quotas.clone(),
+        approval_queue.clone(),
+    );
+    let tools_state = (state.clone(), policies.clone(), default_role.clone());
+    let tenants_state = (policies.clone(), reloader.clone(), quotas);
+    let approvals_state = (policies.clone(), approval_queue);
+    let policy_store_state = (policies.clone(), reloader);
+    let evaluate_state = (tool_executor.clone(), policies.clone(), audit_log.clone(), default_role.clone());
     let vc_state = virtual_connector.clone();
+    // Only listed origins may call the API from a browser; same-origin pages need no entry
+    let cors_origins: Vec<HeaderValue> = api_auth
+        .allowed_origins()
+        .iter()
+        .filter_map(|origin| HeaderValue::from_str(origin).ok())
+        .collect();
+    let auth_state = (api_auth, policies.clone());
     
     // Check runtime availability
     let native_available = which::which("node").is_ok();
@@ -1359,7 +2205,7 @@ MIT
         .route("/api/connector/virtual/disconnect", post(virtual_disconnect).with_state(vc_state))
         // Tools & Execution
         .route("/api/status", get(get_status).with_state(status_state))
-        .route("/api/tools", get(get_tools).post(create_tool))
+        .route("/api/tools", get(get_tools).with_state(tools_state).post(create_tool))
         .route("/api/tool-manifests", get(get_tool_manifests))
         .route("/api/plugins", get(get_plugins))
         .route("/api/plugins/create", post(create_plugin))
@@ -1373,13 +2219,34 @@ MIT
         .route("/api/connections", post(register_connection))
         .route("/api/connections/:id", axum::routing::delete(disconnect))
         .route("/api/connections/:id/heartbeat", post(heartbeat))
+        // Audit
+        .route("/api/audit/verify", get(verify_audit).with_state(audit_log.clone()))
+        .route("/api/audit/export", get(export_audit).with_state(audit_log))
         // Policies
-        .route("/api/policies", get(get_policies).post(update_policies).with_state(policies_state))
+        .route("/api/policies", get(get_policies).post(update_policies).with_state(policy_store_state.clone()))
+        .route("/api/policies/revisions", get(list_policy_revisions).with_state(policy_store_state.clone()))
+        .route("/api/policies/revisions/:rev", get(get_policy_revision).with_state(policy_store_state.clone()))
+        .route("/api/policies/revisions/:rev/diff", get(diff_policy_revision).with_state(policy_store_state.clone()))
+        .route("/api/policies/revisions/:rev/rollback", post(rollback_policies).with_state(policy_store_state))
+        .route("/api/policies/evaluate", post(evaluate_policies).with_state(evaluate_state))
+        // Tenants
+        .route("/api/tenants", get(list_tenants).with_state(tenants_state.clone()))
+        .route(
+            "/api/tenants/:id",
+            get(get_tenant).put(put_tenant).delete(delete_tenant).with_state(tenants_state),
+        )
+        // Approvals
+        .route("/api/approvals", get(list_approvals).with_state(approvals_state.clone()))
+        .route("/api/approvals/stream", get(stream_approvals).with_state(approvals_state.clone()))
+        .route("/api/approvals/:id", get(get_approval).with_state(approvals_state.clone()))
+        .route("/api/approvals/:id/approve", post(approve_request).with_state(approvals_state.clone()))
+        .route("/api/approvals/:id/reject", post(reject_request).with_state(approvals_state))
         // Settings (port configuration)
-        .merge(settings_router(settings_state.cfg_path.clone(), settings_state))
+        .merge(settings_router(settings_state))
+        .layer(middleware::from_fn_with_state(auth_state, require_auth))
         .layer(
             CorsLayer::new()
-                .allow_origin(Any)
+                .allow_origin(AllowOrigin::list(cors_origins))
                 .allow_methods(Any)
                 .allow_headers(Any),
         )
@@ -1390,11 +2257,70 @@ MIT
         app = app.fallback_service(serve_dir);
     }
 
-    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
-    tracing::info!("API server listening on {}", addr);
+    let bound = *port.borrow_and_update();
+    let mut server = ApiServer::spawn(bind_api_listener(bound).await?, app.clone())?;
+    loop {
+        if port.changed().await.is_err() {
+            // No more port changes; serve until the server stops
+            return Ok(server.handle.await??);
+        }
+        let next = *port.borrow_and_update();
+        if next == server.port {
+            continue;
+        }
+        // Serve the new listener straight away and drain the old one beside it;
+        // a port that cannot be bound keeps the current listener
+        match bind_api_listener(next).await {
+            Ok(listener) => {
+                let previous = std::mem::replace(&mut server, ApiServer::spawn(listener, app.clone())?);
+                previous.drain(LISTENER_DRAIN);
+            }
+            Err(e) => tracing::error!("Cannot rebind API server to port {}: {}", next, e),
+        }
+    }
+}
 
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+/// How long connections to a replaced listener, such as approval streams, may keep it open
+const LISTENER_DRAIN: std::time::Duration = std::time::Duration::from_secs(30);
 
-    Ok(())
+/// API server running on one listener
+struct ApiServer {
+    port: u16,
+    handle: tokio::task::JoinHandle<std::io::Result<()>>,
+    stop: tokio::sync::oneshot::Sender<()>,
+}
+
+impl ApiServer {
+    fn spawn(listener: tokio::net::TcpListener, app: axum::Router) -> anyhow::Result<Self> {
+        let port = listener.local_addr()?.port();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+                .with_graceful_shutdown(async move {
+                    let _ = stopped.await;
+                })
+                .await
+        });
+        Ok(Self { port, handle, stop })
+    }
+
+    /// Stop accepting and let open connections finish, closing any still open after `timeout`
+    fn drain(self, timeout: std::time::Duration) {
+        let _ = self.stop.send(());
+        let mut handle = self.handle;
+        let port = self.port;
+        tokio::spawn(async move {
+            if tokio::time::timeout(timeout, &mut handle).await.is_err() {
+                tracing::warn!("Closing connections still open on port {} after {:?}", port, timeout);
+                handle.abort();
+            }
+        });
+    }
+}
+
+async fn bind_api_listener(port: u16) -> anyhow::Result<tokio::net::TcpListener> {
+    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    tracing::info!("API server listening on {}", addr);
+    Ok(listener)
 }
@@ -1,24 +1,329 @@
 use crate::types::ContextFrame;
-use std::sync::Arc;
-use std::collections::HashMap;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry};
+use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+
+/// Instrumentation scope for all spans emitted by the server
+pub const TRACER_NAME: &str = "nurones-mcp";
+
+/// W3C trace context header / field names
+pub const TRACEPARENT: &str = "traceparent";
+pub const TRACESTATE: &str = "tracestate";
+
+/// Latency buckets (seconds) for tool execution; tools range from µs reads to long scrapes
+const TOOL_LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
 
 /// Observability Service: OTel + Prometheus integration with context propagation
 pub struct ObservabilityService {
     metrics: Arc<prometheus::Registry>,
+    runtime_metrics: Arc<Metrics>,
+    recorded: Mutex<HashMap<String, prometheus::GaugeVec>>,
     active_traces: Arc<tokio::sync::RwLock<HashMap<String, TraceSpan>>>,
 }
 
+/// Prometheus metric families fed from the executor, event bus and context engine hot paths
+#[derive(Clone)]
+pub struct Metrics {
+    tool_calls: IntCounterVec,
+    tool_duration: HistogramVec,
+    tool_inflight: IntGaugeVec,
+    events_published: IntCounterVec,
+    events_duplicate: IntCounterVec,
+    engine_adjustments: IntCounterVec,
+    redactions: IntCounterVec,
+    quota_rejections: IntCounterVec,
+    approvals: IntCounterVec,
+    /// Tenants with their own label value; any other tenant is reported as `other`
+    tenants: Arc<std::sync::RwLock<HashSet<String>>>,
+}
+
+/// Tenant label for tenants not listed in `tenancy.tenants`
+pub const OTHER_TENANT: &str = "other";
+
+impl Metrics {
+    /// Create metric families and register them with `registry`
+    pub fn new(registry: &Registry) -> prometheus::Result<Self> {
+        let tool_calls = IntCounterVec::new(
+            Opts::new("mcp_tool_calls_total", "Tool executions by tool, tenant, stage, runtime and outcome"),
+            &["tool", "tenant", "stage", "runtime", "outcome"],
+        )?;
+        let tool_duration = HistogramVec::new(
+            HistogramOpts::new("mcp_tool_duration_seconds", "Tool execution latency in seconds")
+                .buckets(TOOL_LATENCY_BUCKETS.to_vec()),
+            &["tool", "tenant", "stage", "runtime", "outcome"],
+        )?;
+        let tool_inflight = IntGaugeVec::new(
+            Opts::new("mcp_tool_inflight", "Tool executions currently in progress"),
+            &["tool", "runtime"],
+        )?;
+        let events_published = IntCounterVec::new(
+            Opts::new("mcp_event_bus_published_total", "Events stored by the event bus"),
+            &["event_type", "tenant"],
+        )?;
+        let events_duplicate = IntCounterVec::new(
+            Opts::new("mcp_event_bus_duplicates_total", "Events short-circuited by correlation_id idempotency"),
+            &["event_type", "tenant"],
+        )?;
+        let engine_adjustments = IntCounterVec::new(
+            Opts::new("mcp_context_engine_adjustments_total", "Context engine adjustments (mode=applied|clamped|shadow)"),
+            &["key", "mode"],
+        )?;
+
+        let redactions = IntCounterVec::new(
+            Opts::new("mcp_redactions_total", "Secrets redacted by detector and source (tool id or log)"),
+            &["detector", "source"],
+        )?;
+        let quota_rejections = IntCounterVec::new(
+            Opts::new("mcp_tenant_quota_rejections_total", "Tool calls refused by a tenant quota (quota=rate|concurrency)"),
+            &["tenant", "quota"],
+        )?;
+        let approvals = IntCounterVec::new(
+            Opts::new("mcp_approvals_total", "Approval requests by outcome (approved|rejected|expired|cancelled)"),
+            &["outcome"],
+        )?;
+
+        registry.register(Box::new(tool_calls.clone()))?;
+        registry.register(Box::new(tool_duration.clone()))?;
+        registry.register(Box::new(tool_inflight.clone()))?;
+        registry.register(Box::new(events_published.clone()))?;
+        registry.register(Box::new(events_duplicate.clone()))?;
+        registry.register(Box::new(engine_adjustments.clone()))?;
+        registry.register(Box::new(redactions.clone()))?;
+        registry.register(Box::new(quota_rejections.clone()))?;
+        registry.register(Box::new(approvals.clone()))?;
+
+        Ok(Self {
+            tool_calls,
+            tool_duration,
+            tool_inflight,
+            events_published,
+            events_duplicate,
+            engine_adjustments,
+            redactions,
+            quota_rejections,
+            approvals,
+            tenants: Arc::default(),
+        })
+    }
+
+    /// Tenants that keep their id as a label value, usually the keys of `tenancy.tenants`.
+    /// Tenant ids come from client frames, so unlisted ones share one series.
+    pub fn set_tenants<'a>(&self, tenants: impl IntoIterator<Item = &'a String>) {
+        *self.tenants.write().unwrap() = tenants.into_iter().cloned().collect();
+    }
+
+    fn tenant_label(&self, tenant: &str) -> String {
+        if self.tenants.read().unwrap().contains(tenant) {
+            tenant.to_string()
+        } else {
+            OTHER_TENANT.to_string()
+        }
+    }
+
+    /// Mark a tool execution as started; the returned guard decrements the inflight gauge on drop
+    pub fn track_inflight(&self, tool: &str, runtime: &str) -> InflightGuard {
+        let gauge = self.tool_inflight.with_label_values(&[tool, runtime]);
+        gauge.inc();
+        InflightGuard { gauge }
+    }
+
+    /// Record a finished tool execution
+    pub fn observe_tool_call(
+        &self,
+        tool: &str,
+        context: &ContextFrame,
+        runtime: &str,
+        outcome: &str,
+        elapsed: std::time::Duration,
+    ) {
+        let tenant = self.tenant_label(&context.tenant_id);
+        let labels = [tool, tenant.as_str(), context.stage.as_str(), runtime, outcome];
+        self.tool_calls.with_label_values(&labels).inc();
+        self.tool_duration.with_label_values(&labels).observe(elapsed.as_secs_f64());
+    }
+
+    pub fn event_published(&self, event_type: &str, tenant: &str) {
+        self.events_published.with_label_values(&[event_type, self.tenant_label(tenant).as_str()]).inc();
+    }
+
+    pub fn event_duplicate(&self, event_type: &str, tenant: &str) {
+        self.events_duplicate.with_label_values(&[event_type, self.tenant_label(tenant).as_str()]).inc();
+    }
+
+    pub fn engine_adjustment(&self, key: &str, mode: &str) {
+        self.engine_adjustments.with_label_values(&[key, mode]).inc();
+    }
+
+    pub fn engine_adjustment_count(&self, key: &str, mode: &str) -> u64 {
+        self.engine_adjustments.with_label_values(&[key, mode]).get()
+    }
+
+    pub fn redaction(&self, detector: &str, source: &str, count: u64) {
+        self.redactions.with_label_values(&[detector, source]).inc_by(count);
+    }
+
+    pub fn redaction_count(&self, detector: &str, source: &str) -> u64 {
+        self.redactions.with_label_values(&[detector, source]).get()
+    }
+
+    pub fn quota_rejection(&self, tenant: &str, quota: &str) {
+        self.quota_rejections.with_label_values(&[self.tenant_label(tenant).as_str(), quota]).inc();
+    }
+
+    pub fn quota_rejection_count(&self, tenant: &str, quota: &str) -> u64 {
+        self.quota_rejections.with_label_values(&[self.tenant_label(tenant).as_str(), quota]).get()
+    }
+
+    pub fn approval_decision(&self, outcome: &str) {
+        self.approvals.with_label_values(&[outcome]).inc();
+    }
+}
+
+impl Default for Metrics {
+    /// Metrics backed by a private registry, for components used without a server
+    fn default() -> Self {
+        Self::new(&Registry::new()).expect("metric families are statically valid")
+    }
+}
+
+/// Decrements the inflight gauge when a tool execution ends
+pub struct InflightGuard {
+    gauge: prometheus::IntGauge,
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.gauge.dec();
+    }
+}
+
 #[derive(Debug, Clone)]
 struct TraceSpan {
     name: String,
     start_time: std::time::Instant,
     context: Option<ContextFrame>,
+    /// OTel context carrying the live span, used to parent child spans
+    otel: Context,
+}
+
+/// Install a global OTLP/HTTP span exporter sending to `{endpoint}/v1/traces`.
+/// Until this is called, spans go to the no-op tracer.
+pub fn init_otlp_tracing(endpoint: &str) -> anyhow::Result<()> {
+    use opentelemetry_otlp::WithExportConfig;
+
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .http()
+                .with_endpoint(endpoint.trim_end_matches('/')),
+        )
+        .with_trace_config(opentelemetry_sdk::trace::config().with_resource(
+            opentelemetry_sdk::Resource::new(vec![
+                KeyValue::new("service.name", TRACER_NAME),
+                KeyValue::new("service.version", crate::VERSION),
+            ]),
+        ))
+        .install_batch(opentelemetry_sdk::runtime::Tokio)?;
+
+    tracing::info!("OTLP trace export enabled: {}/v1/traces", endpoint.trim_end_matches('/'));
+    Ok(())
+}
+
+/// Flush pending spans and shut down the global tracer provider (blocking)
+pub fn shutdown_tracing() {
+    global::shutdown_tracer_provider();
+}
+
+/// ContextFrame fields as span attributes
+pub fn context_attributes(ctx: &ContextFrame) -> Vec<KeyValue> {
+    let mut attrs = vec![
+        KeyValue::new("mcp.reason_trace_id", ctx.reason_trace_id.clone()),
+        KeyValue::new("mcp.tenant_id", ctx.tenant_id.clone()),
+        KeyValue::new("mcp.stage", ctx.stage.as_str()),
+        KeyValue::new("mcp.risk_level", ctx.risk_level as i64),
+    ];
+    if let Some(score) = ctx.novelty_score {
+        attrs.push(KeyValue::new("mcp.novelty_score", score));
+    }
+    if let Some(conf) = ctx.context_confidence {
+        attrs.push(KeyValue::new("mcp.context_confidence", conf));
+    }
+    if let Some(flags) = &ctx.flags {
+        attrs.push(KeyValue::new("mcp.flags.read_only", flags.read_only));
+        attrs.push(KeyValue::new("mcp.flags.allow_autotune", flags.allow_autotune));
+        attrs.push(KeyValue::new("mcp.flags.dry_run", flags.dry_run));
+    }
+    attrs
+}
+
+/// Start a span as a child of the current OTel context, for WASI and subprocess runs.
+/// The span ends when dropped.
+pub fn child_span(name: &'static str, attributes: Vec<KeyValue>) -> global::BoxedSpan {
+    let tracer = global::tracer(TRACER_NAME);
+    tracer
+        .span_builder(name)
+        .with_kind(SpanKind::Internal)
+        .with_attributes(attributes)
+        .start_with_context(&tracer, &Context::current())
+}
+
+/// Parse W3C `traceparent`/`tracestate` into a remote parent context.
+/// Returns `None` when the header is missing or malformed.
+pub fn extract_trace_context(traceparent: &str, tracestate: Option<&str>) -> Option<Context> {
+    let mut carrier = HashMap::new();
+    carrier.insert(TRACEPARENT.to_string(), traceparent.to_string());
+    if let Some(state) = tracestate {
+        carrier.insert(TRACESTATE.to_string(), state.to_string());
+    }
+    let cx = TraceContextPropagator::new().extract_with_context(&Context::new(), &carrier);
+    cx.span().span_context().is_valid().then_some(cx)
+}
+
+/// Serialize the span in `cx` as W3C `traceparent` (+ `tracestate` when non-empty).
+/// Empty when `cx` carries no valid span.
+pub fn inject_trace_context(cx: &Context) -> HashMap<String, String> {
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(cx, &mut carrier);
+    carrier.retain(|_, v| !v.is_empty());
+    carrier
+}
+
+/// Parent for spans started on behalf of `ctx`: the active span if there is one,
+/// otherwise the caller's `traceparent` from the frame
+pub fn parent_context(ctx: Option<&ContextFrame>) -> Context {
+    let current = Context::current();
+    if current.span().span_context().is_valid() {
+        return current;
+    }
+    ctx.and_then(|c| {
+        c.traceparent
+            .as_deref()
+            .and_then(|tp| extract_trace_context(tp, c.tracestate.as_deref()))
+    })
+    .unwrap_or(current)
+}
+
+impl Default for ObservabilityService {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ObservabilityService {
     pub fn new() -> Self {
+        let registry = prometheus::Registry::new();
+        let runtime_metrics = Metrics::new(&registry).expect("metric families are statically valid");
         Self {
-            metrics: Arc::new(prometheus::Registry::new()),
+            metrics: Arc::new(registry),
+            runtime_metrics: Arc::new(runtime_metrics),
+            recorded: Mutex::new(HashMap::new()),
             active_traces: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
         }
     }
@@ -39,18 +344,58 @@ impl ObservabilityService {
             "Recording metric"
         );
 
-        // In production, this would push to Prometheus
-        // For now, we log it
+        let tags = tags.unwrap_or_default();
+        let mut label_names: Vec<&str> = tags.keys().map(|k| k.as_str()).collect();
+        label_names.sort_unstable();
+        let label_values: Vec<&str> = label_names.iter().map(|k| tags[*k].as_str()).collect();
+
+        let name = sanitize_metric_name(metric);
+        let mut recorded = self.recorded.lock().unwrap();
+        let gauge = match recorded.get(&name) {
+            Some(gauge) => gauge.clone(),
+            None => {
+                let gauge = match prometheus::GaugeVec::new(
+                    Opts::new(name.clone(), format!("Recorded metric {}", metric)),
+                    &label_names,
+                ) {
+                    Ok(gauge) => gauge,
+                    Err(e) => {
+                        tracing::warn!("Cannot create metric '{}': {}", name, e);
+                        return;
+                    }
+                };
+                if let Err(e) = self.metrics.register(Box::new(gauge.clone())) {
+                    tracing::warn!("Cannot register metric '{}': {}", name, e);
+                    return;
+                }
+                recorded.insert(name.clone(), gauge.clone());
+                gauge
+            }
+        };
+
+        // Label sets are fixed on first use; mismatched tags are dropped rather than panicking
+        match gauge.get_metric_with_label_values(&label_values) {
+            Ok(g) => g.set(value),
+            Err(e) => tracing::warn!("Metric '{}' recorded with inconsistent tags: {}", name, e),
+        }
     }
 
     /// Start a new trace span
     pub async fn start_trace(&self, name: &str, context: Option<ContextFrame>) -> String {
         let trace_id = uuid::Uuid::new_v4().to_string();
+
+        let tracer = global::tracer(TRACER_NAME);
+        let otel_span = tracer
+            .span_builder(name.to_string())
+            .with_kind(SpanKind::Server)
+            .with_attributes(context.as_ref().map(context_attributes).unwrap_or_default())
+            .start_with_context(&tracer, &parent_context(context.as_ref()));
         
         let span = TraceSpan {
             name: name.to_string(),
             start_time: std::time::Instant::now(),
             context: context.clone(),
+            otel: Context::current_with_span(otel_span),
         };
 
         let mut traces = self.active_traces.write().await;
@@ -82,15 +427,31 @@ impl ObservabilityService {
                 "Ended trace"
             );
 
-            // In production, export to OTel collector
+            let otel_span = span.otel.span();
+            otel_span.set_status(match status {
+                TraceStatus::Ok => Status::Ok,
+                TraceStatus::Error => Status::error("request failed"),
+            });
+            otel_span.end();
         }
     }
 
+    /// OTel context of an active trace, for parenting work done on its behalf
+    pub async fn trace_context(&self, trace_id: &str) -> Option<Context> {
+        let traces = self.active_traces.read().await;
+        traces.get(trace_id).map(|span| span.otel.clone())
+    }
+
     /// Get Prometheus registry for metrics export
     pub fn registry(&self) -> Arc<prometheus::Registry> {
         Arc::clone(&self.metrics)
     }
 
+    /// Get the hot-path metric families registered in this service's registry
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.runtime_metrics)
+    }
+
     /// Get active traces snapshot
     pub async fn active_traces(&self) -> Vec<String> {
         let traces = self.active_traces.read().await;
@@ -98,6 +459,18 @@ impl ObservabilityService {
     }
 }
 
+/// Map an arbitrary metric name onto the Prometheus `[a-zA-Z_:][a-zA-Z0-9_:]*` charset
+fn sanitize_metric_name(name: &str) -> String {
+    let mut out: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == ':' { c } else { '_' })
+        .collect();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum TraceStatus {
     Ok,
@@ -119,12 +492,31 @@ mod tests {
         let active = service.active_traces().await;
         assert_eq!(active.len(), 1);
 
+        assert!(service.trace_context(&trace_id).await.is_some());
+
         service.end_trace(&trace_id, TraceStatus::Ok).await;
 
         let active = service.active_traces().await;
         assert_eq!(active.len(), 0);
     }
 
+    #[test]
+    fn test_context_attributes() {
+        let ctx = ContextFrame::default();
+        let attrs = context_attributes(&ctx);
+        let get = |key: &str| {
+            attrs
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.to_string())
+        };
+
+        assert_eq!(get("mcp.tenant_id").as_deref(), Some("default"));
+        assert_eq!(get("mcp.stage").as_deref(), Some("dev"));
+        assert_eq!(get("mcp.risk_level").as_deref(), Some("0"));
+        assert!(get("mcp.novelty_score").is_none());
+    }
+
     #[test]
     fn test_metric_recording() {
         let service = ObservabilityService::new();
@@ -134,6 +526,66 @@ mod tests {
         tags.insert("environment".to_string(), "test".to_string());
 
         service.record("test_metric", 42.0, Some(tags), Some(ctx));
-        // Should not panic
+
+        let families = service.registry().gather();
+        let family = families.iter().find(|f| f.get_name() == "test_metric").unwrap();
+        assert_eq!(family.get_metric()[0].get_gauge().get_value(), 42.0);
+    }
+
+    #[test]
+    fn test_tool_call_metrics() {
+        let service = ObservabilityService::new();
+        let metrics = service.metrics();
+        let ctx = ContextFrame::default();
+
+        {
+            let _guard = metrics.track_inflight("fs.read", "native");
+            assert_eq!(metrics.tool_inflight.with_label_values(&["fs.read", "native"]).get(), 1);
+            metrics.observe_tool_call("fs.read", &ctx, "native", "success", std::time::Duration::from_millis(3));
+        }
+        assert_eq!(metrics.tool_inflight.with_label_values(&["fs.read", "native"]).get(), 0);
+
+        let calls = metrics
+            .tool_calls
+            .with_label_values(&["fs.read", OTHER_TENANT, "dev", "native", "success"])
+            .get();
+        assert_eq!(calls, 1, "unlisted tenants share the `other` label");
+
+        metrics.set_tenants(&["default".to_string()]);
+        metrics.observe_tool_call("fs.read", &ctx, "native", "success", std::time::Duration::from_millis(3));
+        let calls = metrics
+            .tool_calls
+            .with_label_values(&["fs.read", "default", "dev", "native", "success"])
+            .get();
+        assert_eq!(calls, 1);
+        assert!(service
+            .registry()
+            .gather()
+            .iter()
+            .any(|f| f.get_name() == "mcp_tool_duration_seconds"));
+    }
+
+    #[tokio::test]
+    async fn test_traceparent_round_trip() {
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let cx = extract_trace_context(traceparent, Some("vendor=abc")).unwrap();
+        let carrier = inject_trace_context(&cx);
+        assert_eq!(carrier[TRACEPARENT], traceparent);
+        assert_eq!(carrier[TRACESTATE], "vendor=abc");
+
+        assert!(extract_trace_context("garbage", None).is_none());
+        assert!(inject_trace_context(&Context::new()).is_empty());
+
+        // A request trace joins the caller's distributed trace
+        let service = ObservabilityService::new();
+        let ctx = ContextFrame {
+            traceparent: Some(traceparent.to_string()),
+            ..ContextFrame::default()
+        };
+        let trace_id = service.start_trace("request", Some(ctx)).await;
+        let cx = service.trace_context(&trace_id).await.unwrap();
+        let out = inject_trace_context(&cx);
+        assert!(out[TRACEPARENT].starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        service.end_trace(&trace_id, TraceStatus::Ok).await;
     }
 }
@@ -1,3 +1,12 @@
+use crate::approvals::ApprovalSettings;
+use crate::audit::AuditEntry;
+use crate::egress::{host_matches, is_valid_host_pattern, EgressPolicy};
+use crate::process_sandbox::ProcessPolicy;
+use crate::redaction::RedactionConfig;
+use crate::secrets::{EnvPolicy, SecretScope};
+use crate::security::{FsAllowEntry, FsMode, FsRule, Sandbox, SandboxTrace};
+use crate::tenants::TenancyPolicy;
+use crate::types::{ContextFrame, RiskLevel, Stage};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -8,9 +17,199 @@ use std::path::Path;
 pub struct Policies {
     pub roles: HashMap<String, Vec<String>>,
     pub users: HashMap<String, String>,
-    pub fs_allowlist: Vec<String>,
+    /// Directories (full access) or `{path, mode, deny, exclude}` rules
+    pub fs_allowlist: Vec<FsAllowEntry>,
+    /// Attribute-based rules evaluated after the role grant; any matching deny wins,
+    /// and a matching `approve` rule parks an allowed call for a human decision
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<PolicyRule>,
+    /// Hosts, address ranges and limits for `http.request` and `fetch.url`
+    #[serde(default, skip_serializing_if = "EgressPolicy::is_default")]
+    pub egress: EgressPolicy,
+    /// Commands, environment, working directory and limits for `process.execute`
+    #[serde(default, skip_serializing_if = "ProcessPolicy::is_default")]
+    pub process: ProcessPolicy,
+    /// Environment variables `env.get` may return, globally, per role and per tenant
+    #[serde(default, skip_serializing_if = "EnvPolicy::is_default")]
+    pub env: EnvPolicy,
+    /// Hosts, roles and tenants each `${secret:NAME}` may be used for; unlisted secrets are never expanded
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub secret_scopes: HashMap<String, SecretScope>,
+    /// Secret detectors applied to tool results, audit entries and logs
+    #[serde(default, skip_serializing_if = "RedactionConfig::is_default")]
+    pub redaction: RedactionConfig,
+    /// Per-tenant allowlists, enabled tools, rules and quotas
+    #[serde(default, skip_serializing_if = "TenancyPolicy::is_default")]
+    pub tenancy: TenancyPolicy,
+    /// How long calls wait for approval and who may decide
+    #[serde(default, skip_serializing_if = "ApprovalSettings::is_default")]
+    pub approval: ApprovalSettings,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Allow,
+    Deny,
+    /// Allowed calls wait for a human decision
+    Approve,
+}
+
+/// Rule over the caller, tool, ContextFrame and input.
+/// Empty filters match anything; every condition present must hold.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub effect: Effect,
+    /// Tool patterns (`*`, `fs.*`, `fs.read`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+    #[serde(default)]
+    pub when: RuleConditions,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleConditions {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<Stage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tenants: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_risk_level: Option<RiskLevel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_risk_level: Option<RiskLevel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    /// Matches tools whose manifest carries any of these permission tags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input: Vec<InputCondition>,
+}
+
+/// Condition on one input field, addressed by key or JSON pointer (`/headers/host`)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputCondition {
+    pub field: String,
+    /// Path value lies under this prefix (per component)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+    /// URL value's host equals this, or ends with it for `*.example.com`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url_host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<serde_json::Value>,
+}
+
+/// Everything a policy decision can depend on
+pub struct PolicyRequest<'a> {
+    pub user: &'a str,
+    /// Role for users not listed in `users`
+    pub default_role: Option<&'a str>,
+    pub tool: &'a str,
+    /// Permission tags from the tool manifest
+    pub tool_permissions: &'a [String],
+    pub input: &'a serde_json::Value,
+    pub context: &'a ContextFrame,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RuleMatch {
+    /// Rule id, or `role:<name>` for the role grant
+    pub rule: String,
+    pub effect: Effect,
+    pub explanation: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PolicyDecision {
+    pub allowed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    pub matched: Vec<RuleMatch>,
+    /// The match that decided the outcome, in words
+    pub explanation: String,
+    /// An `approve` rule matched, so an allowed call waits for a human decision
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub requires_approval: bool,
+}
+
+impl PolicyDecision {
+    /// Ids of the `approve` rules that matched
+    pub fn approval_rules(&self) -> Vec<String> {
+        self.matched
+            .iter()
+            .filter(|m| m.effect == Effect::Approve)
+            .map(|m| m.rule.clone())
+            .collect()
+    }
+
+    /// Deny the call for a reason found outside the rules, recorded as a matched deny
+    pub fn refuse(&mut self, rule: String, reason: String) {
+        self.allowed = false;
+        self.requires_approval = false;
+        self.explanation = format!("{}: {}", rule, reason);
+        self.matched.push(RuleMatch {
+            rule,
+            effect: Effect::Deny,
+            explanation: reason,
+        });
+    }
+}
+
+/// Decision plus the allowlist resolution of any filesystem paths in the input
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PolicyExplanation {
+    /// Policy allows the call and every traced path is accessible
+    pub allowed: bool,
+    pub decision: PolicyDecision,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fs: Vec<SandboxTrace>,
+}
+
+/// Audited call whose outcome differs between two policy documents
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DecisionChange {
+    pub seq: u64,
+    pub ts: chrono::DateTime<chrono::Utc>,
+    pub actor: String,
+    pub tool: String,
+    pub current: bool,
+    pub candidate: bool,
+    pub explanation: String,
+}
+
+/// Outcome of [`Policies::validate`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PolicyValidation {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl PolicyValidation {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl std::fmt::Display for PolicyValidation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid policies: {}", self.errors.join("; "))
+    }
+}
+
+/// Lets a failed validation travel as an error and be told apart by callers
+impl std::error::Error for PolicyValidation {}
+
+/// Input fields of `fs.*` tools that name paths
+const FS_PATH_FIELDS: &[&str] = &["path", "destination"];
+
 impl Default for Policies {
     fn default() -> Self {
         let mut roles = HashMap::new();
@@ -18,14 +217,47 @@ impl Default for Policies {
         roles.insert("operator".to_string(), vec!["fs.read".to_string(), "fs.list".to_string()]);
         roles.insert("reader".to_string(), vec!["fs.read".to_string()]);
 
+        // No user is admin until one is mapped explicitly
         let mut users = HashMap::new();
-        users.insert("local:dev".to_string(), "admin".to_string());
         users.insert("guest".to_string(), "reader".to_string());
 
         Self {
             roles,
             users,
-            fs_allowlist: vec!["/workspace".to_string(), "/tmp".to_string()],
+            fs_allowlist: vec!["/workspace".into(), "/tmp".into()],
+            rules: vec![
+                PolicyRule {
+                    id: "caution-approval".to_string(),
+                    description: Some("caution-level calls need a human decision".to_string()),
+                    effect: Effect::Approve,
+                    tools: Vec::new(),
+                    roles: Vec::new(),
+                    users: Vec::new(),
+                    when: RuleConditions {
+                        min_risk_level: Some(RiskLevel::Caution),
+                        ..RuleConditions::default()
+                    },
+                },
+                PolicyRule {
+                    id: "delete-approval".to_string(),
+                    description: Some("tools that delete need a human decision".to_string()),
+                    effect: Effect::Approve,
+                    tools: Vec::new(),
+                    roles: Vec::new(),
+                    users: Vec::new(),
+                    when: RuleConditions {
+                        permissions: vec!["delete".to_string()],
+                        ..RuleConditions::default()
+                    },
+                },
+            ],
+            egress: EgressPolicy::default(),
+            process: ProcessPolicy::default(),
+            env: EnvPolicy::default(),
+            secret_scopes: HashMap::new(),
+            redaction: RedactionConfig::default(),
+            tenancy: TenancyPolicy::default(),
+            approval: ApprovalSettings::default(),
         }
     }
 }
@@ -43,18 +275,144 @@ impl Policies {
         Ok(policies)
     }
 
-    /// Save policies to JSON file
+    /// Save policies to JSON file, replacing it atomically
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let content = serde_json::to_string_pretty(self)
             .with_context(|| "Failed to serialize policies")?;
         
-        fs::write(&path, content)
+        write_atomic(path.as_ref(), content.as_bytes())
             .with_context(|| format!("Failed to write policies to {:?}", path.as_ref()))?;
         
         tracing::info!("Saved policies to {:?}", path.as_ref());
         Ok(())
     }
 
+    /// Problems that would make the document misbehave. `errors` reject an update;
+    /// `warnings` (e.g. allowlist roots that do not exist yet) are reported only.
+    pub fn validate(&self) -> PolicyValidation {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+
+        for (user, role) in &self.users {
+            if !self.roles.contains_key(role) {
+                errors.push(format!("user '{}' has undefined role '{}'", user, role));
+            }
+        }
+        for (role, patterns) in &self.roles {
+            for pattern in patterns.iter().filter(|p| !is_valid_tool_pattern(p)) {
+                errors.push(format!("role '{}': invalid tool pattern '{}'", role, pattern));
+            }
+        }
+
+        errors.extend(self.validate_rules(&self.rules));
+
+        let mut roots = std::collections::HashSet::new();
+        for rule in self.fs_rules() {
+            let path = Path::new(&rule.path);
+            if !path.is_absolute() {
+                errors.push(format!("fs_allowlist '{}' is not absolute", rule.path));
+                continue;
+            }
+            let root = match crate::security::canonicalize_lenient(path) {
+                Ok(root) => root,
+                Err(e) => {
+                    errors.push(format!("fs_allowlist '{}' cannot be resolved: {}", rule.path, e));
+                    continue;
+                }
+            };
+            if !root.exists() {
+                warnings.push(format!("fs_allowlist '{}' does not exist", rule.path));
+            }
+            if !roots.insert(root.clone()) {
+                warnings.push(format!("fs_allowlist '{}' is listed more than once", rule.path));
+            }
+            for deny in &rule.deny {
+                let denied = crate::security::canonicalize_lenient(&root.join(deny));
+                if !denied.is_ok_and(|d| d.starts_with(&root)) {
+                    errors.push(format!("fs_allowlist '{}': deny '{}' is outside the entry", rule.path, deny));
+                }
+            }
+            for pattern in &rule.exclude {
+                if let Err(e) = glob::Pattern::new(pattern) {
+                    errors.push(format!("fs_allowlist '{}': bad exclude '{}': {}", rule.path, pattern, e));
+                }
+            }
+        }
+
+        errors.extend(self.egress.validate());
+        errors.extend(self.process.validate());
+        errors.extend(self.env.validate());
+        for role in self.env.roles.keys().filter(|r| !self.roles.contains_key(*r)) {
+            errors.push(format!("env references undefined role '{}'", role));
+        }
+        errors.extend(crate::secrets::validate_scopes(&self.secret_scopes, |role| self.roles.contains_key(role)));
+        errors.extend(self.redaction.validate());
+        for pattern in self.redaction.skip_tools.iter().filter(|p| !is_valid_tool_pattern(p)) {
+            errors.push(format!("redaction: invalid tool pattern '{}'", pattern));
+        }
+
+        errors.extend(self.approval.validate());
+        errors.extend(self.tenancy.validate());
+        let overlays = std::iter::once(("defaults".to_string(), &self.tenancy.defaults)).chain(
+            self.tenancy.tenants.iter().map(|(id, o)| (format!("tenant '{}'", id), o)),
+        );
+        for (scope, overlay) in overlays {
+            for pattern in overlay.tools.iter().flatten().filter(|p| !is_valid_tool_pattern(p)) {
+                errors.push(format!("tenancy {}: invalid tool pattern '{}'", scope, pattern));
+            }
+            for error in self.validate_rules(&overlay.rules) {
+                errors.push(format!("tenancy {}: {}", scope, error));
+            }
+        }
+
+        PolicyValidation { errors, warnings }
+    }
+
+    /// Problems in one rule list: ids, tool and host patterns, roles, risk ranges
+    fn validate_rules(&self, rules: &[PolicyRule]) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut ids = std::collections::HashSet::new();
+        for rule in rules {
+            if rule.id.is_empty() {
+                errors.push("rule with empty id".to_string());
+            } else if !ids.insert(rule.id.as_str()) {
+                errors.push(format!("duplicate rule id '{}'", rule.id));
+            }
+            for pattern in rule.tools.iter().filter(|p| !is_valid_tool_pattern(p)) {
+                errors.push(format!("rule '{}': invalid tool pattern '{}'", rule.id, pattern));
+            }
+            for role in rule.roles.iter().filter(|r| !self.roles.contains_key(*r)) {
+                errors.push(format!("rule '{}' references undefined role '{}'", rule.id, role));
+            }
+            if let (Some(min), Some(max)) = (rule.when.min_risk_level, rule.when.max_risk_level) {
+                if min > max {
+                    errors.push(format!("rule '{}': min_risk_level above max_risk_level", rule.id));
+                }
+            }
+            for condition in &rule.when.input {
+                if condition.field.is_empty() {
+                    errors.push(format!("rule '{}': input condition without a field", rule.id));
+                }
+                if let Some(host) = &condition.url_host {
+                    if !is_valid_host_pattern(host) {
+                        errors.push(format!("rule '{}': invalid url_host pattern '{}'", rule.id, host));
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// Allowlist entries as sandbox rules
+    pub fn fs_rules(&self) -> Vec<FsRule> {
+        self.fs_allowlist.iter().map(FsAllowEntry::to_rule).collect()
+    }
+
+    /// The tenant's overlay allowlist, or the shared one
+    pub fn fs_rules_for(&self, tenant_id: &str) -> Vec<FsRule> {
+        self.tenancy.fs_rules(tenant_id).unwrap_or_else(|| self.fs_rules())
+    }
+
     /// Check if a user is allowed to execute a specific tool
     pub fn is_tool_allowed(&self, user: &str, tool: &str) -> bool {
         // Get user's role
@@ -66,39 +424,354 @@ impl Policies {
             }
         };
 
-        // Get role's allowed tools
-        let allowed_tools = match self.roles.get(role) {
-            Some(t) => t,
+        if self.role_grants(role, tool) {
+            return true;
+        }
+
+        tracing::warn!(
+            "User '{}' (role: '{}') not allowed to execute tool '{}'",
+            user, role, tool
+        );
+        false
+    }
+
+    /// Whether a role's tool patterns cover `tool`
+    pub fn role_grants(&self, role: &str, tool: &str) -> bool {
+        match self.roles.get(role) {
+            Some(allowed_tools) => allowed_tools.iter().any(|pattern| tool_matches(pattern, tool)),
             None => {
                 tracing::warn!("Role '{}' not found in policies, denying access", role);
-                return false;
+                false
             }
-        };
+        }
+    }
 
-        // Check if tool is allowed (supports wildcard)
-        if allowed_tools.contains(&"*".to_string()) {
+    /// Whether a caller could use `tool` at all: its role grants it or an allow rule
+    /// names it for them. Request-specific conditions are not considered, so
+    /// `evaluate` may still deny an individual call.
+    pub fn may_use(&self, user: Option<&str>, default_role: Option<&str>, tool: &str) -> bool {
+        let role = user
+            .and_then(|u| self.users.get(u).map(String::as_str))
+            .or(default_role);
+        if role.is_some_and(|r| self.roles.get(r).is_some_and(|t| t.iter().any(|p| tool_matches(p, tool)))) {
             return true;
         }
+        self.rules.iter().any(|rule| {
+            rule.effect == Effect::Allow
+                && (rule.tools.is_empty() || rule.tools.iter().any(|p| tool_matches(p, tool)))
+                && (rule.users.is_empty() || user.is_some_and(|u| rule.users.iter().any(|x| x == u)))
+                && (rule.roles.is_empty() || role.is_some_and(|r| rule.roles.iter().any(|x| x == r)))
+        })
+    }
+
+    /// Decide a call: the role grant or a matching allow rule admits it, and any
+    /// matching deny rule (or the tenant's overlay refusing it) refuses it. A matching
+    /// approve rule leaves the outcome alone but holds an admitted call for approval.
+    pub fn evaluate(&self, request: &PolicyRequest) -> PolicyDecision {
+        let role = self
+            .users
+            .get(request.user)
+            .cloned()
+            .or_else(|| request.default_role.map(str::to_string));
+        let mut matched = Vec::new();
+
+        let role_grant = role.as_deref().is_some_and(|r| self.role_grants(r, request.tool));
+        if let (Some(r), true) = (&role, role_grant) {
+            matched.push(RuleMatch {
+                rule: format!("role:{}", r),
+                effect: Effect::Allow,
+                explanation: format!("role '{}' grants '{}'", r, request.tool),
+            });
+        }
 
-        if allowed_tools.contains(&tool.to_string()) {
-            return true;
+        let tenant_id = request.context.tenant_id.as_str();
+        if let Some(refusal) = self.tenancy.refusal(tenant_id, request.tool) {
+            matched.push(RuleMatch {
+                rule: format!("tenant:{}", tenant_id),
+                effect: Effect::Deny,
+                explanation: refusal,
+            });
         }
 
-        // Check prefix matching (e.g., "fs.*" allows all fs tools)
-        for allowed in allowed_tools {
-            if allowed.ends_with(".*") {
-                let prefix = allowed.trim_end_matches(".*");
-                if tool.starts_with(prefix) {
-                    return true;
-                }
+        let tenant_rules = self.tenancy.resolve(tenant_id).rules;
+        let paths = PathResolver {
+            policies: self,
+            tenant_id,
+            sandbox: std::cell::OnceCell::new(),
+        };
+        for rule in self.rules.iter().chain(&tenant_rules) {
+            if let Some(conditions) = rule.matches(request, role.as_deref(), &paths) {
+                matched.push(RuleMatch {
+                    rule: rule.id.clone(),
+                    effect: rule.effect,
+                    explanation: rule.explain(&conditions),
+                });
             }
         }
 
-        tracing::warn!(
-            "User '{}' (role: '{}') not allowed to execute tool '{}'",
-            user, role, tool
-        );
-        false
+        let decisive = matched
+            .iter()
+            .find(|m| m.effect == Effect::Deny)
+            .or_else(|| matched.iter().find(|m| m.effect == Effect::Allow));
+        let (allowed, explanation) = match decisive {
+            Some(m) => (m.effect == Effect::Allow, format!("{}: {}", m.rule, m.explanation)),
+            None => (
+                false,
+                match &role {
+                    Some(r) => format!("role '{}' does not grant '{}' and no rule allows it", r, request.tool),
+                    None => format!("user '{}' has no role and no rule allows '{}'", request.user, request.tool),
+                },
+            ),
+        };
+
+        PolicyDecision {
+            allowed,
+            role,
+            requires_approval: allowed && matched.iter().any(|m| m.effect == Effect::Approve),
+            matched,
+            explanation,
+        }
+    }
+
+    /// `evaluate`, plus the trace of each `fs.*` path through this document's allowlist
+    pub fn explain(&self, request: &PolicyRequest) -> PolicyExplanation {
+        let decision = self.evaluate(request);
+        let fs: Vec<SandboxTrace> = if request.tool.starts_with("fs.") {
+            let sandbox = Sandbox::from_rules(&self.fs_rules_for(&request.context.tenant_id));
+            let access = FsMode::for_tool(request.tool);
+            FS_PATH_FIELDS
+                .iter()
+                .filter_map(|field| request.input.get(field)?.as_str())
+                .map(|path| sandbox.explain(path, access))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        PolicyExplanation {
+            allowed: decision.allowed && fs.iter().all(|t| t.allowed),
+            decision,
+            fs,
+        }
+    }
+
+    /// Replay audited calls under `self` and `candidate`, returning those whose
+    /// outcome would change. Inputs are the audit log's redacted summaries.
+    pub fn diff_decisions(
+        &self,
+        candidate: &Policies,
+        entries: &[AuditEntry],
+        default_role: Option<&str>,
+        tool_permissions: impl Fn(&str) -> Vec<String>,
+    ) -> Vec<DecisionChange> {
+        entries
+            .iter()
+            .filter_map(|entry| {
+                let permissions = tool_permissions(&entry.tool);
+                let request = PolicyRequest {
+                    user: &entry.actor,
+                    default_role,
+                    tool: &entry.tool,
+                    tool_permissions: &permissions,
+                    input: &entry.input,
+                    context: &entry.context,
+                };
+                let current = self.explain(&request);
+                let proposed = candidate.explain(&request);
+                (current.allowed != proposed.allowed).then(|| DecisionChange {
+                    seq: entry.seq,
+                    ts: entry.ts,
+                    actor: entry.actor.clone(),
+                    tool: entry.tool.clone(),
+                    current: current.allowed,
+                    candidate: proposed.allowed,
+                    explanation: if proposed.decision.allowed {
+                        proposed
+                            .fs
+                            .iter()
+                            .find(|t| !t.allowed)
+                            .map(|t| format!("{}: {}", t.path, t.explanation))
+                            .unwrap_or(proposed.decision.explanation)
+                    } else {
+                        proposed.decision.explanation
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+/// Patterns `tool_matches` understands: `*`, a name, or `prefix.*`
+fn is_valid_tool_pattern(pattern: &str) -> bool {
+    let name = pattern.strip_suffix(".*").unwrap_or(pattern);
+    pattern == "*" || (!name.is_empty() && !name.contains('*'))
+}
+
+/// Write via a temporary sibling and rename, so readers never see a partial file.
+/// Each call gets its own temporary name, so concurrent saves cannot interleave.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    use std::io::Write;
+    let name = path.file_name().context("path has no file name")?.to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4().simple()));
+    let written = (|| -> Result<()> {
+        let mut file = fs::OpenOptions::new().write(true).create_new(true).open(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    written?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        // Persist the rename itself; not every platform can open directories
+        if let Ok(dir) = fs::File::open(parent) {
+            dir.sync_all().ok();
+        }
+    }
+    Ok(())
+}
+
+/// Tool pattern match: `*`, exact name, or `prefix.*`
+pub fn tool_matches(pattern: &str, tool: &str) -> bool {
+    pattern == "*"
+        || pattern == tool
+        || pattern
+            .strip_suffix(".*")
+            .is_some_and(|prefix| tool.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('.')))
+}
+
+/// Resolves `path_prefix` inputs the way the tenant's sandbox will, built on first use
+struct PathResolver<'a> {
+    policies: &'a Policies,
+    tenant_id: &'a str,
+    sandbox: std::cell::OnceCell<Sandbox>,
+}
+
+impl PathResolver<'_> {
+    fn canonical(&self, path: &str) -> Option<std::path::PathBuf> {
+        let sandbox = self
+            .sandbox
+            .get_or_init(|| Sandbox::from_rules(&self.policies.fs_rules_for(self.tenant_id)));
+        sandbox.canonicalize(path).ok()
+    }
+}
+
+impl PolicyRule {
+    /// Descriptions of the conditions that held, or `None` if the rule does not apply
+    fn matches(&self, request: &PolicyRequest, role: Option<&str>, paths: &PathResolver) -> Option<Vec<String>> {
+        let mut held = Vec::new();
+        let ctx = request.context;
+        let when = &self.when;
+
+        if !self.tools.is_empty() {
+            self.tools.iter().find(|p| tool_matches(p, request.tool))?;
+            held.push(format!("tool={}", request.tool));
+        }
+        if !self.users.is_empty() {
+            self.users.iter().find(|u| *u == request.user)?;
+            held.push(format!("user={}", request.user));
+        }
+        if !self.roles.is_empty() {
+            let role = role?;
+            self.roles.iter().find(|r| *r == role)?;
+            held.push(format!("role={}", role));
+        }
+        if !when.stages.is_empty() {
+            when.stages.iter().find(|s| **s == ctx.stage)?;
+            held.push(format!("stage={}", ctx.stage.as_str()));
+        }
+        if !when.tenants.is_empty() {
+            when.tenants.iter().find(|t| **t == ctx.tenant_id)?;
+            held.push(format!("tenant={}", ctx.tenant_id));
+        }
+        if let Some(min) = when.min_risk_level {
+            if ctx.risk_level < min {
+                return None;
+            }
+            held.push(format!("risk_level {} >= {}", ctx.risk_level as u8, min as u8));
+        }
+        if let Some(max) = when.max_risk_level {
+            if ctx.risk_level > max {
+                return None;
+            }
+            held.push(format!("risk_level {} <= {}", ctx.risk_level as u8, max as u8));
+        }
+        if let Some(read_only) = when.read_only {
+            let actual = ctx.flags.as_ref().is_some_and(|f| f.read_only);
+            if actual != read_only {
+                return None;
+            }
+            held.push(format!("read_only={}", actual));
+        }
+        if !when.permissions.is_empty() {
+            let tag = when
+                .permissions
+                .iter()
+                .find(|p| request.tool_permissions.contains(p))?;
+            held.push(format!("tool permission '{}'", tag));
+        }
+        // Deny and approve rules fail closed when a path cannot be resolved
+        let fail_closed = self.effect != Effect::Allow;
+        for condition in &when.input {
+            held.push(condition.matches(request.input, paths, fail_closed)?);
+        }
+
+        Some(held)
+    }
+
+    fn explain(&self, conditions: &[String]) -> String {
+        let effect = match self.effect {
+            Effect::Allow => "allow",
+            Effect::Deny => "deny",
+            Effect::Approve => "require approval",
+        };
+        let when = if conditions.is_empty() {
+            "always".to_string()
+        } else {
+            conditions.join(", ")
+        };
+        match &self.description {
+            Some(description) => format!("{} ({} when {})", description, effect, when),
+            None => format!("{} when {}", effect, when),
+        }
+    }
+}
+
+impl InputCondition {
+    /// Description of the match, or `None` if the field is missing or does not match.
+    /// Paths are compared after the sandbox resolves them (relative paths, `..`,
+    /// root shorthand and symlinks); with `fail_closed`, the unresolved spelling
+    /// under the prefix, or a path that cannot be resolved, also matches.
+    fn matches(&self, input: &serde_json::Value, paths: &PathResolver, fail_closed: bool) -> Option<String> {
+        let value = if self.field.starts_with('/') {
+            input.pointer(&self.field)?
+        } else {
+            input.get(&self.field)?
+        };
+
+        if let Some(prefix) = &self.path_prefix {
+            let path = value.as_str()?;
+            let field = self.field.trim_start_matches('/');
+            let Some(resolved) = paths.canonical(path) else {
+                return fail_closed.then(|| format!("input.{} '{}' cannot be resolved", field, path));
+            };
+            let canonical_prefix = crate::security::canonicalize_lenient(Path::new(prefix)).ok();
+            let under = resolved.starts_with(prefix)
+                || canonical_prefix.is_some_and(|p| resolved.starts_with(p))
+                || (fail_closed && Path::new(path).starts_with(prefix));
+            return under.then(|| format!("input.{} under {}", field, prefix));
+        }
+        if let Some(pattern) = &self.url_host {
+            let url = reqwest::Url::parse(value.as_str()?).ok()?;
+            let host = url.host_str()?.to_ascii_lowercase();
+            return host_matches(pattern, &host).then(|| format!("input.{} host {}", self.field.trim_start_matches('/'), host));
+        }
+        if let Some(expected) = &self.equals {
+            return (value == expected)
+                .then(|| format!("input.{} = {}", self.field.trim_start_matches('/'), expected));
+        }
+        Some(format!("input.{} present", self.field.trim_start_matches('/')))
     }
 }
 
@@ -106,23 +779,372 @@ impl Policies {
 mod tests {
     use super::*;
 
+    /// Default policies plus `ops` as admin
+    fn with_admin() -> Policies {
+        let mut policies = Policies::default();
+        policies.users.insert("ops".to_string(), "admin".to_string());
+        policies
+    }
+
     #[test]
     fn test_tool_permission() {
-        let policies = Policies::default();
+        // The localhost principal is not an admin by default
+        assert!(!Policies::default().is_tool_allowed("local:dev", "fs.write"));
+        let policies = with_admin();
 
         // Admin can do everything
-        assert!(policies.is_tool_allowed("local:dev", "fs.read"));
-        assert!(policies.is_tool_allowed("local:dev", "fs.write"));
-        assert!(policies.is_tool_allowed("local:dev", "db.query"));
+        assert!(policies.is_tool_allowed("ops", "fs.read"));
+        assert!(policies.is_tool_allowed("ops", "fs.write"));
+        assert!(policies.is_tool_allowed("ops", "db.query"));
 
         // Reader can only read
         assert!(policies.is_tool_allowed("guest", "fs.read"));
         assert!(!policies.is_tool_allowed("guest", "fs.write"));
     }
 
+    #[test]
+    fn test_fs_allowlist_rules() {
+        let policies: Policies = serde_json::from_str(r#"{
+            "roles": {}, "users": {},
+            "fs_allowlist": ["/tmp", {"path": "/workspace", "mode": "read", "exclude": ["**/*.pem"]}]
+        }"#).unwrap();
+        let rules = policies.fs_rules();
+        assert_eq!(rules[0], FsRule::full("/tmp"));
+        assert_eq!(rules[1].mode, crate::security::FsMode::Read);
+        assert_eq!(rules[1].exclude, vec!["**/*.pem".to_string()]);
+    }
+
+    fn request<'a>(
+        user: &'a str,
+        tool: &'a str,
+        input: &'a serde_json::Value,
+        context: &'a ContextFrame,
+        tool_permissions: &'a [String],
+    ) -> PolicyRequest<'a> {
+        PolicyRequest {
+            user,
+            default_role: None,
+            tool,
+            tool_permissions,
+            input,
+            context,
+        }
+    }
+
+    #[test]
+    fn test_attribute_rules() {
+        let policies = Policies {
+            rules: serde_json::from_value(serde_json::json!([
+                {"id": "no-exec-in-prod", "effect": "deny", "tools": ["process.execute"], "when": {"stages": ["prod"]}},
+                {"id": "safe-deletes", "description": "fs.delete requires risk_level 0", "effect": "deny",
+                 "tools": ["fs.delete"], "when": {"min_risk_level": 1}},
+                {"id": "no-etc", "effect": "deny", "when": {"input": [{"field": "path", "path_prefix": "/etc"}]}},
+                {"id": "internal-net", "effect": "deny", "when": {"permissions": ["network"],
+                 "input": [{"field": "url", "url_host": "*.internal"}]}},
+            ]))
+            .unwrap(),
+            ..with_admin()
+        };
+        let input = serde_json::json!({});
+        let none: Vec<String> = Vec::new();
+
+        let prod = ContextFrame { stage: Stage::Prod, ..ContextFrame::default() };
+        let decision = policies.evaluate(&request("ops", "process.execute", &input, &prod, &none));
+        assert!(!decision.allowed);
+        assert!(decision.explanation.starts_with("no-exec-in-prod"));
+        assert!(decision.matched.iter().any(|m| m.rule == "role:admin"));
+
+        let dev = ContextFrame::default();
+        assert!(policies.evaluate(&request("ops", "process.execute", &input, &dev, &none)).allowed);
+
+        let caution = ContextFrame { risk_level: RiskLevel::Caution, ..ContextFrame::default() };
+        let decision = policies.evaluate(&request("ops", "fs.delete", &input, &caution, &none));
+        assert!(!decision.allowed);
+        assert!(decision.explanation.contains("requires risk_level 0"));
+        assert!(policies.evaluate(&request("ops", "fs.delete", &input, &dev, &none)).allowed);
+
+        let etc = serde_json::json!({"path": "/etc/passwd"});
+        assert!(!policies.evaluate(&request("ops", "fs.read", &etc, &dev, &none)).allowed);
+        let etcetera = serde_json::json!({"path": "/etcetera/x"});
+        assert!(policies.evaluate(&request("ops", "fs.read", &etcetera, &dev, &none)).allowed);
+        let dotted = serde_json::json!({"path": "/tmp/../etc/./passwd"});
+        assert!(!policies.evaluate(&request("ops", "fs.read", &dotted, &dev, &none)).allowed);
+        #[cfg(unix)]
+        {
+            let dir = std::env::temp_dir().join(format!("mcp-policy-link-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let link = dir.join("etc-link");
+            std::os::unix::fs::symlink("/etc", &link).ok();
+            let linked = serde_json::json!({"path": link.join("passwd")});
+            assert!(!policies.evaluate(&request("ops", "fs.read", &linked, &dev, &none)).allowed);
+            std::fs::remove_dir_all(&dir).ok();
+        }
+
+        let network = vec!["network".to_string()];
+        let url = serde_json::json!({"url": "http://db.internal:5432/"});
+        assert!(!policies.evaluate(&request("ops", "http.request", &url, &dev, &network)).allowed);
+        let url = serde_json::json!({"url": "https://example.com/"});
+        assert!(policies.evaluate(&request("ops", "http.request", &url, &dev, &network)).allowed);
+    }
+
+    #[test]
+    fn test_allow_rule_and_default_role() {
+        let policies = Policies {
+            rules: serde_json::from_value(serde_json::json!([
+                {"id": "guest-telemetry", "effect": "allow", "users": ["guest"], "tools": ["telemetry.push"]}
+            ]))
+            .unwrap(),
+            ..Policies::default()
+        };
+        let input = serde_json::json!({});
+        let ctx = ContextFrame::default();
+
+        assert!(policies.evaluate(&request("guest", "telemetry.push", &input, &ctx, &[])).allowed);
+        let decision = policies.evaluate(&request("guest", "fs.write", &input, &ctx, &[]));
+        assert!(!decision.allowed);
+        assert!(decision.explanation.contains("role 'reader' does not grant"));
+
+        let mut anonymous = request("nobody", "fs.read", &input, &ctx, &[]);
+        assert!(!policies.evaluate(&anonymous).allowed);
+        anonymous.default_role = Some("operator");
+        let decision = policies.evaluate(&anonymous);
+        assert!(decision.allowed);
+        assert_eq!(decision.role.as_deref(), Some("operator"));
+    }
+
+    #[test]
+    fn test_may_use_hides_ungranted_tools() {
+        let policies = with_admin();
+        assert!(policies.may_use(Some("ops"), Some("reader"), "process.execute"));
+        assert!(!policies.may_use(Some("guest"), Some("admin"), "fs.write"));
+        // Unknown callers fall back to the default role
+        assert!(policies.may_use(None, Some("operator"), "fs.list"));
+        assert!(!policies.may_use(None, Some("operator"), "fs.write"));
+        assert!(!policies.may_use(None, None, "fs.read"));
+    }
+
+    #[tokio::test]
+    async fn test_default_policy_holds_deleting_tools() {
+        let executor = crate::tool_executor::InMemoryToolExecutor::new();
+        executor
+            .load_tools(concat!(env!("CARGO_MANIFEST_DIR"), "/../.mcp/tools"))
+            .await
+            .unwrap();
+        let policies = with_admin();
+        let ctx = ContextFrame::default();
+        let input = serde_json::json!({});
+
+        for (tool, held) in [("fs.move", true), ("fs.delete", true), ("fs.write", false), ("fs.stat", false)] {
+            let permissions = executor.manifest(tool).await.unwrap().permissions;
+            let decision = policies.evaluate(&request("ops", tool, &input, &ctx, &permissions));
+            assert!(decision.allowed, "{}", tool);
+            assert_eq!(decision.requires_approval, held, "{}", tool);
+        }
+        let permissions = executor.manifest("fs.move").await.unwrap().permissions;
+        let decision = policies.evaluate(&request("ops", "fs.move", &input, &ctx, &permissions));
+        assert_eq!(decision.approval_rules(), vec!["delete-approval"]);
+    }
+
+    #[tokio::test]
+    async fn test_explain_uses_manifest_permissions() {
+        // fs.move is only described by its manifest
+        let executor = crate::tool_executor::InMemoryToolExecutor::new();
+        executor
+            .load_tools(concat!(env!("CARGO_MANIFEST_DIR"), "/../.mcp/tools"))
+            .await
+            .unwrap();
+        let permissions = executor.manifest("fs.move").await.unwrap().permissions;
+        let policies = Policies {
+            fs_allowlist: vec!["/srv/policy-move".into()],
+            rules: serde_json::from_value(serde_json::json!([
+                {"id": "no-deletes", "effect": "deny", "when": {"permissions": ["delete"]}},
+            ]))
+            .unwrap(),
+            ..with_admin()
+        };
+        let ctx = ContextFrame::default();
+        let input = serde_json::json!({"path": "/srv/policy-move/a", "destination": "/srv/policy-move/b"});
+
+        let explanation = policies.explain(&request("ops", "fs.move", &input, &ctx, &permissions));
+        assert!(!explanation.allowed);
+        assert!(explanation.decision.matched.iter().any(|m| m.rule == "no-deletes"));
+        assert_eq!(explanation.fs.len(), 2);
+    }
+
+    #[test]
+    fn test_explain_and_diff() {
+        let current = Policies {
+            fs_allowlist: vec!["/srv/policy-explain".into()],
+            ..with_admin()
+        };
+        let candidate = Policies {
+            fs_allowlist: serde_json::from_value(serde_json::json!([
+                {"path": "/srv/policy-explain", "mode": "read"}
+            ]))
+            .unwrap(),
+            ..current.clone()
+        };
+        let ctx = ContextFrame::default();
+        let write = serde_json::json!({"path": "/srv/policy-explain/out.txt"});
+        let request = request("ops", "fs.write", &write, &ctx, &[]);
+
+        let explanation = current.explain(&request);
+        assert!(explanation.allowed);
+        assert_eq!(explanation.fs.len(), 1);
+        let explanation = candidate.explain(&request);
+        assert!(explanation.decision.allowed && !explanation.allowed);
+
+        let entry = |seq: u64, tool: &str, input: serde_json::Value| AuditEntry {
+            seq,
+            ts: chrono::Utc::now(),
+            actor: "ops".to_string(),
+            role: None,
+            tool: tool.to_string(),
+            input,
+            context: ContextFrame::default(),
+            decision: crate::audit::AuditDecision::Allow,
+            reason: None,
+            outcome: "success".to_string(),
+            error: None,
+            duration_ms: 0,
+            prev_hash: String::new(),
+            hash: String::new(),
+        };
+        let history = vec![
+            entry(0, "fs.read", serde_json::json!({"path": "/srv/policy-explain/a"})),
+            entry(1, "fs.write", write.clone()),
+        ];
+        let changes = current.diff_decisions(&candidate, &history, None, |_| Vec::new());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].seq, 1);
+        assert!(changes[0].current && !changes[0].candidate);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Policies::default().validate().is_valid());
+
+        let mut policies = Policies::default();
+        policies.users.insert("ci".to_string(), "deployer".to_string());
+        policies.roles.insert("broken".to_string(), vec!["fs.*.x".to_string(), "".to_string()]);
+        policies.fs_allowlist = serde_json::from_value(serde_json::json!([
+            "relative/dir",
+            {"path": "/srv/policy-validate", "deny": ["../../etc"], "exclude": ["[unclosed"]},
+            "/srv/policy-validate",
+        ]))
+        .unwrap();
+        policies.rules = serde_json::from_value(serde_json::json!([
+            {"id": "r", "effect": "deny", "roles": ["ghost"], "when": {"input": [{"field": "url", "url_host": "a.*.com"}]}},
+            {"id": "r", "effect": "allow"},
+        ]))
+        .unwrap();
+
+        let validation = policies.validate();
+        let errors = validation.errors.join("\n");
+        for expected in [
+            "user 'ci' has undefined role 'deployer'",
+            "invalid tool pattern 'fs.*.x'",
+            "invalid tool pattern ''",
+            "'relative/dir' is not absolute",
+            "deny '../../etc' is outside the entry",
+            "bad exclude '[unclosed'",
+            "references undefined role 'ghost'",
+            "invalid url_host pattern 'a.*.com'",
+            "duplicate rule id 'r'",
+        ] {
+            assert!(errors.contains(expected), "missing '{}' in:\n{}", expected, errors);
+        }
+        assert!(validation.warnings.iter().any(|w| w.contains("more than once")));
+        assert!(validation.warnings.iter().any(|w| w.contains("does not exist")));
+    }
+
+    #[test]
+    fn test_tenant_overlays() {
+        let policies = Policies {
+            tenancy: serde_json::from_value(serde_json::json!({
+                "defaults": {"fs_allowlist": ["/srv/tenants/{tenant}"]},
+                "tenants": {
+                    "acme": {"tools": ["fs.*"], "rules": [
+                        {"id": "acme-no-delete", "effect": "deny", "tools": ["fs.delete"]}
+                    ]},
+                    "globex": {"rules": [{"id": "bad", "effect": "deny", "roles": ["ghost"]}]}
+                }
+            }))
+            .unwrap(),
+            ..with_admin()
+        };
+        let acme = ContextFrame { tenant_id: "acme".to_string(), ..ContextFrame::default() };
+        let read = serde_json::json!({"path": "/srv/tenants/acme/a.txt"});
+
+        let decision = policies.evaluate(&request("ops", "process.execute", &read, &acme, &[]));
+        assert!(!decision.allowed);
+        assert_eq!(decision.explanation, "tenant:acme: tenant 'acme' does not enable 'process.execute'");
+        let decision = policies.evaluate(&request("ops", "fs.delete", &read, &acme, &[]));
+        assert!(decision.explanation.starts_with("acme-no-delete"));
+
+        assert!(policies.explain(&request("ops", "fs.read", &read, &acme, &[])).allowed);
+        // Another tenant's directory is outside acme's allowlist
+        let other = serde_json::json!({"path": "/srv/tenants/globex/a.txt"});
+        assert!(!policies.explain(&request("ops", "fs.read", &other, &acme, &[])).allowed);
+
+        let errors = policies.validate().errors.join("\n");
+        assert!(errors.contains("tenancy tenant 'globex': rule 'bad' references undefined role 'ghost'"), "{}", errors);
+    }
+
     #[test]
     fn test_unknown_user() {
         let policies = Policies::default();
         assert!(!policies.is_tool_allowed("unknown", "fs.read"));
     }
+
+    #[test]
+    fn test_approval_rules() {
+        let mut policies = with_admin();
+        policies.rules.push(
+            serde_json::from_value(serde_json::json!(
+                {"id": "prod-writes", "effect": "approve", "when": {"stages": ["prod"], "permissions": ["write"]}}
+            ))
+            .unwrap(),
+        );
+        let input = serde_json::json!({});
+        let none: Vec<String> = Vec::new();
+        let write = vec!["write".to_string()];
+
+        let safe = ContextFrame::default();
+        assert!(!policies.evaluate(&request("ops", "fs.write", &input, &safe, &write)).requires_approval);
+
+        let caution = ContextFrame { risk_level: RiskLevel::Caution, ..ContextFrame::default() };
+        let decision = policies.evaluate(&request("ops", "fs.read", &input, &caution, &none));
+        assert!(decision.allowed && decision.requires_approval);
+        assert_eq!(decision.approval_rules(), vec!["caution-approval"]);
+
+        let prod = ContextFrame { stage: Stage::Prod, ..ContextFrame::default() };
+        let decision = policies.evaluate(&request("ops", "fs.write", &input, &prod, &write));
+        assert_eq!(decision.approval_rules(), vec!["prod-writes"]);
+        // Refused calls are never queued
+        let decision = policies.evaluate(&request("guest", "fs.write", &input, &prod, &write));
+        assert!(!decision.allowed && !decision.requires_approval);
+    }
+
+    #[test]
+    fn test_concurrent_atomic_writes() {
+        let dir = std::env::temp_dir().join(format!("mcp-write-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("policies.json");
+
+        let writers: Vec<_> = (0..8u8)
+            .map(|n| {
+                let path = path.clone();
+                std::thread::spawn(move || write_atomic(&path, &vec![b'a' + n; 64 * 1024]).unwrap())
+            })
+            .collect();
+        writers.into_iter().for_each(|w| w.join().unwrap());
+
+        // Exactly one writer's contents, whole, and no temporary files left over
+        let written = std::fs::read(&path).unwrap();
+        assert_eq!(written.len(), 64 * 1024);
+        assert!(written.iter().all(|b| *b == written[0]));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
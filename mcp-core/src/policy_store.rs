@@ -0,0 +1,259 @@
+use crate::policies::{write_atomic, Policies};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One saved version of the policy document
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PolicyRevision {
+    pub revision: u64,
+    pub author: String,
+    pub ts: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub policies: Policies,
+}
+
+/// Revision metadata without the document, for listings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevisionSummary {
+    pub revision: u64,
+    pub author: String,
+    pub ts: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Value that differs between two documents, addressed by JSON pointer
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PolicyChange {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+/// Live policy file plus its revision history in `<name>.history/NNNNNN.json`.
+/// Callers serialize commits (the server holds the policies write lock).
+pub struct PolicyStore {
+    path: PathBuf,
+    history_dir: PathBuf,
+}
+
+impl PolicyStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let history_dir = path.with_extension("history");
+        Self { path, history_dir }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record `policies` as the next revision and atomically replace the live file.
+    /// The revision is withdrawn if the live file cannot be written, so history
+    /// never claims a document that was not in effect.
+    pub fn commit(&self, policies: &Policies, author: &str, message: Option<String>) -> Result<PolicyRevision> {
+        let revision = self.record(policies, author, message)?;
+        if let Err(e) = policies.save(&self.path) {
+            let _ = fs::remove_file(self.revision_path(revision.revision));
+            return Err(e)
+                .with_context(|| format!("Failed to save {:?}; revision {} withdrawn", self.path, revision.revision));
+        }
+        Ok(revision)
+    }
+
+    /// Record `policies` as the next revision without touching the live file,
+    /// e.g. for a document that was edited on disk
+    pub fn record(&self, policies: &Policies, author: &str, message: Option<String>) -> Result<PolicyRevision> {
+        fs::create_dir_all(&self.history_dir)
+            .with_context(|| format!("Failed to create {:?}", self.history_dir))?;
+        let revision = PolicyRevision {
+            revision: self.latest_number()? + 1,
+            author: author.to_string(),
+            ts: Utc::now(),
+            message,
+            policies: policies.clone(),
+        };
+        write_atomic(
+            &self.revision_path(revision.revision),
+            serde_json::to_string_pretty(&revision)?.as_bytes(),
+        )?;
+        tracing::info!("Policies revision {} saved by {}", revision.revision, author);
+        Ok(revision)
+    }
+
+    /// All revisions, oldest first
+    pub fn revisions(&self) -> Result<Vec<RevisionSummary>> {
+        self.numbers()?
+            .into_iter()
+            .map(|n| {
+                let r = self.read(n)?;
+                Ok(RevisionSummary {
+                    revision: r.revision,
+                    author: r.author,
+                    ts: r.ts,
+                    message: r.message,
+                })
+            })
+            .collect()
+    }
+
+    pub fn revision(&self, number: u64) -> Result<Option<PolicyRevision>> {
+        if !self.revision_path(number).exists() {
+            return Ok(None);
+        }
+        self.read(number).map(Some)
+    }
+
+    pub fn latest(&self) -> Result<Option<PolicyRevision>> {
+        match self.latest_number()? {
+            0 => Ok(None),
+            n => self.revision(n),
+        }
+    }
+
+    /// Re-commit an earlier revision's document as a new revision. It is validated
+    /// against the current rules first and refused with a [`PolicyValidation`](crate::policies::PolicyValidation) error.
+    pub fn rollback(&self, number: u64, author: &str) -> Result<Option<PolicyRevision>> {
+        let Some(target) = self.revision(number)? else {
+            return Ok(None);
+        };
+        let validation = target.policies.validate();
+        if !validation.is_valid() {
+            return Err(validation.into());
+        }
+        self.commit(&target.policies, author, Some(format!("rollback to revision {}", number)))
+            .map(Some)
+    }
+
+    fn read(&self, number: u64) -> Result<PolicyRevision> {
+        let path = self.revision_path(number);
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))
+    }
+
+    fn revision_path(&self, number: u64) -> PathBuf {
+        self.history_dir.join(format!("{:06}.json", number))
+    }
+
+    fn latest_number(&self) -> Result<u64> {
+        Ok(self.numbers()?.last().copied().unwrap_or(0))
+    }
+
+    fn numbers(&self) -> Result<Vec<u64>> {
+        if !self.history_dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut numbers: Vec<u64> = fs::read_dir(&self.history_dir)?
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name();
+                name.to_str()?.strip_suffix(".json")?.parse().ok()
+            })
+            .collect();
+        numbers.sort_unstable();
+        Ok(numbers)
+    }
+}
+
+/// Differences between two JSON documents. Objects are compared per key,
+/// anything else (including arrays) as a whole value.
+pub fn diff_json(before: &Value, after: &Value) -> Vec<PolicyChange> {
+    let mut changes = Vec::new();
+    diff_into("", before, after, &mut changes);
+    changes
+}
+
+fn diff_into(path: &str, before: &Value, after: &Value, changes: &mut Vec<PolicyChange>) {
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => diff_into(&child, x, y, changes),
+                    (x, y) => changes.push(PolicyChange {
+                        path: child,
+                        before: x.cloned(),
+                        after: y.cloned(),
+                    }),
+                }
+            }
+        }
+        _ if before != after => changes.push(PolicyChange {
+            path: path.to_string(),
+            before: Some(before.clone()),
+            after: Some(after.clone()),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_list_and_rollback() {
+        let dir = std::env::temp_dir().join(format!("mcp-policy-store-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = PolicyStore::new(dir.join("policies.json"));
+        assert!(store.latest().unwrap().is_none());
+
+        let first = Policies::default();
+        store.commit(&first, "system", None).unwrap();
+        let mut second = first.clone();
+        second.users.insert("ci".to_string(), "operator".to_string());
+        store.commit(&second, "local:dev", Some("add ci".to_string())).unwrap();
+
+        let revisions = store.revisions().unwrap();
+        assert_eq!(revisions.iter().map(|r| r.revision).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(revisions[1].author, "local:dev");
+        assert!(Policies::load(store.path()).unwrap().users.contains_key("ci"));
+
+        let rolled = store.rollback(1, "local:dev").unwrap().unwrap();
+        assert_eq!(rolled.revision, 3);
+        assert!(!Policies::load(store.path()).unwrap().users.contains_key("ci"));
+        assert!(store.rollback(9, "local:dev").unwrap().is_none());
+
+        // A revision that no longer validates is not put back into effect
+        let mut broken = first.clone();
+        broken.users.insert("ghost".to_string(), "no-such-role".to_string());
+        store.record(&broken, "local:dev", None).unwrap();
+        let error = store.rollback(4, "local:dev").unwrap_err();
+        assert!(error.is::<crate::policies::PolicyValidation>(), "{}", error);
+        assert_eq!(store.revisions().unwrap().len(), 4);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_failed_commit_leaves_no_revision() {
+        let dir = std::env::temp_dir().join(format!("mcp-policy-store-fail-{}", std::process::id()));
+        // A directory where the live file should be makes the save fail
+        fs::create_dir_all(dir.join("policies.json")).unwrap();
+        let store = PolicyStore::new(dir.join("policies.json"));
+
+        assert!(store.commit(&Policies::default(), "system", None).is_err());
+        assert!(store.revisions().unwrap().is_empty());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_diff_json() {
+        let before = serde_json::json!({"users": {"a": "reader"}, "fs_allowlist": ["/tmp"]});
+        let after = serde_json::json!({"users": {"a": "admin", "b/c": "reader"}, "fs_allowlist": ["/tmp"]});
+        let changes = diff_json(&before, &after);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, "/users/a");
+        assert_eq!(changes[1].path, "/users/b~1c");
+        assert!(changes[1].before.is_none());
+    }
+}
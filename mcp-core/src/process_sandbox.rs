@@ -0,0 +1,351 @@
+use crate::security::{FsMode, Sandbox};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Restrictions for `process.execute`. With no `commands` nothing may run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessPolicy {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<CommandRule>,
+    /// Server environment variables passed through; everything else is cleared
+    pub env_passthrough: Vec<String>,
+    /// Variables set for every command
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Directory used when the call gives no `cwd`; defaults to the first allowlist root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    pub timeout_ms: u64,
+    /// Cap per stream; the rest is discarded and the result marked truncated
+    pub max_output_bytes: usize,
+    pub limits: ResourceLimits,
+}
+
+/// A command callers may run and the arguments it accepts
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CommandRule {
+    /// Name callers pass as `command`
+    pub command: String,
+    /// Executable to run; looked up on the passthrough `PATH` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Glob patterns; every argument must match one. Empty allows no arguments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_args: Option<usize>,
+}
+
+/// rlimits applied to the child before exec
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    pub cpu_secs: u64,
+    pub memory_mb: u64,
+    /// Processes for the user the server runs as (`RLIMIT_NPROC`)
+    pub max_processes: u64,
+}
+
+/// Outcome of a sandboxed run
+#[derive(Debug, Serialize)]
+pub struct ProcessOutput {
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub stdout_truncated: bool,
+    pub stderr_truncated: bool,
+    pub timed_out: bool,
+}
+
+impl Default for ProcessPolicy {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            env_passthrough: vec!["PATH".to_string(), "LANG".to_string()],
+            env: HashMap::new(),
+            working_dir: None,
+            timeout_ms: 30_000,
+            max_output_bytes: 1024 * 1024,
+            limits: ResourceLimits::default(),
+        }
+    }
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            cpu_secs: 30,
+            memory_mb: 1024,
+            max_processes: 256,
+        }
+    }
+}
+
+impl ProcessPolicy {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut names = HashSet::new();
+        for rule in &self.commands {
+            if rule.command.is_empty() {
+                errors.push("process: command rule without a command".to_string());
+            } else if !names.insert(rule.command.as_str()) {
+                errors.push(format!("process: command '{}' is listed more than once", rule.command));
+            }
+            if rule.path.as_ref().is_some_and(|p| !Path::new(p).is_absolute()) {
+                errors.push(format!("process: command '{}' path is not absolute", rule.command));
+            }
+            for pattern in &rule.args {
+                if let Err(e) = glob::Pattern::new(pattern) {
+                    errors.push(format!("process: command '{}' bad arg pattern '{}': {}", rule.command, pattern, e));
+                }
+            }
+        }
+        if self.working_dir.as_ref().is_some_and(|d| !Path::new(d).is_absolute()) {
+            errors.push("process: working_dir is not absolute".to_string());
+        }
+        if self.timeout_ms == 0 {
+            errors.push("process: timeout_ms must be positive".to_string());
+        }
+        errors
+    }
+
+    /// Check a call against the allowlist and resolve its executable
+    pub fn authorize(&self, command: &str, args: &[&str]) -> Result<PathBuf> {
+        let Some(rule) = self.commands.iter().find(|r| r.command == command) else {
+            bail!("command '{}' is not in the process allowlist", command);
+        };
+        if let Some(max) = rule.max_args {
+            if args.len() > max {
+                bail!("command '{}' accepts at most {} arguments", command, max);
+            }
+        }
+        let patterns: Vec<glob::Pattern> = rule.args.iter().filter_map(|p| glob::Pattern::new(p).ok()).collect();
+        if let Some(arg) = args.iter().find(|a| !patterns.iter().any(|p| p.matches(a))) {
+            bail!("argument '{}' is not allowed for '{}'", arg, command);
+        }
+        match &rule.path {
+            Some(path) => Ok(PathBuf::from(path)),
+            None => {
+                let search = self.environment().remove("PATH").unwrap_or_default();
+                which::which_in(command, Some(search), "/")
+                    .with_context(|| format!("command '{}' not found on PATH", command))
+            }
+        }
+    }
+
+    /// Passthrough variables from the server plus the fixed `env`
+    pub fn environment(&self) -> HashMap<String, String> {
+        let mut env: HashMap<String, String> = self
+            .env_passthrough
+            .iter()
+            .filter_map(|key| std::env::var(key).ok().map(|value| (key.clone(), value)))
+            .collect();
+        env.extend(self.env.clone());
+        env
+    }
+
+    /// Working directory for a call, confined to the fs allowlist
+    pub fn working_dir(&self, cwd: Option<&str>, sandbox: &Sandbox) -> Result<PathBuf> {
+        let dir = match cwd.or(self.working_dir.as_deref()) {
+            Some(dir) => dir.to_string(),
+            None => sandbox
+                .roots()
+                .next()
+                .context("no fs allowlist root to run commands in")?
+                .to_string_lossy()
+                .to_string(),
+        };
+        let resolved = sandbox.check(&dir, FsMode::Read)?;
+        if !resolved.is_dir() {
+            bail!("working directory {:?} is not a directory", resolved);
+        }
+        Ok(resolved)
+    }
+
+    /// Run an authorized command in its own process group with a cleared
+    /// environment, rlimits, capped output and a timeout that kills the group
+    pub async fn run(
+        &self,
+        executable: &Path,
+        args: &[&str],
+        cwd: &Path,
+        extra_env: &[(String, String)],
+    ) -> Result<ProcessOutput> {
+        let mut command = tokio::process::Command::new(executable);
+        command
+            .args(args)
+            .current_dir(cwd)
+            .env_clear()
+            .envs(self.environment())
+            .envs(extra_env.iter().cloned())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        {
+            let limits = self.limits;
+            command.process_group(0);
+            // SAFETY: only calls setrlimit, which is async-signal-safe
+            unsafe {
+                command.pre_exec(move || limits.apply());
+            }
+        }
+
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("failed to start {:?}", executable))?;
+        let pid = child.id();
+        let stdout = child.stdout.take().context("stdout not captured")?;
+        let stderr = child.stderr.take().context("stderr not captured")?;
+        let cap = self.max_output_bytes;
+
+        let run = async {
+            let (out, err, status) = tokio::join!(read_capped(stdout, cap), read_capped(stderr, cap), child.wait());
+            (out, err, status)
+        };
+        let timeout = std::time::Duration::from_millis(self.timeout_ms);
+        let finished = tokio::time::timeout(timeout, run).await;
+        // Also reaps anything the command left running in the background
+        kill_group(pid);
+
+        let Ok((stdout, stderr, status)) = finished else {
+            let _ = child.wait().await;
+            return Ok(ProcessOutput {
+                exit_code: None,
+                stdout: String::new(),
+                stderr: format!("killed after {} ms", self.timeout_ms),
+                stdout_truncated: false,
+                stderr_truncated: false,
+                timed_out: true,
+            });
+        };
+        let (stdout, stdout_truncated) = stdout?;
+        let (stderr, stderr_truncated) = stderr?;
+        Ok(ProcessOutput {
+            exit_code: status?.code(),
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+            stdout_truncated,
+            stderr_truncated,
+            timed_out: false,
+        })
+    }
+}
+
+impl ResourceLimits {
+    #[cfg(unix)]
+    fn apply(&self) -> std::io::Result<()> {
+        let set = |resource, value: u64| {
+            let limit = libc::rlimit {
+                rlim_cur: value as libc::rlim_t,
+                rlim_max: value as libc::rlim_t,
+            };
+            // SAFETY: plain syscall on a stack value
+            if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        };
+        set(libc::RLIMIT_CPU, self.cpu_secs)?;
+        set(libc::RLIMIT_AS, self.memory_mb.saturating_mul(1024 * 1024))?;
+        set(libc::RLIMIT_NPROC, self.max_processes)?;
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn kill_group(pid: Option<u32>) {
+    if let Some(pid) = pid {
+        // SAFETY: signals the group created for this child only
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_group(_pid: Option<u32>) {}
+
+/// Read a stream to the end, keeping at most `cap` bytes
+async fn read_capped<R: AsyncRead + Unpin>(mut reader: R, cap: usize) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok((kept, truncated));
+        }
+        let room = cap.saturating_sub(kept.len());
+        kept.extend_from_slice(&buf[..n.min(room)]);
+        truncated |= n > room;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ProcessPolicy {
+        serde_json::from_value(serde_json::json!({
+            "commands": [
+                {"command": "echo", "args": ["-n", "[a-z]*"], "max_args": 3},
+                {"command": "sh", "path": "/bin/sh", "args": ["*"]}
+            ],
+            "timeout_ms": 500,
+            "max_output_bytes": 8
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_authorize() {
+        let policy = policy();
+        assert!(policy.validate().is_empty());
+        assert!(policy.authorize("echo", &["-n", "hello"]).unwrap().is_absolute());
+        assert!(policy.authorize("echo", &["Hello"]).is_err());
+        assert!(policy.authorize("echo", &["a", "b", "c", "d"]).is_err());
+        assert!(policy.authorize("rm", &["-rf", "/"]).is_err());
+        assert!(ProcessPolicy::default().authorize("echo", &[]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_confined() {
+        let dir = std::env::temp_dir().join(format!("mcp-process-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sandbox = Sandbox::new(&[dir.to_string_lossy().to_string()]);
+        let policy = policy();
+        let cwd = policy.working_dir(None, &sandbox).unwrap();
+        assert!(policy.working_dir(Some("/etc"), &sandbox).is_err());
+
+        // Cleared environment, confined cwd, capped output
+        std::env::set_var("MCP_PROCESS_TEST_SECRET", "leak");
+        let sh = policy.authorize("sh", &["-c", "x"]).unwrap();
+        let out = policy
+            .run(&sh, &["-c", "echo \"${MCP_PROCESS_TEST_SECRET:-none}\"; pwd"], &cwd, &[])
+            .await
+            .unwrap();
+        assert_eq!(out.exit_code, Some(0));
+        assert!(out.stdout.starts_with("none\n"));
+        assert!(out.stdout_truncated && !out.timed_out);
+
+        // Timeout kills the whole group, including background children
+        let started = std::time::Instant::now();
+        let out = policy.run(&sh, &["-c", "sleep 30 & sleep 30"], &cwd, &[]).await.unwrap();
+        assert!(out.timed_out && out.exit_code.is_none());
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
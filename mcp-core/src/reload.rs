@@ -0,0 +1,333 @@
+use crate::config::ServerConfig;
+use crate::context::ContextEngine;
+use crate::observability::Metrics;
+use crate::policies::Policies;
+use crate::policy_store::{PolicyRevision, PolicyStore};
+use crate::security::FsRule;
+use crate::server_state::ServerState;
+use crate::tool_executor::InMemoryToolExecutor;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex, RwLock};
+
+/// Quiet period after a file event before reloading, so editors that write
+/// in several steps trigger a single reload
+const DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Reloads `.mcp/config.json` and `.mcp/policies.json` into the running server.
+/// Each file is parsed and validated in full before anything is swapped, so an
+/// invalid edit is logged and the running configuration stays untouched.
+pub struct Reloader {
+    config_path: PathBuf,
+    /// Last configuration applied
+    config: Mutex<ServerConfig>,
+    policies: Arc<RwLock<Policies>>,
+    policy_store: Arc<PolicyStore>,
+    executor: Arc<InMemoryToolExecutor>,
+    context_engine: Arc<ContextEngine>,
+    state: Arc<ServerState>,
+    port: Arc<watch::Sender<u16>>,
+    /// Allowlist given on the command line, which policies never replace
+    fs_override: Option<Vec<FsRule>>,
+    /// `--context-engine on|off`, which outlives config reloads
+    engine_override: Option<bool>,
+    /// Tool directory used when the config does not set `toolsDir`
+    default_tools_dir: String,
+    /// Told which tenants get their own label when the policies change
+    metrics: Arc<Metrics>,
+}
+
+impl Reloader {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        config_path: impl Into<PathBuf>,
+        config: ServerConfig,
+        policies: Arc<RwLock<Policies>>,
+        policy_store: Arc<PolicyStore>,
+        executor: Arc<InMemoryToolExecutor>,
+        context_engine: Arc<ContextEngine>,
+        state: Arc<ServerState>,
+        port: Arc<watch::Sender<u16>>,
+    ) -> Self {
+        Self {
+            config_path: config_path.into(),
+            config: Mutex::new(config),
+            policies,
+            policy_store,
+            executor,
+            context_engine,
+            state,
+            port,
+            fs_override: None,
+            engine_override: None,
+            default_tools_dir: ".mcp/tools".to_string(),
+            metrics: Arc::new(Metrics::default()),
+        }
+    }
+
+    /// Keep this allowlist regardless of `fs_allowlist` in the policies
+    pub fn with_fs_override(mut self, rules: Option<Vec<FsRule>>) -> Self {
+        self.fs_override = rules;
+        self
+    }
+
+    pub fn with_engine_override(mut self, enabled: Option<bool>) -> Self {
+        self.engine_override = enabled;
+        self
+    }
+
+    pub fn with_default_tools_dir(mut self, dir: impl Into<String>) -> Self {
+        self.default_tools_dir = dir.into();
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    pub fn policy_store(&self) -> &Arc<PolicyStore> {
+        &self.policy_store
+    }
+
+    /// Reload both files; each is applied or rejected on its own
+    pub async fn reload_all(&self) {
+        if let Err(e) = self.reload_config().await {
+            tracing::error!("Config reload rejected, keeping running config: {:#}", e);
+        }
+        if let Err(e) = self.reload_policies().await {
+            tracing::error!("Policies reload rejected, keeping running policies: {:#}", e);
+        }
+    }
+
+    /// Apply `config.json` changes to the context engine, tool directory and listener port
+    pub async fn reload_config(&self) -> Result<()> {
+        let path = self.config_path.to_string_lossy().to_string();
+        let mut next = ServerConfig::load(&path).with_context(|| format!("Failed to load {}", path))?;
+        if let Some(enabled) = self.engine_override {
+            next.context_engine.enabled = enabled;
+        }
+        next.validate()?;
+
+        let mut current = self.config.lock().await;
+        let tools_dir = next.tools_dir.as_deref().unwrap_or(&self.default_tools_dir);
+        if next.tools_dir != current.tools_dir {
+            // Fails (and rejects the whole file) if the directory is unreadable
+            self.executor
+                .reload_tools(tools_dir)
+                .await
+                .with_context(|| format!("Failed to load tools from {}", tools_dir))?;
+            self.state.sync_tools(&self.executor.manifests().await).await;
+        }
+        if next.context_engine != current.context_engine {
+            self.context_engine.configure(&next.context_engine);
+            self.state.set_context_engine(next.context_engine.enabled).await;
+            tracing::info!("Context engine settings reloaded");
+        }
+        if next.server.port != current.server.port {
+            tracing::info!("Server port changed to {}, rebinding", next.server.port);
+            self.port.send_replace(next.server.port);
+        }
+        *current = next;
+        tracing::info!("Configuration reloaded from {}", path);
+        Ok(())
+    }
+
+    /// Apply `policies.json` edited on disk, recording it as a revision
+    pub async fn reload_policies(&self) -> Result<()> {
+        let next = Policies::load(self.policy_store.path())?;
+        let validation = next.validate();
+        if !validation.is_valid() {
+            anyhow::bail!("invalid policies: {}", validation.errors.join("; "));
+        }
+
+        let mut current = self.policies.write().await;
+        if serde_json::to_value(&*current)? == serde_json::to_value(&next)? {
+            // Our own write, or a save that changed nothing
+            return Ok(());
+        }
+        self.policy_store.record(&next, "file", Some("reloaded from disk".to_string()))?;
+        self.apply_policies(&mut current, next);
+        tracing::info!("Policies reloaded from {:?}", self.policy_store.path());
+        Ok(())
+    }
+
+    /// Save an updated document as a new revision and put it into effect
+    pub async fn commit_policies(&self, next: Policies, author: &str) -> Result<PolicyRevision> {
+        let mut current = self.policies.write().await;
+        let revision = self.policy_store.commit(&next, author, None)?;
+        self.apply_policies(&mut current, next);
+        Ok(revision)
+    }
+
+    /// Re-commit an earlier revision and put it into effect
+    pub async fn rollback_policies(&self, revision: u64, author: &str) -> Result<Option<PolicyRevision>> {
+        let mut current = self.policies.write().await;
+        let Some(rolled) = self.policy_store.rollback(revision, author)? else {
+            return Ok(None);
+        };
+        self.apply_policies(&mut current, rolled.policies.clone());
+        Ok(Some(rolled))
+    }
+
+    fn apply_policies(&self, current: &mut Policies, next: Policies) {
+        if self.fs_override.is_none() {
+            self.executor.set_fs_rules(&next.fs_rules());
+        }
+        self.executor.set_egress(next.egress.clone());
+        self.executor.set_process(next.process.clone());
+        self.executor.set_env_policy(next.env.clone());
+        self.executor.set_secret_scopes(next.secret_scopes.clone());
+        self.executor.set_tenancy(next.tenancy.clone());
+        self.metrics.set_tenants(next.tenancy.tenants.keys());
+        if let Err(e) = self.executor.redactor().configure(&next.redaction) {
+            tracing::error!("Keeping previous redaction settings: {:#}", e);
+        }
+        *current = next;
+    }
+
+    /// Watch both files and reload on change. Directories are watched rather
+    /// than the files, which atomic saves replace. Keep the watcher alive.
+    pub fn watch(self: &Arc<Self>) -> Result<notify::RecommendedWatcher> {
+        use notify::{RecursiveMode, Watcher};
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<PathBuf>();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+        })?;
+
+        let config_path = absolute(&self.config_path);
+        let policies_path = absolute(self.policy_store.path());
+        let mut dirs: Vec<PathBuf> = [&config_path, &policies_path]
+            .iter()
+            .filter_map(|p| p.parent().map(Path::to_path_buf))
+            .collect();
+        dirs.dedup();
+        for dir in &dirs {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            tracing::info!("Watching {:?} for config and policy changes", dir);
+        }
+
+        let reloader = self.clone();
+        tokio::spawn(async move {
+            while let Some(first) = rx.recv().await {
+                let mut changed = vec![first];
+                // Collect the burst of events a single save produces
+                while let Ok(Some(path)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+                    changed.push(path);
+                }
+                if changed.contains(&config_path) {
+                    if let Err(e) = reloader.reload_config().await {
+                        tracing::error!("Config reload rejected, keeping running config: {:#}", e);
+                    }
+                }
+                if changed.contains(&policies_path) {
+                    if let Err(e) = reloader.reload_policies().await {
+                        tracing::error!("Policies reload rejected, keeping running policies: {:#}", e);
+                    }
+                }
+            }
+        });
+        Ok(watcher)
+    }
+
+    /// Reload both files on SIGHUP
+    #[cfg(unix)]
+    pub fn reload_on_sighup(self: &Arc<Self>) -> Result<()> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = signal(SignalKind::hangup())?;
+        let reloader = self.clone();
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                tracing::info!("SIGHUP received, reloading configuration and policies");
+                reloader.reload_all().await;
+            }
+        });
+        Ok(())
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(dir: &Path) -> (Arc<Reloader>, watch::Receiver<u16>) {
+        std::fs::create_dir_all(dir.join("tools")).unwrap();
+        let config_path = dir.join("config.json");
+        let config: ServerConfig = serde_json::from_value(serde_json::json!({
+            "profile": "test",
+            "transports": ["stdio"],
+            "rbac": {"defaultRole": "operator"},
+            "observability": {"otelExporter": "http://localhost:4318"},
+            "context_engine": {"enabled": true, "changeCapPctPerDay": 10, "minConfidence": 0.6},
+            "server": {"port": 50550}
+        }))
+        .unwrap();
+        std::fs::write(&config_path, serde_json::to_string(&config).unwrap()).unwrap();
+
+        let store = Arc::new(PolicyStore::new(dir.join("policies.json")));
+        store.commit(&Policies::default(), "system", None).unwrap();
+        let (port_tx, port_rx) = watch::channel(config.server.port);
+        let reloader = Reloader::new(
+            config_path,
+            config,
+            Arc::new(RwLock::new(Policies::default())),
+            store,
+            Arc::new(InMemoryToolExecutor::new()),
+            Arc::new(ContextEngine::new(true, 10, 0.6)),
+            Arc::new(ServerState::new()),
+            Arc::new(port_tx),
+        )
+        .with_default_tools_dir(dir.join("tools").to_string_lossy());
+        (Arc::new(reloader), port_rx)
+    }
+
+    #[tokio::test]
+    async fn test_invalid_files_keep_running_config() {
+        let dir = std::env::temp_dir().join(format!("mcp-reload-{}", std::process::id()));
+        let (reloader, port) = fixture(&dir);
+        let config_path = dir.join("config.json");
+        let policies_path = dir.join("policies.json");
+
+        // Valid change: new port is published for the listener to rebind
+        let mut config: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+        config["server"]["port"] = serde_json::json!(50551);
+        std::fs::write(&config_path, config.to_string()).unwrap();
+        reloader.reload_config().await.unwrap();
+        assert_eq!(*port.borrow(), 50551);
+
+        // Invalid change: rejected, port unchanged
+        config["server"]["port"] = serde_json::json!(50552);
+        config["context_engine"]["minConfidence"] = serde_json::json!(7.0);
+        std::fs::write(&config_path, config.to_string()).unwrap();
+        assert!(reloader.reload_config().await.is_err());
+        assert_eq!(*port.borrow(), 50551);
+
+        // Policies with a dangling role are rejected
+        let mut policies = Policies::default();
+        policies.users.insert("ci".to_string(), "deployer".to_string());
+        std::fs::write(&policies_path, serde_json::to_string(&policies).unwrap()).unwrap();
+        assert!(reloader.reload_policies().await.is_err());
+        assert!(!reloader.policies.read().await.users.contains_key("ci"));
+
+        // Valid edit is applied and recorded as a revision
+        policies.users.insert("ci".to_string(), "operator".to_string());
+        std::fs::write(&policies_path, serde_json::to_string(&policies).unwrap()).unwrap();
+        reloader.reload_policies().await.unwrap();
+        assert!(reloader.policies.read().await.users.contains_key("ci"));
+        assert_eq!(reloader.policy_store().latest().unwrap().unwrap().author, "file");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
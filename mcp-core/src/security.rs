@@ -1,99 +1,644 @@
 use anyhow::{bail, Result};
-use path_absolutize::Absolutize;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use glob::glob;
 
-/// Expands a path with wildcards to a list of matching files
-pub fn expand_wildcard_path(path: &str, allow_list: &[String]) -> Result<Vec<PathBuf>> {
+/// Most paths one wildcard expands to
+const MAX_WILDCARD_MATCHES: usize = 10_000;
+
+/// Expands a path with wildcards (including `**` and wildcards in directory
+/// components) to the matching files and directories, in name order
+pub fn expand_wildcard_path(path: &str, sandbox: &Sandbox) -> Result<Vec<PathBuf>> {
     // If no wildcards, return as-is (resolved)
-    if !path.contains('*') && !path.contains('?') {
-        let resolved = resolve_path(path, allow_list)?;
+    let Some((dir_part, glob_part)) = crate::fs_walk::split_wildcard(path) else {
+        let resolved = sandbox.resolve(path)?;
         return Ok(vec![resolved]);
-    }
-    
-    // Split path into directory and pattern parts
-    let (dir_part, file_pattern) = path.rsplit_once('/')
-        .ok_or_else(|| anyhow::anyhow!("Invalid path: {}", path))?;
+    };
     
     // Resolve the directory part (which may use /contracts shorthand)
-    let resolved_dir = resolve_path(dir_part, allow_list)?;
-    let resolved_dir_str = resolved_dir.to_string_lossy();
+    let resolved_dir = sandbox.resolve(&dir_part)?;
+    let pattern = glob::Pattern::new(&glob_part)
+        .map_err(|e| anyhow::anyhow!("Invalid wildcard pattern '{}': {}", path, e))?;
     
-    // Build full glob pattern with resolved directory
-    let pattern = format!("{}/{}", resolved_dir_str, file_pattern);
+    tracing::info!("Expanding wildcard: '{}' -> '{}' under '{}'", path, glob_part, resolved_dir.display());
     
-    tracing::info!("Expanding wildcard: '{}' -> pattern: '{}'", path, pattern);
-    
-    // Use glob to find matching files
+    // Walk below the directory; denied and excluded entries are never visited
     let mut matches = Vec::new();
-    match glob(&pattern) {
-        Ok(paths) => {
-            for entry in paths {
-                match entry {
-                    Ok(matched_path) => {
-                        // Verify each match is in allowlist
-                        let path_str = matched_path.to_string_lossy().to_string();
-                        if is_allowed(&path_str, allow_list).is_ok() {
-                            matches.push(matched_path);
-                            tracing::debug!("  Matched: {}", path_str);
-                        } else {
-                            tracing::warn!("  Matched but not in allowlist: {}", path_str);
-                        }
-                    }
-                    Err(e) => tracing::warn!("Glob entry error: {}", e),
+    crate::fs_walk::Walk::new(sandbox)
+        .with_gitignore(false)
+        .with_max_depth(crate::fs_walk::glob_depth(&glob_part))
+        .run(&resolved_dir, &mut |entry| {
+            if crate::fs_walk::matches(&pattern, &entry.relative) {
+                // Verify each match is in allowlist, after following symlinks
+                match sandbox.resolve(&entry.path.to_string_lossy()) {
+                    Ok(canonical) => matches.push(canonical),
+                    Err(_) => tracing::warn!("  Matched but not in allowlist: {}", entry.path.display()),
                 }
             }
-        }
-        Err(e) => {
-            tracing::error!("Glob pattern error: {}", e);
-            bail!("Invalid wildcard pattern '{}': {}", pattern, e);
-        }
-    }
+            if matches.len() == MAX_WILDCARD_MATCHES {
+                tracing::warn!("Wildcard '{}' stopped at {} matches", path, MAX_WILDCARD_MATCHES);
+                return std::ops::ControlFlow::Break(());
+            }
+            std::ops::ControlFlow::Continue(())
+        })?;
     
     if matches.is_empty() {
-        bail!("No files matched pattern '{}'. Resolved pattern: '{}'. Check if files exist.", path, pattern);
+        bail!("No files matched pattern '{}'. Check if files exist.", path);
     }
     
     tracing::info!("Found {} file(s) matching '{}'", matches.len(), path);
     Ok(matches)
 }
 
-/// Resolves a path that may use shorthand notation (e.g., /contracts/...)
-/// to its actual filesystem path based on the allowlist
-pub fn resolve_path(path: &str, allow_list: &[String]) -> Result<PathBuf> {
-    tracing::info!("resolve_path: input='{}', allowlist={:?}", path, allow_list);
-    
-    // If already absolute and NOT using shorthand, return as-is
-    if path.starts_with('/') && !path.starts_with("/contracts") && !path.starts_with("/workspace") {
-        tracing::info!("  -> returning as-is (already absolute, no shorthand)");
-        return Ok(PathBuf::from(path));
+/// Most symlinks followed while resolving one path (matches Linux MAXSYMLINKS)
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Access a filesystem operation needs. Each mode includes the ones before it:
+/// `write` mounts are readable, `delete` mounts are writable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FsMode {
+    Read,
+    Write,
+    Delete,
+}
+
+impl FsMode {
+    /// Access required by a filesystem tool
+    pub fn for_tool(tool_id: &str) -> Self {
+        match tool_id {
+            "fs.write" | "fs.mkdir" => FsMode::Write,
+            "fs.delete" | "fs.move" => FsMode::Delete,
+            _ => FsMode::Read,
+        }
     }
-    
-    // Try smart resolution for shorthand paths like /contracts/..., /workspace/...
-    for base in allow_list {
-        let base_abs = Path::new(base).absolutize()?.to_path_buf();
-        
-        if let Some(base_name) = base_abs.file_name().and_then(|n| n.to_str()) {
-            let with_slash = format!("/{}/", base_name);
-            tracing::info!("  Checking base '{}', base_name='{}', checking if path starts with '{}'", base, base_name, with_slash);
-            
-            // Check if path starts with /<basename>/
-            if let Some(after_base) = path.strip_prefix(&with_slash) {
-                // Reconstruct full path: base + remaining
-                let resolved = base_abs.join(after_base);
-                tracing::info!("  -> RESOLVED /{}/{}  =>  {}", base_name, after_base, resolved.display());
-                return Ok(resolved);
-            } else if path == format!("/{}", base_name) {
-                // Exact match: "/contracts" -> full path
-                tracing::info!("  -> RESOLVED /{}  =>  {}", base_name, base_abs.display());
-                return Ok(base_abs);
+}
+
+/// Allowlist entry with an access mode, denied sub-paths and glob excludes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FsRule {
+    pub path: String,
+    #[serde(default = "default_rule_mode")]
+    pub mode: FsMode,
+    /// Sub-paths (absolute, or relative to `path`) that are never accessible
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    /// Globs matched against the path relative to `path`, e.g. `**/.git/**`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+fn default_rule_mode() -> FsMode {
+    FsMode::Read
+}
+
+impl FsRule {
+    /// Full access to `path`, the meaning of a plain allowlist string
+    pub fn full(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            mode: FsMode::Delete,
+            deny: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// `fs_allowlist` element: a plain directory (full access) or a rule object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FsAllowEntry {
+    Path(String),
+    Rule(FsRule),
+}
+
+impl FsAllowEntry {
+    pub fn path(&self) -> &str {
+        match self {
+            FsAllowEntry::Path(path) => path,
+            FsAllowEntry::Rule(rule) => &rule.path,
+        }
+    }
+
+    pub fn to_rule(&self) -> FsRule {
+        match self {
+            FsAllowEntry::Path(path) => FsRule::full(path.clone()),
+            FsAllowEntry::Rule(rule) => rule.clone(),
+        }
+    }
+}
+
+impl From<&str> for FsAllowEntry {
+    fn from(path: &str) -> Self {
+        FsAllowEntry::Path(path.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SandboxRule {
+    /// Canonical root
+    root: PathBuf,
+    mode: FsMode,
+    /// Canonical denied paths
+    deny: Vec<PathBuf>,
+    exclude: Vec<glob::Pattern>,
+    /// Entry as configured, for error messages
+    entry: String,
+}
+
+/// How the sandbox resolved one path, as returned by [`Sandbox::explain`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxTrace {
+    pub path: String,
+    pub access: FsMode,
+    /// Canonical path after symlinks and shorthand
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+    /// Resolved via the `/<root name>/...` shorthand
+    pub shorthand: bool,
+    /// Innermost allowlist entry containing the path, as configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<FsMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocked_by: Option<String>,
+    pub allowed: bool,
+    pub explanation: String,
+}
+
+/// Filesystem sandbox over the allowlist. Paths are resolved through symlinks
+/// (including for targets that do not exist yet) before containment is checked,
+/// so a link inside an allowed root cannot point outside it.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    rules: Vec<SandboxRule>,
+    base_dir: Option<PathBuf>,
+}
+
+impl Sandbox {
+    /// Sandbox granting full access beneath each directory
+    pub fn new(allow_list: &[String]) -> Self {
+        let rules: Vec<FsRule> = allow_list.iter().map(|p| FsRule::full(p.clone())).collect();
+        Self::from_rules(&rules)
+    }
+
+    /// Sandbox enforcing per-entry modes, denies and excludes
+    pub fn from_rules(rules: &[FsRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| match Self::compile(rule) {
+                Ok(compiled) => Some(compiled),
+                Err(e) => {
+                    tracing::warn!("Ignoring allowlist entry '{}': {}", rule.path, e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules, base_dir: None }
+    }
+
+    fn compile(rule: &FsRule) -> Result<SandboxRule> {
+        let root = canonicalize_lenient(&absolute(Path::new(&rule.path), None))?;
+        let deny = rule
+            .deny
+            .iter()
+            .map(|d| canonicalize_lenient(&absolute(Path::new(d), Some(&root))))
+            .collect::<Result<_>>()?;
+        let exclude = rule
+            .exclude
+            .iter()
+            .map(|g| glob::Pattern::new(g).map_err(|e| anyhow::anyhow!("bad exclude '{}': {}", g, e)))
+            .collect::<Result<_>>()?;
+        Ok(SandboxRule {
+            root,
+            mode: rule.mode,
+            deny,
+            exclude,
+            entry: rule.path.clone(),
+        })
+    }
+
+    /// Resolve relative paths against `base_dir` instead of the working directory
+    pub fn with_base_dir(mut self, base_dir: Option<&str>) -> Self {
+        self.base_dir = base_dir.map(PathBuf::from);
+        self
+    }
+
+    /// Allowlist roots, canonicalized
+    pub fn roots(&self) -> impl Iterator<Item = &Path> {
+        self.rules.iter().map(|rule| rule.root.as_path())
+    }
+
+    /// Roots a WASI module may be handed whole. A guest's own file access never
+    /// goes through `check`, so only full-access entries qualify that have no
+    /// deny or exclude rules, no nested entry narrowing them and no enclosing
+    /// entry with rules of its own.
+    pub fn wasi_preopens(&self) -> Vec<&Path> {
+        let unrestricted = |rule: &SandboxRule| rule.deny.is_empty() && rule.exclude.is_empty();
+        self.rules
+            .iter()
+            .filter(|rule| rule.mode == FsMode::Delete && unrestricted(rule))
+            .filter(|rule| {
+                self.rules.iter().all(|other| {
+                    std::ptr::eq(*rule, other)
+                        || !(other.root.starts_with(&rule.root) || (rule.root.starts_with(&other.root) && !unrestricted(other)))
+                })
+            })
+            .map(|rule| rule.root.as_path())
+            .collect()
+    }
+
+    /// Canonical path for `path` if it may be read. See [`Sandbox::check`].
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        self.check(path, FsMode::Read)
+    }
+
+    /// Canonical path for `path` if it stays inside an allowlist root whose mode
+    /// grants `access` and no deny or exclude of an enclosing entry matches it.
+    /// Accepts `/<root name>/...` shorthand for roots such as `/contracts`.
+    pub fn check(&self, path: &str, access: FsMode) -> Result<PathBuf> {
+        let (canonical, shorthand) = self.canonical(path, true)?;
+        self.authorize(self.trace(path, access, &canonical, shorthand), canonical)
+    }
+
+    /// Like [`Sandbox::check`], but a symlink in the final component is not
+    /// followed: the path names the link itself, as deleting or renaming needs
+    pub fn check_entry(&self, path: &str, access: FsMode) -> Result<PathBuf> {
+        let (canonical, shorthand) = self.canonical(path, false)?;
+        self.authorize(self.trace(path, access, &canonical, shorthand), canonical)
+    }
+
+    /// The canonical path `check` would test for `path`, shorthand included,
+    /// without deciding access; for matching policy conditions
+    pub fn canonicalize(&self, path: &str) -> Result<PathBuf> {
+        self.canonical(path, true).map(|(canonical, _)| canonical)
+    }
+
+    /// Canonical path, and whether it came from the shorthand
+    fn canonical(&self, path: &str, follow_last: bool) -> Result<(PathBuf, bool)> {
+        let requested = absolute(Path::new(path), self.base_dir.as_deref());
+        let canonical = resolve_entry(&requested, follow_last)?;
+
+        // Shorthand: "/contracts/x" for allowlist entry ".../contracts"
+        if self.rule_of(&canonical).is_none() {
+            if let Some(expanded) = self.expand_shorthand(path) {
+                let expanded = resolve_entry(&expanded, follow_last)?;
+                if self.rule_of(&expanded).is_some() {
+                    tracing::debug!("Sandbox: '{}' -> '{}' (shorthand)", path, expanded.display());
+                    return Ok((expanded, true));
+                }
             }
         }
+        Ok((canonical, false))
     }
-    
-    // If no match found, return original (will likely fail security check)
-    tracing::warn!("  -> NO RESOLUTION FOUND, returning original path: {}", path);
-    Ok(PathBuf::from(path))
+
+    /// The access decision for an already-resolved path; `check` enforces it
+    /// and `explain` returns it as is
+    fn trace(&self, path: &str, access: FsMode, canonical: &Path, shorthand: bool) -> SandboxTrace {
+        let mut trace = SandboxTrace {
+            path: path.to_string(),
+            access,
+            resolved: Some(canonical.display().to_string()),
+            shorthand,
+            entry: None,
+            root: None,
+            mode: None,
+            blocked_by: None,
+            allowed: false,
+            explanation: String::new(),
+        };
+
+        let Some(rule) = self.rule_of(canonical) else {
+            trace.explanation = format!("'{}' is under no allowlist entry", canonical.display());
+            return trace;
+        };
+        trace.entry = Some(rule.entry.clone());
+        trace.root = Some(rule.root.display().to_string());
+        trace.mode = Some(rule.mode);
+
+        if let Some(blocked_by) = self.blocked_by(canonical) {
+            trace.explanation = format!("denied by {}", blocked_by);
+            trace.blocked_by = Some(blocked_by);
+        } else if rule.mode < access {
+            trace.explanation = format!("entry '{}' is {:?}, {:?} needed", rule.entry, rule.mode, access);
+        } else {
+            trace.allowed = true;
+            trace.explanation = format!("entry '{}' grants {:?}", rule.entry, access);
+        }
+        trace
+    }
+
+    fn authorize(&self, trace: SandboxTrace, canonical: PathBuf) -> Result<PathBuf> {
+        let path = &trace.path;
+        if trace.allowed {
+            tracing::debug!("Sandbox: '{}' -> '{}' ({:?})", path, canonical.display(), trace.access);
+            return Ok(canonical);
+        }
+
+        let (Some(entry), Some(mode)) = (&trace.entry, trace.mode) else {
+            tracing::warn!("Sandbox: denied '{}' (resolves to '{}')", path, canonical.display());
+            bail!(
+                "Security error: Path '{}' not in filesystem allowlist. Allowed: {:?}",
+                path,
+                self.rules.iter().map(|r| r.entry.as_str()).collect::<Vec<_>>()
+            );
+        };
+        if let Some(blocked_by) = &trace.blocked_by {
+            tracing::warn!("Sandbox: '{}' denied by {}", path, blocked_by);
+            bail!("Security error: Path '{}' is denied by {}", path, blocked_by);
+        }
+        bail!(
+            "Security error: {:?} access to '{}' not permitted; allowlist entry '{}' is {:?}",
+            trace.access,
+            path,
+            entry,
+            mode
+        )
+    }
+
+    /// Step-by-step account of how `check` treats `path`, for policy dry runs
+    pub fn explain(&self, path: &str, access: FsMode) -> SandboxTrace {
+        match self.canonical(path, true) {
+            Ok((canonical, shorthand)) => self.trace(path, access, &canonical, shorthand),
+            Err(e) => SandboxTrace {
+                path: path.to_string(),
+                access,
+                resolved: None,
+                shorthand: false,
+                entry: None,
+                root: None,
+                mode: None,
+                blocked_by: None,
+                allowed: false,
+                explanation: format!("cannot resolve: {}", e),
+            },
+        }
+    }
+
+    /// Whether a canonical path is hidden by a deny or exclude, e.g. when listing
+    pub fn is_hidden(&self, canonical: &Path) -> bool {
+        self.blocked_by(canonical).is_some()
+    }
+
+    /// Whether a recursive operation needing `access` may take a canonical path
+    /// along: it is not hidden, and its innermost entry grants `access`, so a
+    /// read-only mount nested under a deletable parent is left alone
+    pub fn permits_within(&self, canonical: &Path, access: FsMode) -> bool {
+        !self.is_hidden(canonical) && self.rule_of(canonical).is_some_and(|rule| rule.mode >= access)
+    }
+
+    /// Innermost allowlist root containing an already-canonical path.
+    /// Containment is per path component, so `/tmp` never admits `/tmpfoo`.
+    pub fn root_of(&self, canonical: &Path) -> Option<&Path> {
+        self.rule_of(canonical).map(|rule| rule.root.as_path())
+    }
+
+    fn rule_of(&self, canonical: &Path) -> Option<&SandboxRule> {
+        self.rules
+            .iter()
+            .filter(|rule| canonical.starts_with(&rule.root))
+            .max_by_key(|rule| rule.root.components().count())
+    }
+
+    /// Deny or exclude of any enclosing entry that matches, described for errors
+    fn blocked_by(&self, canonical: &Path) -> Option<String> {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        self.rules
+            .iter()
+            .filter(|rule| canonical.starts_with(&rule.root))
+            .find_map(|rule| {
+                if let Some(denied) = rule.deny.iter().find(|d| canonical.starts_with(d)) {
+                    return Some(format!("deny '{}'", denied.display()));
+                }
+                let relative = canonical.strip_prefix(&rule.root).ok()?;
+                rule.exclude
+                    .iter()
+                    .find(|pattern| pattern.matches_path_with(relative, options))
+                    .map(|pattern| format!("exclude '{}'", pattern.as_str()))
+            })
+    }
+
+    fn expand_shorthand(&self, path: &str) -> Option<PathBuf> {
+        self.roots().find_map(|root| {
+            let name = root.file_name()?.to_str()?;
+            if path == format!("/{}", name) {
+                Some(root.to_path_buf())
+            } else {
+                path.strip_prefix(&format!("/{}/", name)).map(|rest| root.join(rest))
+            }
+        })
+    }
+
+    /// Resolve `path` and open it without following any symlink at open time.
+    /// Each component below the root is opened relative to its parent directory
+    /// (`openat` with `O_NOFOLLOW`), so a directory swapped for a symlink after
+    /// `resolve` cannot redirect the open outside the sandbox.
+    pub fn open_beneath(&self, path: &str, options: &OpenBeneath) -> Result<std::fs::File> {
+        let access = if options.write { FsMode::Write } else { FsMode::Read };
+        let resolved = self.check(path, access)?;
+        let root = self
+            .root_of(&resolved)
+            .expect("check only returns contained paths")
+            .to_path_buf();
+        let relative = resolved.strip_prefix(&root)?.to_path_buf();
+        open_beneath(&root, &relative, options)
+    }
+}
+
+/// How `Sandbox::open_beneath` opens the final component
+#[derive(Debug, Clone, Default)]
+pub struct OpenBeneath {
+    pub write: bool,
+    pub create: bool,
+    pub truncate: bool,
+    pub append: bool,
+    /// Fail if the file already exists
+    pub create_new: bool,
+}
+
+impl OpenBeneath {
+    pub fn read() -> Self {
+        Self::default()
+    }
+
+    /// Create or truncate for writing
+    pub fn write() -> Self {
+        Self {
+            write: true,
+            create: true,
+            truncate: true,
+            append: false,
+            create_new: false,
+        }
+    }
+
+    /// Append to a file, creating it if needed
+    pub fn append() -> Self {
+        Self {
+            write: true,
+            create: true,
+            append: true,
+            ..Self::default()
+        }
+    }
+
+    /// Create a file that must not exist yet
+    pub fn create_new() -> Self {
+        Self {
+            write: true,
+            create: true,
+            create_new: true,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(unix)]
+fn open_beneath(root: &Path, relative: &Path, options: &OpenBeneath) -> Result<std::fs::File> {
+    use std::ffi::CString;
+    use std::os::fd::{AsRawFd, FromRawFd};
+    use std::os::unix::ffi::OsStrExt;
+
+    let openat = |dir: &std::fs::File, name: &std::ffi::OsStr, flags: i32| -> Result<std::fs::File> {
+        let name = CString::new(name.as_bytes())?;
+        // SAFETY: `dir` is an open directory fd and `name` is NUL-terminated
+        let fd = unsafe { libc::openat(dir.as_raw_fd(), name.as_ptr(), flags | libc::O_NOFOLLOW | libc::O_CLOEXEC, 0o644) };
+        if fd < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ELOOP) {
+                bail!("Security error: symlink at '{}' while opening beneath {}", name.to_string_lossy(), root.display());
+            }
+            return Err(err.into());
+        }
+        // SAFETY: `fd` was just returned by openat and is owned by nobody else
+        Ok(unsafe { std::fs::File::from_raw_fd(fd) })
+    };
+
+    let mut dir = std::fs::File::open(root)?;
+    let mut components = relative.components().peekable();
+    if components.peek().is_none() {
+        return Ok(dir);
+    }
+    while let Some(component) = components.next() {
+        let std::path::Component::Normal(name) = component else {
+            bail!("Security error: unexpected component in '{}'", relative.display());
+        };
+        if components.peek().is_some() {
+            dir = openat(&dir, name, libc::O_RDONLY | libc::O_DIRECTORY)?;
+        } else {
+            let mut flags = if options.write { libc::O_WRONLY } else { libc::O_RDONLY };
+            if options.create {
+                flags |= libc::O_CREAT;
+            }
+            if options.truncate {
+                flags |= libc::O_TRUNC;
+            }
+            if options.append {
+                flags |= libc::O_APPEND;
+            }
+            if options.create_new {
+                flags |= libc::O_CREAT | libc::O_EXCL;
+            }
+            return openat(&dir, name, flags);
+        }
+    }
+    unreachable!("loop returns on the last component")
+}
+
+#[cfg(not(unix))]
+fn open_beneath(root: &Path, relative: &Path, options: &OpenBeneath) -> Result<std::fs::File> {
+    Ok(std::fs::OpenOptions::new()
+        .read(!options.write)
+        .write(options.write)
+        .create(options.create)
+        .truncate(options.truncate)
+        .append(options.append)
+        .create_new(options.create_new)
+        .open(root.join(relative))?)
+}
+
+/// Make `path` absolute against `base_dir` (or the working directory) without touching the filesystem
+fn absolute(path: &Path, base_dir: Option<&Path>) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    match base_dir {
+        Some(base) => base.join(path),
+        None => std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/")).join(path),
+    }
+}
+
+/// Resolve every symlink in an absolute path, like `fs::canonicalize`, but
+/// tolerate components that do not exist yet (write targets) and dangling links.
+/// `..` is applied to the resolved prefix, matching kernel semantics.
+pub fn canonicalize_lenient(path: &Path) -> Result<PathBuf> {
+    use std::path::Component;
+
+    if !path.is_absolute() {
+        bail!("Path must be absolute: {}", path.display());
+    }
+
+    let mut resolved = PathBuf::new();
+    // Components still to walk, in reverse so `pop` yields the next one
+    let mut pending: Vec<std::ffi::OsString> = Vec::new();
+    let push_components = |pending: &mut Vec<std::ffi::OsString>, resolved: &mut PathBuf, path: &Path| {
+        let mut parts = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::Prefix(prefix) => *resolved = PathBuf::from(prefix.as_os_str()),
+                Component::RootDir => resolved.push(Component::RootDir.as_os_str()),
+                Component::CurDir => {}
+                Component::ParentDir => parts.push(std::ffi::OsString::from("..")),
+                Component::Normal(name) => parts.push(name.to_os_string()),
+            }
+        }
+        pending.extend(parts.into_iter().rev());
+    };
+    push_components(&mut pending, &mut resolved, path);
+
+    let mut hops = 0;
+    while let Some(name) = pending.pop() {
+        if name == ".." {
+            resolved.pop();
+            continue;
+        }
+        let candidate = resolved.join(&name);
+        match std::fs::symlink_metadata(&candidate) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                hops += 1;
+                if hops > MAX_SYMLINK_HOPS {
+                    bail!("Too many levels of symbolic links: {}", path.display());
+                }
+                let target = std::fs::read_link(&candidate)?;
+                if target.is_absolute() {
+                    resolved = PathBuf::new();
+                }
+                push_components(&mut pending, &mut resolved, &target);
+            }
+            _ => resolved = candidate,
+        }
+    }
+    Ok(resolved)
+}
+
+/// `canonicalize_lenient`, optionally keeping the final component as given so
+/// that a symlink there is named rather than followed
+fn resolve_entry(path: &Path, follow_last: bool) -> Result<PathBuf> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !follow_last => Ok(canonicalize_lenient(parent)?.join(name)),
+        _ => canonicalize_lenient(path),
+    }
+}
+
+/// Resolves a path that may use shorthand notation (e.g., /contracts/...)
+/// to its canonical filesystem path inside the allowlist
+pub fn resolve_path(path: &str, allow_list: &[String]) -> Result<PathBuf> {
+    Sandbox::new(allow_list).resolve(path)
 }
 
 /// Validates that a given file path is within the allowed filesystem directories
@@ -104,68 +649,10 @@ pub fn is_allowed(path: &str, allow_list: &[String]) -> Result<()> {
 
 /// Validates path with optional base directory for relative path resolution
 pub fn is_allowed_with_base(path: &str, allow_list: &[String], base_dir: Option<&str>) -> Result<()> {
-    tracing::info!("=== SECURITY CHECK: path='{}', allowlist={:?} ===", path, allow_list);
-    
-    // Try to resolve the path
-    let abs = if path.starts_with('/') {
-        // Absolute path - use as-is for now
-        Path::new(path).absolutize()?.to_path_buf()
-    } else if let Some(base) = base_dir {
-        // Relative path with base_dir - resolve against base
-        let base_path = Path::new(base);
-        base_path.join(path).absolutize()?.to_path_buf()
-    } else {
-        // Relative path without base_dir - resolve against current dir
-        Path::new(path).absolutize()?.to_path_buf()
-    };
-    
-    tracing::info!("  Resolved to absolute path: '{}'", abs.display());
-    
-    // Collect all candidate paths to check
-    let mut candidates = vec![];
-    
-    for base in allow_list {
-        let base_abs = Path::new(base).absolutize()?.to_path_buf();
-        tracing::info!("  Checking against base: '{}' (resolved: '{}')", base, base_abs.display());
-        
-        // Direct match: check if resolved path is under this base
-        if abs.starts_with(&base_abs) {
-            tracing::info!("  ✓ ALLOWED: Direct match!");
-            return Ok(());
-        }
-        
-        // Smart resolution: try to match path fragments
-        // e.g., "/contracts/file.txt" with allowlist "/home/.../nurones-cide/contracts"
-        if let Some(base_name) = base_abs.file_name().and_then(|n| n.to_str()) {
-            tracing::info!("  Base filename: '{}'", base_name);
-            // Check if the input path starts with "/<basename>/" or is exactly "/<basename>"
-            let with_slash = format!("/{}/", base_name);
-            tracing::info!("  Checking if '{}' starts with '{}'", path, with_slash);
-            if let Some(after_base) = path.strip_prefix(&with_slash) {
-                // Input is like "/contracts/subdir/file.txt"
-                // Reconstruct as: /home/.../contracts + subdir/file.txt
-                let candidate = base_abs.join(after_base);
-                tracing::info!("  Candidate path: '{}'", candidate.display());
-                candidates.push(candidate.display().to_string());
-                
-                if candidate.starts_with(&base_abs) {
-                    tracing::info!("  ✓ ALLOWED: Smart resolution match!");
-                    return Ok(());
-                }
-            } else if path == format!("/{}", base_name) {
-                // Exact match: "/contracts" matches ".../contracts"
-                tracing::info!("  ✓ ALLOWED: Exact base match!");
-                return Ok(());
-            }
-        }
-    }
-    
-    tracing::error!("  ✗ DENIED: No match found");
-    bail!(
-        "Security error: Path '{}' not in filesystem allowlist. Allowed: {:?}",
-        path,
-        allow_list
-    );
+    Sandbox::new(allow_list)
+        .with_base_dir(base_dir)
+        .resolve(path)
+        .map(|_| ())
 }
 
 #[cfg(test)]
@@ -192,4 +679,165 @@ mod tests {
         // Should resolve /contracts/... to /home/user/nurones-cide/contracts/...
         assert!(is_allowed("/contracts/COIDE-001/file.txt", &allowlist).is_ok());
     }
+
+    #[cfg(unix)]
+    fn sandbox_fixture() -> (PathBuf, PathBuf, Vec<String>) {
+        let dir = std::env::temp_dir().join(format!("sandbox-test-{}", uuid::Uuid::new_v4()));
+        let root = dir.join("root");
+        let outside = dir.join("outside");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+        std::fs::write(root.join("ok.txt"), "ok").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+        std::os::unix::fs::symlink(outside.join("new.txt"), root.join("dangling")).unwrap();
+        let allowlist = vec![root.to_string_lossy().to_string()];
+        (dir, root, allowlist)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_escape_rejected() {
+        let (dir, root, allowlist) = sandbox_fixture();
+        let sandbox = Sandbox::new(&allowlist);
+        let root_str = root.to_string_lossy();
+
+        assert!(sandbox.resolve(&format!("{}/ok.txt", root_str)).is_ok());
+        assert!(sandbox.resolve(&format!("{}/escape/secret.txt", root_str)).is_err());
+        // Not-yet-existing targets behind a link are resolved too
+        assert!(sandbox.resolve(&format!("{}/escape/new/file.txt", root_str)).is_err());
+        assert!(sandbox.resolve(&format!("{}/dangling", root_str)).is_err());
+        assert!(sandbox.resolve(&format!("{}/sub/../../outside/secret.txt", root_str)).is_err());
+        // New files inside the root are fine
+        assert!(sandbox.resolve(&format!("{}/new/dir/file.txt", root_str)).is_ok());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_prefix_confusion_rejected() {
+        let allowlist = vec!["/tmp/sandbox-prefix".to_string()];
+        assert!(is_allowed("/tmp/sandbox-prefix/file", &allowlist).is_ok());
+        assert!(is_allowed("/tmp/sandbox-prefixfoo/file", &allowlist).is_err());
+        assert!(is_allowed("/tmp/sandbox-prefix/../other", &allowlist).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_beneath() {
+        use std::io::{Read, Write};
+
+        let (dir, root, allowlist) = sandbox_fixture();
+        let sandbox = Sandbox::new(&allowlist);
+        let root_str = root.to_string_lossy();
+
+        let mut content = String::new();
+        sandbox
+            .open_beneath(&format!("{}/ok.txt", root_str), &OpenBeneath::read())
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "ok");
+
+        sandbox
+            .open_beneath(&format!("{}/written.txt", root_str), &OpenBeneath::write())
+            .unwrap()
+            .write_all(b"hi")
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("written.txt")).unwrap(), "hi");
+
+        assert!(sandbox
+            .open_beneath(&format!("{}/escape/secret.txt", root_str), &OpenBeneath::read())
+            .is_err());
+        // A symlink appearing after resolution is refused at open time
+        assert!(open_beneath(&root, Path::new("escape/secret.txt"), &OpenBeneath::read()).is_err());
+        assert!(open_beneath(&root, Path::new("dangling"), &OpenBeneath::write()).is_err());
+        assert!(!root.parent().unwrap().join("outside/new.txt").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_modes_denies_and_excludes() {
+        let rules: Vec<FsRule> = serde_json::from_value(serde_json::json!([
+            {"path": "/srv/sandbox-modes", "mode": "read", "deny": ["private"], "exclude": ["**/.git", "**/.git/**", "**/*.pem"]},
+            {"path": "/srv/sandbox-modes/scratch", "mode": "write"},
+        ]))
+        .unwrap();
+        let sandbox = Sandbox::from_rules(&rules);
+
+        assert!(sandbox.check("/srv/sandbox-modes/docs/a.md", FsMode::Read).is_ok());
+        assert!(sandbox.check("/srv/sandbox-modes/docs/a.md", FsMode::Write).is_err());
+        assert!(sandbox.check("/srv/sandbox-modes/scratch/out.txt", FsMode::Write).is_ok());
+        assert!(sandbox.check("/srv/sandbox-modes/scratch/out.txt", FsMode::Delete).is_err());
+
+        assert!(sandbox.check("/srv/sandbox-modes/private/key", FsMode::Read).is_err());
+        assert!(sandbox.check("/srv/sandbox-modes/repo/.git/config", FsMode::Read).is_err());
+        assert!(sandbox.check("/srv/sandbox-modes/.git/HEAD", FsMode::Read).is_err());
+        assert!(sandbox.check("/srv/sandbox-modes/repo/.git", FsMode::Read).is_err(), "the directory itself");
+        // Outer excludes still apply inside a nested writable entry
+        assert!(sandbox.check("/srv/sandbox-modes/scratch/tls/server.pem", FsMode::Read).is_err());
+        assert!(sandbox.is_hidden(Path::new("/srv/sandbox-modes/certs/ca.pem")));
+
+        // Only unrestricted full-access entries are handed to WASI modules
+        assert!(sandbox.wasi_preopens().is_empty());
+        let open = Sandbox::from_rules(&[
+            FsRule::full("/srv/sandbox-open"),
+            FsRule::full("/srv/sandbox-nested"),
+            FsRule { mode: FsMode::Read, ..FsRule::full("/srv/sandbox-nested/docs") },
+        ]);
+        assert_eq!(open.wasi_preopens(), [Path::new("/srv/sandbox-open")]);
+    }
+
+    #[test]
+    fn test_explain_trace() {
+        let rules: Vec<FsRule> = serde_json::from_value(serde_json::json!([
+            {"path": "/srv/sandbox-explain", "mode": "read", "deny": ["private"]},
+        ]))
+        .unwrap();
+        let sandbox = Sandbox::from_rules(&rules);
+
+        let trace = sandbox.explain("/srv/sandbox-explain/a.txt", FsMode::Read);
+        assert!(trace.allowed);
+        assert_eq!(trace.entry.as_deref(), Some("/srv/sandbox-explain"));
+
+        let trace = sandbox.explain("/srv/sandbox-explain/a.txt", FsMode::Write);
+        assert!(!trace.allowed);
+        assert_eq!(trace.mode, Some(FsMode::Read));
+
+        let trace = sandbox.explain("/sandbox-explain/private/key", FsMode::Read);
+        assert!(trace.shorthand);
+        assert_eq!(trace.blocked_by.as_deref(), Some("deny '/srv/sandbox-explain/private'"));
+
+        let trace = sandbox.explain("/etc/passwd", FsMode::Read);
+        assert!(!trace.allowed && trace.entry.is_none());
+
+        // explain reports exactly what check enforces
+        for (path, access) in [
+            ("/srv/sandbox-explain/a.txt", FsMode::Read),
+            ("/srv/sandbox-explain/a.txt", FsMode::Write),
+            ("/sandbox-explain/a.txt", FsMode::Read),
+            ("/sandbox-explain/private/key", FsMode::Read),
+            ("/etc/passwd", FsMode::Read),
+        ] {
+            let trace = sandbox.explain(path, access);
+            let checked = sandbox.check(path, access);
+            assert_eq!(trace.allowed, checked.is_ok(), "{}", path);
+            if let Ok(canonical) = checked {
+                assert_eq!(trace.resolved, Some(canonical.display().to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_allowlist_entry_forms() {
+        let entries: Vec<FsAllowEntry> =
+            serde_json::from_str(r#"["/tmp", {"path": "/workspace", "mode": "write"}]"#).unwrap();
+        assert_eq!(entries[0], FsAllowEntry::Path("/tmp".to_string()));
+        assert_eq!(entries[0].to_rule().mode, FsMode::Delete);
+        assert_eq!(entries[1].to_rule().mode, FsMode::Write);
+
+        // Plain strings stay plain when saved back
+        assert_eq!(serde_json::to_string(&entries[0]).unwrap(), r#""/tmp""#);
+        assert_eq!(FsMode::for_tool("fs.delete"), FsMode::Delete);
+        assert_eq!(FsMode::for_tool("fs.search"), FsMode::Read);
+    }
 }
@@ -4,6 +4,8 @@ use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+use crate::tool_executor::ToolManifest;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Connection {
     pub id: String,
@@ -11,6 +13,9 @@ pub struct Connection {
     pub conn_type: String,
     pub connected_at: DateTime<Utc>,
     pub last_activity: DateTime<Utc>,
+    /// Identity calls on this connection act as
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +34,12 @@ pub struct ServerState {
     pub context_engine_enabled: Arc<RwLock<bool>>,
 }
 
+impl Default for ServerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ServerState {
     pub fn new() -> Self {
         Self {
@@ -48,10 +59,18 @@ impl ServerState {
                 conn_type,
                 connected_at: now,
                 last_activity: now,
+                user: None,
             },
         );
     }
 
+    /// Bind a caller identity to a registered connection
+    pub async fn bind_user(&self, id: &str, user: String) {
+        if let Some(conn) = self.connections.write().await.get_mut(id) {
+            conn.user = Some(user);
+        }
+    }
+
     pub async fn remove_connection(&self, id: &str) {
         let mut connections = self.connections.write().await;
         connections.remove(id);
@@ -74,6 +93,24 @@ impl ServerState {
         tools.insert(name, tool_status);
     }
 
+    /// Mirror the executor's manifests, keeping each tool's enabled flag
+    pub async fn sync_tools(&self, manifests: &[ToolManifest]) {
+        let mut tools = self.tools.write().await;
+        let previous = std::mem::take(&mut *tools);
+        for manifest in manifests {
+            tools.insert(
+                manifest.name.clone(),
+                ToolStatus {
+                    name: manifest.name.clone(),
+                    version: manifest.version.clone(),
+                    enabled: previous.get(&manifest.name).is_none_or(|t| t.enabled),
+                    permissions: manifest.permissions.clone(),
+                    tool_type: manifest.tool_type().to_string(),
+                },
+            );
+        }
+    }
+
     pub async fn toggle_tool(&self, name: &str, enabled: bool) -> Result<(), String> {
         let mut tools = self.tools.write().await;
         if let Some(tool) = tools.get_mut(name) {
@@ -1,6 +1,7 @@
-use axum::{extract::State, routing::{get, put}, Json, Router};
+use axum::{routing::{get, put}, Json, Router};
 use serde::{Deserialize, Serialize};
-use std::{fs, sync::{Arc, Mutex}};
+use std::{fs, sync::Arc};
+use tokio::sync::watch;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ServerSettings {
@@ -10,10 +11,11 @@ pub struct ServerSettings {
 #[derive(Clone)]
 pub struct SettingsState {
     pub cfg_path: String,
-    pub server_port: Arc<Mutex<u16>>,
+    /// Port the API listener is bound to; sending a new one rebinds it
+    pub server_port: Arc<watch::Sender<u16>>,
 }
 
-pub fn settings_router<S>(cfg_path: String, state: SettingsState) -> Router<S>
+pub fn settings_router<S>(state: SettingsState) -> Router<S>
 where
     S: Clone + Send + Sync + 'static,
 {
@@ -31,7 +33,7 @@ where
 async fn get_server_settings(
     state: SettingsState,
 ) -> Json<ServerSettings> {
-    let port = *state.server_port.lock().unwrap();
+    let port = *state.server_port.borrow();
     Json(ServerSettings { port })
 }
 
@@ -40,9 +42,9 @@ async fn update_server_settings(
     Json(body): Json<ServerSettings>,
 ) -> Json<ServerSettings> {
     // Validate port range
-    if body.port < 1024 || body.port > 65535 {
+    if body.port < 1024 {
         tracing::warn!("Invalid port {} requested, must be 1024-65535", body.port);
-        return Json(ServerSettings { port: *state.server_port.lock().unwrap() });
+        return Json(ServerSettings { port: *state.server_port.borrow() });
     }
 
     // Persist to config file
@@ -51,7 +53,8 @@ async fn update_server_settings(
             if let Ok(mut cfg) = serde_json::from_str::<serde_json::Value>(&content) {
                 cfg["server"]["port"] = serde_json::json!(body.port);
                 if let Ok(updated_content) = serde_json::to_string_pretty(&cfg) {
-                    if let Err(e) = fs::write(&state.cfg_path, updated_content) {
+                    let path = std::path::Path::new(&state.cfg_path);
+                    if let Err(e) = crate::policies::write_atomic(path, updated_content.as_bytes()) {
                         tracing::error!("Failed to write config: {}", e);
                     } else {
                         tracing::info!("Updated server.port to {} in {}", body.port, state.cfg_path);
@@ -64,8 +67,8 @@ async fn update_server_settings(
         }
     }
 
-    // Update in-memory value (restart required to rebind port)
-    *state.server_port.lock().unwrap() = body.port;
-    
+    // The API server rebinds to the new port
+    state.server_port.send_replace(body.port);
+
     Json(body)
 }
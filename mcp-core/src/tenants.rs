@@ -0,0 +1,353 @@
+use crate::observability::Metrics;
+use crate::policies::{tool_matches, PolicyRule};
+use crate::security::{FsAllowEntry, FsRule};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Placeholder for the tenant id in overlay allowlist paths
+const TENANT_PLACEHOLDER: &str = "{tenant}";
+
+/// Longest tenant id accepted
+const MAX_TENANT_ID_LEN: usize = 64;
+
+/// Quota windows tracked before idle ones are dropped
+const MAX_TRACKED_TENANTS: usize = 1024;
+
+/// Per-tenant overlays keyed on `ContextFrame.tenant_id`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenancyPolicy {
+    /// Refuse calls from tenants without an entry in `tenants`
+    pub require_known: bool,
+    /// Overlay for every tenant; a tenant's own entry replaces the fields it sets
+    #[serde(skip_serializing_if = "TenantOverlay::is_default")]
+    pub defaults: TenantOverlay,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub tenants: HashMap<String, TenantOverlay>,
+    /// Tenant each user acts for, when their credential does not name one
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub users: HashMap<String, String>,
+}
+
+/// Settings layered over the shared policy for one tenant
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantOverlay {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Refuse every call from the tenant
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub suspended: bool,
+    /// Replaces the shared `fs_allowlist`; `{tenant}` in a path is the tenant id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fs_allowlist: Option<Vec<FsAllowEntry>>,
+    /// Tool patterns the tenant may use; unset enables every tool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<String>>,
+    /// Rules evaluated after the shared ones, for this tenant's calls only
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<PolicyRule>,
+    #[serde(skip_serializing_if = "TenantQuotas::is_default")]
+    pub quotas: TenantQuotas,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantQuotas {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calls_per_minute: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<u32>,
+}
+
+impl TenantQuotas {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl TenantOverlay {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Letters, digits, `-`, `_` and `.`, so an id can name a directory
+pub fn is_valid_tenant_id(tenant_id: &str) -> bool {
+    !tenant_id.is_empty()
+        && tenant_id.len() <= MAX_TENANT_ID_LEN
+        && tenant_id != "."
+        && tenant_id != ".."
+        && tenant_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+impl TenancyPolicy {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Overlay in effect for a tenant: its own entry over `defaults`, rules from both
+    pub fn resolve(&self, tenant_id: &str) -> TenantOverlay {
+        let mut overlay = self.defaults.clone();
+        if let Some(own) = self.tenants.get(tenant_id) {
+            overlay.description = own.description.clone().or(overlay.description);
+            overlay.suspended |= own.suspended;
+            if own.fs_allowlist.is_some() {
+                overlay.fs_allowlist = own.fs_allowlist.clone();
+            }
+            if own.tools.is_some() {
+                overlay.tools = own.tools.clone();
+            }
+            overlay.rules.extend(own.rules.iter().cloned());
+            overlay.quotas.calls_per_minute = own.quotas.calls_per_minute.or(overlay.quotas.calls_per_minute);
+            overlay.quotas.max_concurrent = own.quotas.max_concurrent.or(overlay.quotas.max_concurrent);
+        }
+        overlay
+    }
+
+    /// Why the tenant may not call `tool`, or `None` if it may
+    pub fn refusal(&self, tenant_id: &str, tool: &str) -> Option<String> {
+        if !is_valid_tenant_id(tenant_id) {
+            return Some(format!("invalid tenant id '{}'", tenant_id));
+        }
+        if self.require_known && !self.tenants.contains_key(tenant_id) {
+            return Some(format!("tenant '{}' is not configured", tenant_id));
+        }
+        let overlay = self.resolve(tenant_id);
+        if overlay.suspended {
+            return Some(format!("tenant '{}' is suspended", tenant_id));
+        }
+        match &overlay.tools {
+            Some(tools) if !tools.iter().any(|p| tool_matches(p, tool)) => {
+                Some(format!("tenant '{}' does not enable '{}'", tenant_id, tool))
+            }
+            _ => None,
+        }
+    }
+
+    /// Why `user` may not act for `tenant_id`, or `None` if it may. Callers are
+    /// bound to the tenant their credential names (`claimed`), else to their
+    /// entry in `users`; once tenancy is configured, unbound callers are refused.
+    pub fn binding_refusal(&self, user: &str, claimed: Option<&str>, tenant_id: &str) -> Option<String> {
+        match claimed.or_else(|| self.users.get(user).map(String::as_str)) {
+            Some(bound) if bound != tenant_id => Some(format!(
+                "'{}' is bound to tenant '{}', not '{}'",
+                user, bound, tenant_id
+            )),
+            Some(_) => None,
+            None if self.is_default() => None,
+            None => Some(format!("'{}' is not bound to a tenant", user)),
+        }
+    }
+
+    /// The tenant's allowlist with `{tenant}` substituted, or `None` to use the shared one
+    pub fn fs_rules(&self, tenant_id: &str) -> Option<Vec<FsRule>> {
+        let entries = self.resolve(tenant_id).fs_allowlist?;
+        // An id that could escape its directory gets an empty allowlist
+        if !is_valid_tenant_id(tenant_id) {
+            return Some(Vec::new());
+        }
+        Some(
+            entries
+                .iter()
+                .map(|entry| {
+                    let mut rule = entry.to_rule();
+                    rule.path = rule.path.replace(TENANT_PLACEHOLDER, tenant_id);
+                    rule
+                })
+                .collect(),
+        )
+    }
+
+    /// Problems in the overlays; rule checks that need the role table are left to the caller
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let overlays = std::iter::once(("defaults".to_string(), &self.defaults))
+            .chain(self.tenants.iter().map(|(id, o)| (format!("tenant '{}'", id), o)));
+        for id in self.tenants.keys().filter(|id| !is_valid_tenant_id(id)) {
+            errors.push(format!("tenancy: invalid tenant id '{}'", id));
+        }
+        for (user, id) in self.users.iter().filter(|(_, id)| !is_valid_tenant_id(id)) {
+            errors.push(format!("tenancy: user '{}' is bound to invalid tenant id '{}'", user, id));
+        }
+        for (scope, overlay) in overlays {
+            for entry in overlay.fs_allowlist.iter().flatten() {
+                let rule = entry.to_rule();
+                if !rule.path.starts_with('/') {
+                    errors.push(format!("tenancy {}: fs_allowlist '{}' is not absolute", scope, rule.path));
+                }
+                for pattern in &rule.exclude {
+                    if let Err(e) = glob::Pattern::new(pattern) {
+                        errors.push(format!("tenancy {}: bad exclude '{}': {}", scope, pattern, e));
+                    }
+                }
+            }
+            if overlay.quotas.calls_per_minute == Some(0) || overlay.quotas.max_concurrent == Some(0) {
+                errors.push(format!("tenancy {}: quotas must be positive", scope));
+            }
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    window_start: Option<Instant>,
+    calls: u32,
+    inflight: u32,
+}
+
+/// Calls in the current minute and calls in progress, per tenant
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TenantUsage {
+    pub calls_this_minute: u32,
+    pub inflight: u32,
+}
+
+/// Enforces `calls_per_minute` (fixed one-minute windows) and `max_concurrent`
+pub struct QuotaTracker {
+    usage: Mutex<HashMap<String, Usage>>,
+    metrics: Arc<Metrics>,
+}
+
+impl QuotaTracker {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        Self {
+            usage: Mutex::new(HashMap::new()),
+            metrics,
+        }
+    }
+
+    /// Count a call against the tenant's quotas. The permit releases the
+    /// concurrency slot when dropped; the error says which quota was hit.
+    pub fn acquire(self: &Arc<Self>, tenant_id: &str, quotas: &TenantQuotas) -> Result<QuotaPermit, String> {
+        let now = Instant::now();
+        let mut usage = self.usage.lock().unwrap();
+        if usage.len() >= MAX_TRACKED_TENANTS {
+            usage.retain(|_, u| u.inflight > 0 || u.window_start.is_some_and(|s| now - s < Duration::from_secs(60)));
+        }
+        let entry = usage.entry(tenant_id.to_string()).or_default();
+        if entry.window_start.is_none_or(|s| now - s >= Duration::from_secs(60)) {
+            entry.window_start = Some(now);
+            entry.calls = 0;
+        }
+
+        if let Some(limit) = quotas.calls_per_minute {
+            if entry.calls >= limit {
+                self.metrics.quota_rejection(tenant_id, "rate");
+                return Err(format!("tenant '{}' exceeded {} calls per minute", tenant_id, limit));
+            }
+        }
+        if let Some(limit) = quotas.max_concurrent {
+            if entry.inflight >= limit {
+                self.metrics.quota_rejection(tenant_id, "concurrency");
+                return Err(format!("tenant '{}' already has {} calls in progress", tenant_id, limit));
+            }
+        }
+        entry.calls += 1;
+        entry.inflight += 1;
+        Ok(QuotaPermit {
+            tracker: self.clone(),
+            tenant_id: tenant_id.to_string(),
+        })
+    }
+
+    pub fn usage(&self, tenant_id: &str) -> TenantUsage {
+        let usage = self.usage.lock().unwrap();
+        match usage.get(tenant_id) {
+            Some(u) => TenantUsage {
+                calls_this_minute: if u.window_start.is_some_and(|s| s.elapsed() < Duration::from_secs(60)) {
+                    u.calls
+                } else {
+                    0
+                },
+                inflight: u.inflight,
+            },
+            None => TenantUsage::default(),
+        }
+    }
+}
+
+/// Concurrency slot held for the duration of a call
+pub struct QuotaPermit {
+    tracker: Arc<QuotaTracker>,
+    tenant_id: String,
+}
+
+impl Drop for QuotaPermit {
+    fn drop(&mut self) {
+        if let Some(usage) = self.tracker.usage.lock().unwrap().get_mut(&self.tenant_id) {
+            usage.inflight = usage.inflight.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlays() {
+        let tenancy: TenancyPolicy = serde_json::from_value(serde_json::json!({
+            "defaults": {"fs_allowlist": ["/workspace/{tenant}"], "quotas": {"calls_per_minute": 100}},
+            "tenants": {
+                "acme": {"tools": ["fs.*"], "quotas": {"max_concurrent": 2}},
+                "globex": {"suspended": true},
+                "initech": {"fs_allowlist": [{"path": "/srv/initech", "mode": "read"}]}
+            }
+        }))
+        .unwrap();
+        assert!(tenancy.validate().is_empty());
+
+        let acme = tenancy.resolve("acme");
+        assert_eq!(acme.quotas, TenantQuotas { calls_per_minute: Some(100), max_concurrent: Some(2) });
+        assert_eq!(tenancy.fs_rules("acme").unwrap()[0], FsRule::full("/workspace/acme"));
+        assert_eq!(tenancy.fs_rules("initech").unwrap()[0].path, "/srv/initech");
+        assert!(tenancy.fs_rules("../etc").unwrap().is_empty());
+
+        assert!(tenancy.refusal("acme", "fs.read").is_none());
+        assert!(tenancy.refusal("acme", "process.execute").unwrap().contains("does not enable"));
+        assert!(tenancy.refusal("globex", "fs.read").unwrap().contains("suspended"));
+        assert!(tenancy.refusal("other", "process.execute").is_none());
+        assert!(tenancy.refusal("a/b", "fs.read").unwrap().contains("invalid tenant id"));
+
+        let strict = TenancyPolicy { require_known: true, ..tenancy.clone() };
+        assert!(strict.refusal("other", "fs.read").unwrap().contains("not configured"));
+
+        let bound = TenancyPolicy {
+            users: HashMap::from([("alice".to_string(), "acme".to_string())]),
+            ..tenancy
+        };
+        assert!(bound.binding_refusal("alice", None, "acme").is_none());
+        assert!(bound.binding_refusal("alice", None, "globex").unwrap().contains("bound to tenant 'acme'"));
+        assert!(bound.binding_refusal("bob", Some("globex"), "globex").is_none());
+        assert!(bound.binding_refusal("alice", Some("initech"), "acme").is_some(), "the credential wins");
+        assert!(bound.binding_refusal("mallory", None, "acme").unwrap().contains("not bound"));
+        assert!(TenancyPolicy::default().binding_refusal("mallory", None, "anything").is_none());
+    }
+
+    #[test]
+    fn test_quotas() {
+        let metrics = Arc::new(Metrics::default());
+        let tracker = Arc::new(QuotaTracker::new(metrics.clone()));
+        let quotas = TenantQuotas { calls_per_minute: Some(3), max_concurrent: Some(1) };
+
+        let permit = tracker.acquire("acme", &quotas).unwrap();
+        assert!(tracker.acquire("acme", &quotas).err().unwrap().contains("in progress"));
+        // Other tenants have their own budget
+        drop(tracker.acquire("globex", &quotas).unwrap());
+        drop(permit);
+
+        drop(tracker.acquire("acme", &quotas).unwrap());
+        assert_eq!(tracker.usage("acme").calls_this_minute, 2);
+        drop(tracker.acquire("acme", &quotas).unwrap());
+        assert!(tracker.acquire("acme", &quotas).err().unwrap().contains("calls per minute"));
+        assert_eq!(tracker.usage("acme").inflight, 0);
+        assert_eq!(metrics.quota_rejection_count("acme", "rate"), 1);
+        assert_eq!(metrics.quota_rejection_count("acme", "concurrency"), 1);
+    }
+}
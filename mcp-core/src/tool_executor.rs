@@ -1,7 +1,17 @@
-use crate::types::{ContextFrame, ToolResult};
+use crate::observability::{self, Metrics};
+use crate::types::{ContextFrame, EventMetadata, RiskLevel, ToolResult};
 use crate::tool_wasi::WasiRunner;
-use crate::security::is_allowed;
+use crate::security::{FsMode, FsRule, Sandbox};
+use crate::egress::EgressPolicy;
+use crate::process_sandbox::ProcessPolicy;
+use crate::secrets::{self, EnvPolicy, SecretChain, SecretScope, SecretStore};
+use crate::redaction::Redactor;
+use crate::tenants::TenancyPolicy;
+use crate::event_bus::{Event, EventBus};
+use crate::context::ContextEngine;
 use async_trait::async_trait;
+use opentelemetry::trace::{FutureExt, Span, SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::KeyValue;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -18,7 +28,7 @@ pub trait ToolExecutor: Send + Sync {
     async fn validate_manifest(&self, path: &str) -> anyhow::Result<bool>;
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Deserialize)]
 pub struct ToolManifest {
     pub name: String,
     pub version: String,
@@ -26,13 +36,113 @@ pub struct ToolManifest {
     pub permissions: Vec<String>,
     #[serde(default)]
     pub description: String,
+    /// The tool takes `dry_run` in its input and plans instead of acting
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl ToolManifest {
+    pub fn side_effect(&self) -> SideEffect {
+        SideEffect::from_permissions(&self.permissions)
+    }
+
+    /// Runtime family shown in the tool listing, from the entry scheme
+    pub fn tool_type(&self) -> &'static str {
+        if self.entry.starts_with("wasm://") {
+            "WASI"
+        } else if self.entry.starts_with("nodejs://") {
+            "Extension"
+        } else {
+            "Native"
+        }
+    }
+}
+
+/// What a tool can change, from the least to the most invasive
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SideEffect {
+    /// Reads local state only
+    None,
+    /// Talks to other hosts
+    Network,
+    Write,
+    Delete,
+    Execute,
+}
+
+impl SideEffect {
+    /// The most invasive class any of a manifest's permission tags implies. Model
+    /// calls (`ai`, `compute`) and telemetry (`emit`) leave the host; tags that are
+    /// not recognized, or none at all, count as `Execute` so they fail closed
+    pub fn from_permissions(permissions: &[String]) -> Self {
+        permissions
+            .iter()
+            .map(|p| match p.as_str() {
+                "read" | "db" | "system" => SideEffect::None,
+                "network" | "ai" | "compute" | "emit" | "observability:emit" => SideEffect::Network,
+                "write" => SideEffect::Write,
+                "delete" => SideEffect::Delete,
+                _ => SideEffect::Execute,
+            })
+            .max()
+            .unwrap_or(SideEffect::Execute)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SideEffect::None => "none",
+            SideEffect::Network => "network",
+            SideEffect::Write => "write",
+            SideEffect::Delete => "delete",
+            SideEffect::Execute => "execute",
+        }
+    }
+
+    /// Why the frame forbids a tool of this class: `read_only` refuses writes, deletes
+    /// and execution, and `risk_level` 2 refuses anything beyond local reads
+    pub fn refusal(&self, tool_id: &str, context: &ContextFrame) -> Option<String> {
+        if *self >= SideEffect::Write && context.flags.as_ref().is_some_and(|f| f.read_only) {
+            return Some(format!("'{}' has {} side effects and the read_only flag is set", tool_id, self.as_str()));
+        }
+        if *self > SideEffect::None && context.risk_level == RiskLevel::Block {
+            return Some(format!("'{}' has {} side effects and risk_level is 2 (block)", tool_id, self.as_str()));
+        }
+        None
+    }
 }
 
 /// In-memory tool executor with security enforcement
 pub struct InMemoryToolExecutor {
     tools: Arc<tokio::sync::RwLock<HashMap<String, ToolManifest>>>,
     wasi_runner: WasiRunner,
-    fs_allowlist: Vec<String>,
+    /// Swapped whole when the allowlist is reloaded
+    sandbox: std::sync::RwLock<Arc<Sandbox>>,
+    /// Network policy for the HTTP tools, swapped whole on reload
+    egress: std::sync::RwLock<Arc<EgressPolicy>>,
+    /// Sandbox for `process.execute`, swapped whole on reload
+    process: std::sync::RwLock<Arc<ProcessPolicy>>,
+    /// Variables `env.get` may return, swapped whole on reload
+    env_policy: std::sync::RwLock<Arc<EnvPolicy>>,
+    /// Per-tenant allowlist overlays, swapped whole on reload
+    tenancy: std::sync::RwLock<Arc<TenancyPolicy>>,
+    /// Secrets tools reference as `${secret:NAME}`
+    secrets: Arc<dyn SecretStore>,
+    /// Where each secret may be sent, swapped whole on reload
+    secret_scopes: std::sync::RwLock<Arc<HashMap<String, SecretScope>>>,
+    /// Applied to every result before it is returned
+    redactor: Arc<Redactor>,
+    metrics: Arc<Metrics>,
+    /// Receives a `tool.executed` event per call
+    events: Option<Arc<dyn EventBus>>,
+    /// Learns a latency baseline per tool from every call
+    engine: Option<Arc<ContextEngine>>,
+}
+
+impl Default for InMemoryToolExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl InMemoryToolExecutor {
@@ -43,7 +153,17 @@ impl InMemoryToolExecutor {
                 tracing::warn!("WASI runner initialization failed, using native fallbacks");
                 WasiRunner::disabled()
             }),
-            fs_allowlist: vec!["/workspace".to_string(), "/tmp".to_string()],
+            sandbox: std::sync::RwLock::new(Arc::new(Sandbox::new(&["/workspace".to_string(), "/tmp".to_string()]))),
+            egress: std::sync::RwLock::new(Arc::new(EgressPolicy::default())),
+            process: std::sync::RwLock::new(Arc::new(ProcessPolicy::default())),
+            env_policy: std::sync::RwLock::new(Arc::new(EnvPolicy::default())),
+            tenancy: std::sync::RwLock::new(Arc::new(TenancyPolicy::default())),
+            secrets: Arc::new(SecretChain::default()),
+            secret_scopes: std::sync::RwLock::new(Arc::new(HashMap::new())),
+            redactor: Arc::new(Redactor::default()),
+            metrics: Arc::new(Metrics::default()),
+            events: None,
+            engine: None,
         }
     }
 
@@ -54,10 +174,231 @@ impl InMemoryToolExecutor {
                 tracing::warn!("WASI runner initialization failed, using native fallbacks");
                 WasiRunner::disabled()
             }),
-            fs_allowlist,
+            sandbox: std::sync::RwLock::new(Arc::new(Sandbox::new(&fs_allowlist))),
+            egress: std::sync::RwLock::new(Arc::new(EgressPolicy::default())),
+            process: std::sync::RwLock::new(Arc::new(ProcessPolicy::default())),
+            env_policy: std::sync::RwLock::new(Arc::new(EnvPolicy::default())),
+            tenancy: std::sync::RwLock::new(Arc::new(TenancyPolicy::default())),
+            secrets: Arc::new(SecretChain::default()),
+            secret_scopes: std::sync::RwLock::new(Arc::new(HashMap::new())),
+            redactor: Arc::new(Redactor::default()),
+            metrics: Arc::new(Metrics::default()),
+            events: None,
+            engine: None,
         }
     }
 
+    /// Replace the allowlist with per-path rules (modes, denies, excludes)
+    pub fn with_fs_rules(self, rules: &[FsRule]) -> Self {
+        self.set_fs_rules(rules);
+        self
+    }
+
+    /// Swap the allowlist while running; calls already in flight keep the old one
+    pub fn set_fs_rules(&self, rules: &[FsRule]) {
+        *self.sandbox.write().unwrap() = Arc::new(Sandbox::from_rules(rules));
+    }
+
+    /// Current sandbox snapshot
+    fn sandbox(&self) -> Arc<Sandbox> {
+        self.sandbox.read().unwrap().clone()
+    }
+
+    /// Sandbox for a tenant: its overlay allowlist if it has one, else the shared sandbox
+    fn sandbox_for(&self, tenant_id: &str) -> Arc<Sandbox> {
+        match self.tenancy.read().unwrap().fs_rules(tenant_id) {
+            Some(rules) => Arc::new(Sandbox::from_rules(&rules)),
+            None => self.sandbox(),
+        }
+    }
+
+    pub fn with_tenancy(self, tenancy: TenancyPolicy) -> Self {
+        self.set_tenancy(tenancy);
+        self
+    }
+
+    /// Swap the tenant overlays while running
+    pub fn set_tenancy(&self, tenancy: TenancyPolicy) {
+        *self.tenancy.write().unwrap() = Arc::new(tenancy);
+    }
+
+    /// Current network policy snapshot
+    fn egress(&self) -> Arc<EgressPolicy> {
+        self.egress.read().unwrap().clone()
+    }
+
+    pub fn with_egress(self, egress: EgressPolicy) -> Self {
+        self.set_egress(egress);
+        self
+    }
+
+    /// Swap the network policy while running
+    pub fn set_egress(&self, egress: EgressPolicy) {
+        *self.egress.write().unwrap() = Arc::new(egress);
+    }
+
+    /// Current process sandbox snapshot
+    fn process(&self) -> Arc<ProcessPolicy> {
+        self.process.read().unwrap().clone()
+    }
+
+    pub fn with_process(self, process: ProcessPolicy) -> Self {
+        self.set_process(process);
+        self
+    }
+
+    /// Swap the process sandbox while running
+    pub fn set_process(&self, process: ProcessPolicy) {
+        *self.process.write().unwrap() = Arc::new(process);
+    }
+
+    /// Current `env.get` allowlist snapshot
+    fn env_policy(&self) -> Arc<EnvPolicy> {
+        self.env_policy.read().unwrap().clone()
+    }
+
+    pub fn with_env_policy(self, env_policy: EnvPolicy) -> Self {
+        self.set_env_policy(env_policy);
+        self
+    }
+
+    /// Swap the `env.get` allowlist while running
+    pub fn set_env_policy(&self, env_policy: EnvPolicy) {
+        *self.env_policy.write().unwrap() = Arc::new(env_policy);
+    }
+
+    fn secret_scopes(&self) -> Arc<HashMap<String, SecretScope>> {
+        self.secret_scopes.read().unwrap().clone()
+    }
+
+    pub fn with_secret_scopes(self, scopes: HashMap<String, SecretScope>) -> Self {
+        self.set_secret_scopes(scopes);
+        self
+    }
+
+    /// Swap the secret scopes while running
+    pub fn set_secret_scopes(&self, scopes: HashMap<String, SecretScope>) {
+        *self.secret_scopes.write().unwrap() = Arc::new(scopes);
+    }
+
+    pub fn with_secrets(mut self, secrets: Arc<dyn SecretStore>) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    pub fn redactor(&self) -> &Arc<Redactor> {
+        &self.redactor
+    }
+
+    /// Redact a result's output and error unless the tool opted out
+    fn redact_result(&self, tool_id: &str, mut result: ToolResult) -> ToolResult {
+        if self.redactor.applies_to(tool_id) {
+            if let Some(output) = result.output.as_mut() {
+                self.redactor.redact_value(tool_id, output);
+            }
+            result.error = result.error.map(|e| self.redactor.redact_str(tool_id, &e));
+        }
+        result
+    }
+
+    /// Redact an execution error the same way, since errors often quote inputs or responses
+    fn redact_error(&self, tool_id: &str, error: anyhow::Error) -> anyhow::Error {
+        if self.redactor.applies_to(tool_id) {
+            anyhow::anyhow!(self.redact_message(tool_id, &format!("{:#}", error)))
+        } else {
+            error
+        }
+    }
+
+    /// Error text as it may leave the process, e.g. as a span status
+    fn redact_message(&self, tool_id: &str, message: &str) -> String {
+        if self.redactor.applies_to(tool_id) {
+            self.redactor.redact_str(tool_id, message)
+        } else {
+            message.to_string()
+        }
+    }
+
+    /// Report tool call counters and latency into a shared metrics registry
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Publish a `tool.executed` event for every call, on the stream of its `reason_trace_id`
+    pub fn with_events(mut self, events: Arc<dyn EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Feed each call's latency to the context engine as `tool_duration.<tool>`
+    pub fn with_context_engine(mut self, engine: Arc<ContextEngine>) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    /// Drop denied and excluded paths from a listing or search result produced by a WASI module
+    fn filter_hidden(sandbox: &Sandbox, base: &std::path::Path, output: &mut serde_json::Value) {
+        for key in ["entries", "matches", "files", "results"] {
+            if let Some(items) = output.get_mut(key).and_then(|v| v.as_array_mut()) {
+                items.retain(|item| {
+                    let path = item
+                        .get("path")
+                        .or_else(|| item.get("name"))
+                        .and_then(|v| v.as_str())
+                        .map(|p| base.join(p));
+                    !path.is_some_and(|p| sandbox.is_hidden(&p))
+                });
+            }
+        }
+    }
+
+    /// Runtime label for a registered tool, derived from its manifest entry scheme
+    async fn runtime_of(&self, tool_id: &str) -> &'static str {
+        let tools = self.tools.read().await;
+        match tools.get(tool_id).map(|t| t.entry.as_str()) {
+            Some(entry) if entry.starts_with("wasm://") => "wasi",
+            Some(entry) if entry.starts_with("nodejs://") => "nodejs",
+            Some(entry) if entry.starts_with("native://") => "native",
+            Some(_) => "native",
+            None => "unknown",
+        }
+    }
+
+    /// Run a native filesystem tool on the blocking pool
+    async fn execute_native_fs(
+        tool_id: &str,
+        sandbox: Arc<Sandbox>,
+        input: serde_json::Value,
+        context: ContextFrame,
+        start: std::time::Instant,
+    ) -> anyhow::Result<ToolResult> {
+        let name = tool_id.to_string();
+        let result = tokio::task::spawn_blocking(move || crate::fs_tools::run(&name, &sandbox, &input)).await?;
+        Ok(ToolResult {
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| format!("{} failed: {:#}", tool_id, e)),
+            output: result.ok(),
+            execution_time: start.elapsed().as_millis() as u64,
+            context_used: context,
+        })
+    }
+
+    /// Plan a native filesystem tool on the blocking pool; planning reads files and walks trees
+    async fn plan_native_fs(
+        tool_id: &str,
+        sandbox: Arc<Sandbox>,
+        input: serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        let name = tool_id.to_string();
+        tokio::task::spawn_blocking(move || crate::dry_run::plan(&name, &sandbox, &input)).await?
+    }
+
     /// Execute session compression tool (native Node.js)
     async fn execute_session_compression(
         &self,
@@ -84,12 +425,22 @@ impl InMemoryToolExecutor {
             "reason_trace_id": context.reason_trace_id.clone(),
             "tenant_id": context.tenant_id.clone(),
         });
+        let carrier = trace_carrier();
+        let full_input = with_trace_meta(full_input, &carrier);
         
         let input_json = serde_json::to_string(&full_input)?;
         
         // Execute via Node.js CLI
+        let mut span = observability::child_span(
+            "subprocess.exec",
+            vec![
+                KeyValue::new("process.executable.name", "node"),
+                KeyValue::new("process.command_args", cli_path),
+            ],
+        );
         let mut child = Command::new("node")
             .arg(cli_path)
+            .envs(trace_env(&carrier))
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -102,6 +453,11 @@ impl InMemoryToolExecutor {
         
         // Wait and capture output
         let output = child.wait_with_output()?;
+        span.set_attribute(KeyValue::new("process.exit_code", output.status.code().unwrap_or(-1) as i64));
+        if !output.status.success() {
+            span.set_status(Status::error("session compression exited non-zero"));
+        }
+        span.end();
         
         if output.status.success() {
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -121,7 +477,11 @@ impl InMemoryToolExecutor {
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr);
             let stdout = String::from_utf8_lossy(&output.stdout);
-            tracing::error!("Session compression failed. stderr: {}, stdout: {}", stderr, stdout);
+            tracing::error!(
+                "Session compression failed. stderr: {}, stdout: {}",
+                self.redactor.redact_str("log", &stderr),
+                self.redactor.redact_str("log", &stdout)
+            );
             Ok(ToolResult {
                 success: false,
                 output: None,
@@ -142,7 +502,6 @@ impl InMemoryToolExecutor {
         start: std::time::Instant,
     ) -> anyhow::Result<ToolResult> {
         use std::process::{Command, Stdio};
-        use std::io::Write;
         
         // Create Node.js script to load and execute the extension
         let script = format!(r#"
@@ -164,13 +523,22 @@ impl InMemoryToolExecutor {
             }})();
         "#, entry_path = entry_path, tool_id = tool_id);
         
-        let input_json = serde_json::to_string(&input)?;
+        let carrier = trace_carrier();
+        let input_json = serde_json::to_string(&with_trace_meta(input, &carrier))?;
         
         // Execute via Node.js
-        let mut child = Command::new("node")
+        let mut span = observability::child_span(
+            "subprocess.exec",
+            vec![
+                KeyValue::new("process.executable.name", "node"),
+                KeyValue::new("mcp.extension.entry", entry_path.to_string()),
+            ],
+        );
+        let child = Command::new("node")
             .arg("-e")
             .arg(&script)
             .arg(&input_json)
+            .envs(trace_env(&carrier))
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -178,12 +546,14 @@ impl InMemoryToolExecutor {
         
         // Wait and capture output
         let output = child.wait_with_output()?;
+        span.set_attribute(KeyValue::new("process.exit_code", output.status.code().unwrap_or(-1) as i64));
+        span.end();
         
         let stdout = String::from_utf8_lossy(&output.stdout);
         let stderr = String::from_utf8_lossy(&output.stderr);
         
         if !stderr.is_empty() {
-            tracing::warn!("Extension stderr: {}", stderr);
+            tracing::warn!("Extension stderr: {}", self.redactor.redact_str("log", &stderr));
         }
         
         // Parse the tool output
@@ -202,7 +572,11 @@ impl InMemoryToolExecutor {
                 })
             }
             Err(e) => {
-                tracing::error!("Failed to parse extension output: {}. Output: {}", e, stdout);
+                tracing::error!(
+                    "Failed to parse extension output: {}. Output: {}",
+                    e,
+                    self.redactor.redact_str("log", &stdout)
+                );
                 Ok(ToolResult {
                     success: false,
                     output: None,
@@ -214,6 +588,15 @@ impl InMemoryToolExecutor {
         }
     }
 
+    /// Manifest of a registered tool; its permissions are what policies match
+    pub async fn manifest(&self, tool_id: &str) -> Option<ToolManifest> {
+        self.tools.read().await.get(tool_id).cloned()
+    }
+
+    pub async fn manifests(&self) -> Vec<ToolManifest> {
+        self.tools.read().await.values().cloned().collect()
+    }
+
     /// Register a tool from manifest file
     pub async fn register_tool(&self, manifest_path: &str) -> anyhow::Result<()> {
         let content = tokio::fs::read_to_string(manifest_path).await?;
@@ -226,6 +609,33 @@ impl InMemoryToolExecutor {
         Ok(())
     }
 
+    /// Replace every registered tool with the manifests in `dir_path`.
+    /// The directory is read in full first, so a missing one changes nothing.
+    pub async fn reload_tools(&self, dir_path: &str) -> anyhow::Result<usize> {
+        let mut loaded = HashMap::new();
+        let mut entries = tokio::fs::read_dir(dir_path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let manifest = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_json::from_str::<ToolManifest>(&content)?));
+            match manifest {
+                Ok(manifest) => {
+                    loaded.insert(manifest.name.clone(), manifest);
+                }
+                Err(e) => tracing::error!("Failed to load tool {:?}: {}", path, e),
+            }
+        }
+        let count = loaded.len();
+        *self.tools.write().await = loaded;
+        tracing::info!("Reloaded {} tools from {}", count, dir_path);
+        Ok(count)
+    }
+
     /// Load all tools from directory
     pub async fn load_tools(&self, dir_path: &str) -> anyhow::Result<()> {
         let mut entries = tokio::fs::read_dir(dir_path).await?;
@@ -243,15 +653,70 @@ impl InMemoryToolExecutor {
     }
 }
 
-#[async_trait]
-impl ToolExecutor for InMemoryToolExecutor {
-    async fn execute(
+/// Who a tool call runs for, as resolved and authorized by the API
+#[derive(Clone, Debug, Default)]
+pub struct Caller {
+    pub user: String,
+    pub role: Option<String>,
+}
+
+tokio::task_local! {
+    static CALLER: Caller;
+}
+
+/// Run `future` (typically an `execute` call) on behalf of `caller`
+pub async fn run_as<F: std::future::Future>(caller: Caller, future: F) -> F::Output {
+    CALLER.scope(caller, future).await
+}
+
+/// Caller of the running tool; anonymous outside `run_as`
+fn current_caller() -> Caller {
+    CALLER.try_with(Caller::clone).unwrap_or_default()
+}
+
+/// W3C trace context of the active span, keyed `traceparent`/`tracestate`
+fn trace_carrier() -> HashMap<String, String> {
+    observability::inject_trace_context(&opentelemetry::Context::current())
+}
+
+/// Trace context as subprocess environment (`TRACEPARENT`/`TRACESTATE`)
+fn trace_env(carrier: &HashMap<String, String>) -> Vec<(String, String)> {
+    carrier
+        .iter()
+        .map(|(k, v)| (k.to_ascii_uppercase(), v.clone()))
+        .collect()
+}
+
+/// Merge trace context into the input's MCP `_meta` object
+fn with_trace_meta(mut input: serde_json::Value, carrier: &HashMap<String, String>) -> serde_json::Value {
+    if carrier.is_empty() {
+        return input;
+    }
+    if let Some(obj) = input.as_object_mut() {
+        let meta = obj
+            .entry("_meta")
+            .or_insert_with(|| serde_json::json!({}));
+        if let Some(meta) = meta.as_object_mut() {
+            for (k, v) in carrier {
+                meta.insert(k.clone(), serde_json::Value::String(v.clone()));
+            }
+        }
+    }
+    input
+}
+
+impl InMemoryToolExecutor {
+    /// Route a call to the WASI, Node.js or native implementation of a tool
+    async fn dispatch(
         &self,
         tool_id: &str,
         input: serde_json::Value,
         context: ContextFrame,
     ) -> anyhow::Result<ToolResult> {
         let start = std::time::Instant::now();
+        // One allowlist snapshot for the whole call, even if it is reloaded meanwhile;
+        // tenants with an allowlist overlay only see their own roots
+        let sandbox = self.sandbox_for(&context.tenant_id);
 
         // Validate context
         context.validate().map_err(|e| anyhow::anyhow!(e))?;
@@ -262,6 +727,55 @@ impl ToolExecutor for InMemoryToolExecutor {
             .get(tool_id)
             .ok_or_else(|| anyhow::anyhow!("Tool not found: {}", tool_id))?;
 
+        // The frame's read-only and block semantics hold for every runtime
+        if let Some(refusal) = tool.side_effect().refusal(tool_id, &context) {
+            tracing::warn!("Refused {} for trace {}: {}", tool_id, context.reason_trace_id, refusal);
+            return Ok(ToolResult {
+                success: false,
+                output: None,
+                error: Some(format!("Blocked: {}", refusal)),
+                execution_time: start.elapsed().as_millis() as u64,
+                context_used: context,
+            });
+        }
+
+        // Dry runs never reach a tool that would act for real
+        let mut input = input;
+        if context.flags.as_ref().is_some_and(|f| f.dry_run) && tool.side_effect() > SideEffect::None {
+            let plan = match tool_id {
+                _ if crate::dry_run::PLANNED_TOOLS.contains(&tool_id) => {
+                    Some(Self::plan_native_fs(tool_id, sandbox.clone(), input.clone()).await)
+                }
+                _ if tool.dry_run && input.is_object() => None,
+                _ if tool.dry_run => Some(Err(anyhow::anyhow!("'{}' input must be an object", tool_id))),
+                _ => Some(Err(anyhow::anyhow!("'{}' does not support dry runs", tool_id))),
+            };
+            match plan {
+                Some(Ok(plan)) => {
+                    return Ok(ToolResult {
+                        success: true,
+                        output: Some(serde_json::json!({ "dry_run": true, "plan": plan })),
+                        error: None,
+                        execution_time: start.elapsed().as_millis() as u64,
+                        context_used: context,
+                    });
+                }
+                Some(Err(e)) => {
+                    return Ok(ToolResult {
+                        success: false,
+                        output: None,
+                        error: Some(format!("Dry run failed: {:#}", e)),
+                        execution_time: start.elapsed().as_millis() as u64,
+                        context_used: context,
+                    });
+                }
+                // The tool plans for itself
+                None => {
+                    input["dry_run"] = serde_json::Value::Bool(true);
+                }
+            }
+        }
+
         // Simulate tool execution (production would invoke WASI runtime)
         tracing::info!(
             "Executing tool: {} with context trace: {}",
@@ -269,8 +783,9 @@ impl ToolExecutor for InMemoryToolExecutor {
             context.reason_trace_id
         );
 
-        // Check if this is a WASI tool
-        if tool.entry.starts_with("wasm://") {
+        // WASI tools. Filesystem tools with a native implementation always run
+        // natively below, where every access goes through the sandbox.
+        if tool.entry.starts_with("wasm://") && !crate::fs_tools::is_native(tool_id) {
             let wasm_path = tool.entry.trim_start_matches("wasm://");
             
             tracing::info!("Executing WASI tool: {} from {}", tool_id, wasm_path);
@@ -279,109 +794,51 @@ impl ToolExecutor for InMemoryToolExecutor {
             let mut resolved_input = input.clone();
             if tool_id.starts_with("fs.") {
                 if let Some(path) = input.get("path").and_then(|v| v.as_str()) {
-                    tracing::debug!("Checking path '{}' against allowlist: {:?}", path, self.fs_allowlist);
+                    tracing::debug!("Checking path '{}' against allowlist: {:?}", path, sandbox.roots().collect::<Vec<_>>());
+
+                    // Regular path (no wildcards) - resolve through symlinks and validate
+                    let resolved = sandbox
+                        .check(path, FsMode::for_tool(tool_id))
+                        .map_err(|e| anyhow::anyhow!("Security error: {}", e))?;
+                    let resolved_str = resolved.to_string_lossy().to_string();
+                    tracing::info!("Resolved path '{}' to '{}'", path, resolved_str);
                     
-                    // Check if path contains wildcards
-                    if path.contains('*') || path.contains('?') {
-                        // Expand wildcards to list of files
-                        match crate::security::expand_wildcard_path(path, &self.fs_allowlist) {
-                            Ok(matched_files) => {
-                                tracing::info!("Wildcard '{}' expanded to {} files", path, matched_files.len());
-                                
-                                // For fs.read with wildcards, read all matching files
-                                if tool_id == "fs.read" {
-                                    let mut file_contents = Vec::new();
-                                    for file_path in &matched_files {
-                                        let file_str = file_path.to_string_lossy().to_string();
-                                        match tokio::fs::read_to_string(&file_str).await {
-                                            Ok(content) => {
-                                                file_contents.push(serde_json::json!({
-                                                    "path": file_str,
-                                                    "name": file_path.file_name().and_then(|n| n.to_str()).unwrap_or("unknown"),
-                                                    "content": content,
-                                                    "size": content.len()
-                                                }));
-                                            }
-                                            Err(e) => {
-                                                tracing::warn!("Failed to read {}: {}", file_str, e);
-                                            }
-                                        }
-                                    }
-                                    
-                                    return Ok(ToolResult {
-                                        success: true,
-                                        output: Some(serde_json::json!({
-                                            "pattern": path,
-                                            "matched_count": matched_files.len(),
-                                            "files": file_contents
-                                        })),
-                                        error: None,
-                                        execution_time: start.elapsed().as_millis() as u64,
-                                        context_used: context,
-                                    });
-                                } else if tool_id == "fs.list" {
-                                    // For fs.list with wildcards, return file list
-                                    let file_list: Vec<_> = matched_files.iter().map(|p| {
-                                        let metadata = std::fs::metadata(p).ok();
-                                        serde_json::json!({
-                                            "name": p.file_name().and_then(|n| n.to_str()).unwrap_or("unknown"),
-                                            "path": p.to_string_lossy().to_string(),
-                                            "is_dir": metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false),
-                                            "size": metadata.as_ref().map(|m| m.len()).unwrap_or(0)
-                                        })
-                                    }).collect();
-                                    
-                                    return Ok(ToolResult {
-                                        success: true,
-                                        output: Some(serde_json::json!({
-                                            "pattern": path,
-                                            "matched_count": matched_files.len(),
-                                            "entries": file_list
-                                        })),
-                                        error: None,
-                                        execution_time: start.elapsed().as_millis() as u64,
-                                        context_used: context,
-                                    });
-                                }
-                            }
-                            Err(e) => {
-                                return Ok(ToolResult {
-                                    success: false,
-                                    output: None,
-                                    error: Some(format!("Wildcard expansion failed: {}. Use fs.list to see available files first.", e)),
-                                    execution_time: start.elapsed().as_millis() as u64,
-                                    context_used: context,
-                                });
-                            }
-                        }
-                    } else {
-                        // Regular path (no wildcards) - validate and resolve
-                        is_allowed(path, &self.fs_allowlist)
-                            .map_err(|e| anyhow::anyhow!("Security error: {}", e))?;
-                        
-                        // Resolve the path before passing to WASI
-                        let resolved = crate::security::resolve_path(path, &self.fs_allowlist)
-                            .map_err(|e| anyhow::anyhow!("Failed to resolve path: {}", e))?;
-                        let resolved_str = resolved.to_string_lossy().to_string();
-                        tracing::info!("Resolved path '{}' to '{}'", path, resolved_str);
-                        
-                        // Update input with resolved path
-                        if let Some(obj) = resolved_input.as_object_mut() {
-                            obj.insert("path".to_string(), serde_json::Value::String(resolved_str));
-                        }
+                    // Update input with resolved path
+                    if let Some(obj) = resolved_input.as_object_mut() {
+                        obj.insert("path".to_string(), serde_json::Value::String(resolved_str));
                     }
                 }
             }
             
-            // Convert allowlist to preopen dirs
-            let preopen_dirs: Vec<&str> = self.fs_allowlist.iter().map(|s| s.as_str()).collect();
-            
+            // Only entries without deny, exclude or read-only limits are preopened
+            let roots: Vec<String> = sandbox.wasi_preopens().iter().map(|r| r.to_string_lossy().to_string()).collect();
+            let preopen_dirs: Vec<&str> = roots.iter().map(|s| s.as_str()).collect();
+        
             // Execute WASI module with resolved input
-            match self.wasi_runner.exec(wasm_path, &resolved_input, &preopen_dirs) {
+            let mut span = observability::child_span(
+                "wasi.exec",
+                vec![KeyValue::new("mcp.wasi.module", wasm_path.to_string())],
+            );
+            let carrier = trace_carrier();
+            let resolved_input = with_trace_meta(resolved_input, &carrier);
+            let exec_result =
+                self.wasi_runner
+                    .exec_with_env(wasm_path, &resolved_input, &preopen_dirs, &trace_env(&carrier));
+            if let Err(e) = &exec_result {
+                span.set_status(Status::error(self.redact_message(tool_id, &e.to_string())));
+            }
+            span.end();
+
+            match exec_result {
                 Ok(output_str) => {
-                    let output: serde_json::Value = serde_json::from_str(&output_str)
+                    let mut output: serde_json::Value = serde_json::from_str(&output_str)
                         .unwrap_or_else(|_| serde_json::json!({ "result": output_str }));
-                    
+                    if tool_id.starts_with("fs.") {
+                        if let Some(base) = resolved_input.get("path").and_then(|v| v.as_str()) {
+                            Self::filter_hidden(&sandbox, std::path::Path::new(base), &mut output);
+                        }
+                    }
+                
                     return Ok(ToolResult {
                         success: true,
                         output: Some(output),
@@ -391,7 +848,7 @@ impl ToolExecutor for InMemoryToolExecutor {
                     });
                 }
                 Err(e) => {
-                    tracing::error!("WASI execution failed: {}", e);
+                    tracing::error!("WASI execution failed: {}", self.redactor.redact_str("log", &e.to_string()));
                     return Ok(ToolResult {
                         success: false,
                         output: None,
@@ -425,6 +882,7 @@ impl ToolExecutor for InMemoryToolExecutor {
             // Handle built-in native tools inline
             match tool_id {
                 "http.request" | "fetch.url" | "env.get" | "process.execute" | 
+                "fs.write" | "fs.delete" | "fs.move" | "fs.stat" | "fs.mkdir" | "fs.search" |
                 "telemetry.push" | "db.query" | "db.execute" | "db.schema" |
                 "embedding.generate" | "completion.stream" => {
                     // Continue to tool-specific implementation below
@@ -441,148 +899,92 @@ impl ToolExecutor for InMemoryToolExecutor {
             }
         }
 
-        // For filesystem tools, check context-governed permissions
-        if tool_id.starts_with("fs.") && context.flags.as_ref().map_or(false, |f| f.read_only) {
-            if tool_id == "fs.write" {
+        // Native implementations for common tools (fallback when WASI not available)
+        match tool_id {
+            "fs.read" | "fs.list" | "fs.write" | "fs.delete" | "fs.move" | "fs.stat" | "fs.mkdir" | "fs.search" => {
+                return Self::execute_native_fs(tool_id, sandbox, input, context, start).await;
+            }
+            "http.request" | "fetch.url" if !tool.permissions.iter().any(|p| p == "network") => {
                 return Ok(ToolResult {
                     success: false,
                     output: None,
-                    error: Some("Write operation blocked by read_only flag".to_string()),
+                    error: Some(format!("Tool '{}' does not declare the 'network' permission", tool_id)),
                     execution_time: start.elapsed().as_millis() as u64,
                     context_used: context,
                 });
             }
-        }
-
-        // Native implementations for common tools (fallback when WASI not available)
-        match tool_id {
-            "fs.read" => {
-                // Extract path from input
-                let path = input.get("path")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| anyhow::anyhow!("fs.read requires 'path' parameter"))?;
-                
-                // Enforce allowlist
-                is_allowed(path, &self.fs_allowlist)
-                    .map_err(|e| anyhow::anyhow!("Security error: {}", e))?;
-                
-                // Read file
-                match tokio::fs::read_to_string(path).await {
-                    Ok(content) => {
-                        return Ok(ToolResult {
-                            success: true,
-                            output: Some(serde_json::json!({
-                                "content": content,
-                                "path": path,
-                                "size": content.len()
-                            })),
-                            error: None,
-                            execution_time: start.elapsed().as_millis() as u64,
-                            context_used: context,
-                        });
-                    }
-                    Err(e) => {
-                        return Ok(ToolResult {
-                            success: false,
-                            output: None,
-                            error: Some(format!("Failed to read file: {}", e)),
-                            execution_time: start.elapsed().as_millis() as u64,
-                            context_used: context,
-                        });
-                    }
-                }
-            }
-            "fs.list" => {
-                let path = input.get("path")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(".");
-                
-                is_allowed(path, &self.fs_allowlist)
-                    .map_err(|e| anyhow::anyhow!("Security error: {}", e))?;
-                
-                match tokio::fs::read_dir(path).await {
-                    Ok(mut entries) => {
-                        let mut files = Vec::new();
-                        while let Ok(Some(entry)) = entries.next_entry().await {
-                            if let Ok(metadata) = entry.metadata().await {
-                                files.push(serde_json::json!({
-                                    "name": entry.file_name().to_string_lossy().to_string(),
-                                    "is_dir": metadata.is_dir(),
-                                    "size": metadata.len()
-                                }));
-                            }
-                        }
-                        return Ok(ToolResult {
-                            success: true,
-                            output: Some(serde_json::json!({
-                                "path": path,
-                                "entries": files
-                            })),
-                            error: None,
-                            execution_time: start.elapsed().as_millis() as u64,
-                            context_used: context,
-                        });
-                    }
-                    Err(e) => {
-                        return Ok(ToolResult {
-                            success: false,
-                            output: None,
-                            error: Some(format!("Failed to list directory: {}", e)),
-                            execution_time: start.elapsed().as_millis() as u64,
-                            context_used: context,
-                        });
-                    }
-                }
-            }
             "http.request" => {
                 let url = input.get("url")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| anyhow::anyhow!("http.request requires 'url' parameter"))?;
                 
-                let method = input.get("method").and_then(|v| v.as_str()).unwrap_or("GET");
+                let method = match input.get("method").and_then(|v| v.as_str()).unwrap_or("GET").to_uppercase().as_str() {
+                    "POST" => reqwest::Method::POST,
+                    "PUT" => reqwest::Method::PUT,
+                    "DELETE" => reqwest::Method::DELETE,
+                    _ => reqwest::Method::GET,
+                };
                 let body = input.get("body");
                 
-                let client = reqwest::Client::new();
-                let mut request = match method.to_uppercase().as_str() {
-                    "GET" => client.get(url),
-                    "POST" => client.post(url),
-                    "PUT" => client.put(url),
-                    "DELETE" => client.delete(url),
-                    _ => client.get(url),
+                let user_headers = input.get("headers").and_then(|v| v.as_object());
+                // Header values may reference `${secret:NAME}`, expanded only when the
+                // secret's scope covers the host and caller; the values are scrubbed
+                // from the response so they never reach the caller
+                let host = reqwest::Url::parse(url)
+                    .ok()
+                    .and_then(|u| u.host_str().map(|h| h.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase()))
+                    .unwrap_or_default();
+                let caller = current_caller();
+                let scopes = self.secret_scopes();
+                let permit = |name: &str| match scopes.get(name) {
+                    Some(scope) => scope.check(name, &host, caller.role.as_deref(), &context.tenant_id),
+                    None => anyhow::bail!("secret '{}' has no entry in secret_scopes", name),
                 };
-                
-                if let Some(headers) = input.get("headers").and_then(|v| v.as_object()) {
-                    for (key, value) in headers {
-                        if let Some(val_str) = value.as_str() {
-                            request = request.header(key, val_str);
+                let mut used_secrets = Vec::new();
+                let mut headers = Vec::new();
+                let mut secret_headers = Vec::new();
+                for (key, value) in user_headers.into_iter().flatten() {
+                    let Some(value) = value.as_str() else { continue };
+                    match secrets::expand(self.secrets.as_ref(), value, &mut used_secrets, &permit) {
+                        Ok(expanded) => {
+                            if expanded != value {
+                                secret_headers.push(key.clone());
+                            }
+                            headers.push((key.clone(), expanded));
+                        }
+                        Err(e) => {
+                            return Ok(ToolResult {
+                                success: false,
+                                output: None,
+                                error: Some(format!("HTTP request failed: header '{}': {}", key, e)),
+                                execution_time: start.elapsed().as_millis() as u64,
+                                context_used: context,
+                            });
                         }
                     }
                 }
-                
-                if let Some(body_val) = body {
-                    request = request.json(body_val);
+
+                // Propagate the trace unless the caller set its own trace headers
+                let caller_traced = user_headers.is_some_and(|h| {
+                    h.keys().any(|k| k.eq_ignore_ascii_case(observability::TRACEPARENT))
+                });
+                if !caller_traced {
+                    headers.extend(trace_carrier());
                 }
                 
-                match request.send().await {
+                let egress = self.egress().for_tenant(&context.tenant_id);
+                match egress.send(method, url, &headers, &secret_headers, body).await {
                     Ok(response) => {
-                        let status = response.status().as_u16();
-                        let headers: std::collections::HashMap<String, String> = response
-                            .headers()
-                            .iter()
-                            .filter_map(|(k, v)| {
-                                v.to_str().ok().map(|val| (k.to_string(), val.to_string()))
-                            })
-                            .collect();
-                        
-                        let body = response.text().await.unwrap_or_default();
-                        
+                        let mut output = serde_json::json!({
+                            "status": response.status,
+                            "url": response.url.as_str(),
+                            "headers": response.headers,
+                            "body": String::from_utf8_lossy(&response.body)
+                        });
+                        secrets::scrub(&mut output, &used_secrets);
                         return Ok(ToolResult {
-                            success: status < 400,
-                            output: Some(serde_json::json!({
-                                "status": status,
-                                "headers": headers,
-                                "body": body
-                            })),
+                            success: response.status < 400,
+                            output: Some(output),
                             error: None,
                             execution_time: start.elapsed().as_millis() as u64,
                             context_used: context,
@@ -592,7 +994,7 @@ impl ToolExecutor for InMemoryToolExecutor {
                         return Ok(ToolResult {
                             success: false,
                             output: None,
-                            error: Some(format!("HTTP request failed: {}", e)),
+                            error: Some(secrets::scrub_str(&format!("HTTP request failed: {:#}", e), &used_secrets)),
                             execution_time: start.elapsed().as_millis() as u64,
                             context_used: context,
                         });
@@ -604,10 +1006,11 @@ impl ToolExecutor for InMemoryToolExecutor {
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| anyhow::anyhow!("fetch.url requires 'url' parameter"))?;
                 
-                let client = reqwest::Client::new();
-                match client.get(url).send().await {
+                let headers: Vec<(String, String)> = trace_carrier().into_iter().collect();
+                let egress = self.egress().for_tenant(&context.tenant_id);
+                match egress.send(reqwest::Method::GET, url, &headers, &[], None).await {
                     Ok(response) => {
-                        let content = response.text().await.unwrap_or_default();
+                        let content = String::from_utf8_lossy(&response.body).to_string();
                         return Ok(ToolResult {
                             success: true,
                             output: Some(serde_json::json!({
@@ -624,7 +1027,7 @@ impl ToolExecutor for InMemoryToolExecutor {
                         return Ok(ToolResult {
                             success: false,
                             output: None,
-                            error: Some(format!("Fetch failed: {}", e)),
+                            error: Some(format!("Fetch failed: {:#}", e)),
                             execution_time: start.elapsed().as_millis() as u64,
                             context_used: context,
                         });
@@ -636,13 +1039,30 @@ impl ToolExecutor for InMemoryToolExecutor {
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| anyhow::anyhow!("env.get requires 'key' parameter"))?;
                 
+                // Secrets are never exposed; other variables only when allowlisted
+                let caller = current_caller();
+                let env_policy = self.env_policy();
+                if self.secrets.get(key).is_some()
+                    || !env_policy.allows(key, caller.role.as_deref(), &context.tenant_id)
+                {
+                    return Ok(ToolResult {
+                        success: false,
+                        output: None,
+                        error: Some(format!("Environment variable '{}' is not exposed", key)),
+                        execution_time: start.elapsed().as_millis() as u64,
+                        context_used: context,
+                    });
+                }
+                
                 match std::env::var(key) {
                     Ok(value) => {
+                        let masked = env_policy.mask_secrets && secrets::looks_secret(key, &value);
                         return Ok(ToolResult {
                             success: true,
                             output: Some(serde_json::json!({
                                 "key": key,
-                                "value": value
+                                "value": if masked { "[REDACTED]".to_string() } else { value },
+                                "masked": masked
                             })),
                             error: None,
                             execution_time: start.elapsed().as_millis() as u64,
@@ -669,21 +1089,52 @@ impl ToolExecutor for InMemoryToolExecutor {
                     .and_then(|v| v.as_array())
                     .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
                     .unwrap_or_default();
+                let cwd = input.get("cwd").and_then(|v| v.as_str());
                 
-                use std::process::Command;
-                match Command::new(command).args(&args).output() {
+                // Allowlisted command and arguments, run inside an allowlisted directory
+                let process = self.process();
+                let prepared = process
+                    .authorize(command, &args)
+                    .and_then(|executable| Ok((executable, process.working_dir(cwd, &sandbox)?)));
+                let (executable, cwd) = match prepared {
+                    Ok(prepared) => prepared,
+                    Err(e) => {
+                        return Ok(ToolResult {
+                            success: false,
+                            output: None,
+                            error: Some(format!("Process execution denied: {:#}", e)),
+                            execution_time: start.elapsed().as_millis() as u64,
+                            context_used: context,
+                        });
+                    }
+                };
+                
+                let mut span = observability::child_span(
+                    "subprocess.exec",
+                    vec![KeyValue::new("process.executable.name", command.to_string())],
+                );
+                let exec_result = process
+                    .run(&executable, &args, &cwd, &trace_env(&trace_carrier()))
+                    .await;
+                if let Ok(output) = &exec_result {
+                    span.set_attribute(KeyValue::new("process.exit_code", output.exit_code.unwrap_or(-1) as i64));
+                }
+                span.end();
+
+                match exec_result {
                     Ok(output) => {
-                        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-                        
+                        let success = output.exit_code == Some(0);
+                        let error = if output.timed_out {
+                            Some(format!("Process killed after {} ms", process.timeout_ms))
+                        } else if !success {
+                            Some(output.stderr.clone())
+                        } else {
+                            None
+                        };
                         return Ok(ToolResult {
-                            success: output.status.success(),
-                            output: Some(serde_json::json!({
-                                "stdout": stdout,
-                                "stderr": stderr,
-                                "exit_code": output.status.code()
-                            })),
-                            error: if !output.status.success() { Some(stderr) } else { None },
+                            success,
+                            output: Some(serde_json::to_value(&output)?),
+                            error,
                             execution_time: start.elapsed().as_millis() as u64,
                             context_used: context,
                         });
@@ -692,7 +1143,7 @@ impl ToolExecutor for InMemoryToolExecutor {
                         return Ok(ToolResult {
                             success: false,
                             output: None,
-                            error: Some(format!("Process execution failed: {}", e)),
+                            error: Some(format!("Process execution failed: {:#}", e)),
                             execution_time: start.elapsed().as_millis() as u64,
                             context_used: context,
                         });
@@ -736,7 +1187,7 @@ impl ToolExecutor for InMemoryToolExecutor {
                 });
             }
             "telemetry.push" => {
-                tracing::info!("Telemetry event: {:?}", input);
+                tracing::info!("Telemetry event: {}", self.redactor.redact_str("log", &input.to_string()));
                 return Ok(ToolResult {
                     success: true,
                     output: Some(serde_json::json!({
@@ -760,6 +1211,91 @@ impl ToolExecutor for InMemoryToolExecutor {
             context_used: context,
         })
     }
+}
+
+#[async_trait]
+impl ToolExecutor for InMemoryToolExecutor {
+    async fn execute(
+        &self,
+        tool_id: &str,
+        input: serde_json::Value,
+        context: ContextFrame,
+    ) -> anyhow::Result<ToolResult> {
+        let start = std::time::Instant::now();
+        let runtime = self.runtime_of(tool_id).await;
+        let frame = context.clone();
+
+        let tracer = opentelemetry::global::tracer(observability::TRACER_NAME);
+        let mut attributes = observability::context_attributes(&frame);
+        attributes.push(KeyValue::new("mcp.tool", tool_id.to_string()));
+        attributes.push(KeyValue::new("mcp.runtime", runtime));
+        let span = tracer
+            .span_builder(format!("tool.execute {}", tool_id))
+            .with_kind(SpanKind::Internal)
+            .with_attributes(attributes)
+            .start_with_context(&tracer, &observability::parent_context(Some(&frame)));
+        let cx = opentelemetry::Context::current_with_span(span);
+
+        // Unregistered ids come from clients, so they share one label value
+        let tool_label = if runtime == "unknown" { "unknown" } else { tool_id };
+        let result = {
+            let _inflight = self.metrics.track_inflight(tool_label, runtime);
+            self.dispatch(tool_id, input, context)
+                .with_context(cx.clone())
+                .await
+                .map(|r| self.redact_result(tool_id, r))
+                .map_err(|e| self.redact_error(tool_id, e))
+        };
+
+        let outcome = match &result {
+            Ok(r) if r.success => "success",
+            Ok(_) => "failure",
+            Err(_) => "error",
+        };
+        self.metrics
+            .observe_tool_call(tool_label, &frame, runtime, outcome, start.elapsed());
+        if let Some(engine) = &self.engine {
+            let key = format!("tool_duration.{}", tool_label);
+            engine.adjust_metric(&key, start.elapsed().as_secs_f64() * 1000.0, &frame);
+            if outcome == "success" {
+                engine.record_success(&key);
+            } else {
+                engine.rollback(&key);
+            }
+        }
+        if let Some(events) = &self.events {
+            let event = Event {
+                stream_id: frame.reason_trace_id.clone(),
+                event_type: "tool.executed".to_string(),
+                data: serde_json::json!({
+                    "tool": tool_label,
+                    "runtime": runtime,
+                    "outcome": outcome,
+                    "duration_ms": start.elapsed().as_millis() as u64,
+                }),
+                metadata: EventMetadata {
+                    correlation_id: uuid::Uuid::new_v4().to_string(),
+                    causation_id: None,
+                    user_id: None,
+                },
+                context: frame.clone(),
+            };
+            if let Err(e) = events.publish(event).await {
+                tracing::debug!("tool.executed event not published: {}", e);
+            }
+        }
+
+        let span = cx.span();
+        span.set_attribute(KeyValue::new("mcp.outcome", outcome));
+        match &result {
+            Ok(r) if !r.success => span.set_status(Status::error(r.error.clone().unwrap_or_default())),
+            Err(e) => span.set_status(Status::error(e.to_string())),
+            Ok(_) => span.set_status(Status::Ok),
+        }
+        span.end();
+
+        result
+    }
 
     async fn validate_manifest(&self, path: &str) -> anyhow::Result<bool> {
         let content = tokio::fs::read_to_string(path).await?;
@@ -792,6 +1328,7 @@ mod tests {
                 entry: "native://test".to_string(),
                 permissions: vec!["read".to_string()],
                 description: "Test tool".to_string(),
+                dry_run: false,
             },
         );
 
@@ -817,14 +1354,18 @@ mod tests {
                 entry: "wasm://fs-write.wasm".to_string(),
                 permissions: vec!["write".to_string()],
                 description: "Write file".to_string(),
+                dry_run: false,
             },
         );
 
-        let mut ctx = ContextFrame::default();
-        ctx.flags = Some(Flags {
-            allow_autotune: true,
-            read_only: true,
-        });
+        let ctx = ContextFrame {
+            flags: Some(Flags {
+                allow_autotune: true,
+                read_only: true,
+                dry_run: false,
+            }),
+            ..Default::default()
+        };
 
         let result = executor
             .execute("fs.write", serde_json::json!({}), ctx)
@@ -833,5 +1374,309 @@ mod tests {
         
         assert!(!result.success);
         assert!(result.error.is_some());
+        assert!(result.error.unwrap().contains("read_only"));
+    }
+
+    #[tokio::test]
+    async fn test_listing_mirrors_manifests() {
+        let executor = InMemoryToolExecutor::new();
+        executor
+            .load_tools(concat!(env!("CARGO_MANIFEST_DIR"), "/../.mcp/tools"))
+            .await
+            .unwrap();
+        let state = crate::server_state::ServerState::new();
+        state.sync_tools(&executor.manifests().await).await;
+        state.toggle_tool("fs.stat", false).await.unwrap();
+        state.sync_tools(&executor.manifests().await).await;
+
+        for tool in ["fs.move", "fs.mkdir", "fs.stat", "session.compress", "scrape.site"] {
+            let manifest = executor.manifest(tool).await.unwrap();
+            let listed = state.get_tool(tool).await.unwrap();
+            assert_eq!(listed.permissions, manifest.permissions, "{}", tool);
+        }
+        assert_eq!(state.get_tool("fs.move").await.unwrap().permissions, ["write", "delete"]);
+        assert_eq!(state.get_tool("scrape.site").await.unwrap().tool_type, "Extension");
+        // A resync keeps the operator's enabled flag
+        assert!(!state.get_tool("fs.stat").await.unwrap().enabled);
+        assert!(executor.manifest("no.such.tool").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_bound_labels_and_publish_events() {
+        let service = observability::ObservabilityService::new();
+        let bus = Arc::new(crate::event_bus::InMemoryEventBus::new().with_metrics(service.metrics()));
+        let engine = Arc::new(ContextEngine::new(true, 10, 0.6).with_metrics(service.metrics()));
+        let executor = InMemoryToolExecutor::new()
+            .with_metrics(service.metrics())
+            .with_events(bus.clone())
+            .with_context_engine(engine);
+        executor
+            .load_tools(concat!(env!("CARGO_MANIFEST_DIR"), "/../.mcp/tools"))
+            .await
+            .unwrap();
+        service.metrics().set_tenants(&["acme".to_string()]);
+
+        for (tool, tenant) in [("no.such.tool", "acme"), ("fs.stat", "tenant-from-a-client")] {
+            let ctx = ContextFrame { tenant_id: tenant.to_string(), ..ContextFrame::default() };
+            let _ = executor.execute(tool, serde_json::json!({"path": "/tmp"}), ctx).await;
+        }
+
+        let families = service.registry().gather();
+        let series = |name: &str| -> Vec<HashMap<String, String>> {
+            families
+                .iter()
+                .filter(|f| f.get_name() == name)
+                .flat_map(|f| f.get_metric())
+                .map(|m| m.get_label().iter().map(|l| (l.get_name().to_string(), l.get_value().to_string())).collect())
+                .collect()
+        };
+        // Unregistered tools and unlisted tenants do not mint new series
+        let mut calls: Vec<(String, String)> = series("mcp_tool_calls_total")
+            .into_iter()
+            .map(|l| (l["tool"].clone(), l["tenant"].clone()))
+            .collect();
+        calls.sort();
+        assert_eq!(
+            calls,
+            [("fs.stat".to_string(), "other".to_string()), ("unknown".to_string(), "acme".to_string())]
+        );
+
+        let published = series("mcp_event_bus_published_total");
+        assert_eq!(published.len(), 2, "one tool.executed event per tenant label");
+        assert!(published.iter().all(|l| l["event_type"] == "tool.executed"));
+
+        // Engine adjustments are counted under the same bounded tool label
+        let metrics = service.metrics();
+        assert_eq!(metrics.engine_adjustment_count("tool_duration.fs.stat", "applied"), 1);
+        assert_eq!(metrics.engine_adjustment_count("tool_duration.unknown", "applied"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_calls_feed_context_engine() {
+        use crate::types::Stage;
+
+        let engine = Arc::new(ContextEngine::new(true, 10, 0.6).with_shadow_stages(vec![Stage::Prod]));
+        let executor = InMemoryToolExecutor::new().with_context_engine(engine.clone());
+        executor
+            .load_tools(concat!(env!("CARGO_MANIFEST_DIR"), "/../.mcp/tools"))
+            .await
+            .unwrap();
+
+        for stage in [Stage::Dev, Stage::Prod] {
+            let ctx = ContextFrame { stage, ..ContextFrame::default() };
+            let result = executor.execute("fs.stat", serde_json::json!({"path": "/tmp"}), ctx).await.unwrap();
+            assert!(result.success);
+        }
+
+        // Dev calls are tracked live, prod calls only as shadow proposals
+        assert!(engine.snapshot().contains_key("tool_duration.fs.stat"));
+        let shadow = engine.shadow_snapshot();
+        let sample = shadow.get("tool_duration.fs.stat").expect("shadow proposal recorded");
+        assert!(sample.live >= 0.0);
+
+        // Blocked frames never reach the engine
+        let ctx = ContextFrame { risk_level: RiskLevel::Block, ..ContextFrame::default() };
+        let _ = executor.execute("fs.stat", serde_json::json!({"path": "/tmp"}), ctx).await;
+        assert_eq!(engine.snapshot().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_side_effect_matrix() {
+        use crate::types::RiskLevel;
+
+        let executor = InMemoryToolExecutor::new();
+        executor
+            .load_tools(concat!(env!("CARGO_MANIFEST_DIR"), "/../.mcp/tools"))
+            .await
+            .unwrap();
+
+        // (tool, class); read_only refuses write and up, risk_level 2 anything but none
+        let matrix = [
+            ("completion.stream", SideEffect::Network),
+            ("db.execute", SideEffect::Write),
+            ("db.query", SideEffect::None),
+            ("db.schema", SideEffect::None),
+            ("embedding.generate", SideEffect::Network),
+            ("env.get", SideEffect::None),
+            ("extract.links", SideEffect::Network),
+            ("fetch.url", SideEffect::Network),
+            ("fs.delete", SideEffect::Delete),
+            ("fs.list", SideEffect::None),
+            ("fs.mkdir", SideEffect::Write),
+            ("fs.move", SideEffect::Delete),
+            ("fs.read", SideEffect::None),
+            ("fs.search", SideEffect::None),
+            ("fs.stat", SideEffect::None),
+            ("fs.write", SideEffect::Write),
+            ("http.request", SideEffect::Network),
+            ("parse.html", SideEffect::None),
+            ("process.execute", SideEffect::Execute),
+            ("scrape.site", SideEffect::Write),
+            ("scrape.url", SideEffect::Write),
+            ("session.compress", SideEffect::Write),
+            ("telemetry.push", SideEffect::Network),
+        ];
+        assert_eq!(executor.tools.read().await.len(), matrix.len(), "every built-in tool is covered");
+
+        // Unknown or missing tags fail closed
+        assert_eq!(SideEffect::from_permissions(&["read".to_string(), "gpu".to_string()]), SideEffect::Execute);
+        assert_eq!(SideEffect::from_permissions(&[]), SideEffect::Execute);
+
+        let read_only = ContextFrame {
+            flags: Some(Flags { allow_autotune: false, read_only: true, dry_run: false }),
+            ..ContextFrame::default()
+        };
+        let block = ContextFrame { risk_level: RiskLevel::Block, ..ContextFrame::default() };
+        for (tool_id, class) in matrix {
+            let side_effect = executor.tools.read().await[tool_id].side_effect();
+            assert_eq!(side_effect, class, "{}", tool_id);
+            assert!(side_effect.refusal(tool_id, &ContextFrame::default()).is_none(), "{}", tool_id);
+
+            for (frame, refused) in [(&read_only, class >= SideEffect::Write), (&block, class > SideEffect::None)] {
+                assert_eq!(side_effect.refusal(tool_id, frame).is_some(), refused, "{}", tool_id);
+                // Refused calls stop before any runtime is reached
+                if refused {
+                    let result = executor.execute(tool_id, serde_json::json!({}), frame.clone()).await.unwrap();
+                    assert!(result.error.unwrap().starts_with("Blocked:"), "{}", tool_id);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let dir = std::env::temp_dir().join(format!("mcp-exec-dry-run-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let executor = InMemoryToolExecutor::with_allowlist(vec![dir.to_string_lossy().to_string()]);
+        for (name, permissions) in [("fs.write", vec!["write"]), ("process.execute", vec!["execute"])] {
+            executor.tools.write().await.insert(
+                name.to_string(),
+                ToolManifest {
+                    name: name.to_string(),
+                    version: "1.0.0".to_string(),
+                    entry: "wasm://missing.wasm".to_string(),
+                    permissions: permissions.into_iter().map(String::from).collect(),
+                    description: String::new(),
+                    dry_run: false,
+                },
+            );
+        }
+        let ctx = ContextFrame {
+            flags: Some(Flags { allow_autotune: false, read_only: false, dry_run: true }),
+            ..ContextFrame::default()
+        };
+
+        let file = dir.join("new.txt");
+        let input = serde_json::json!({"path": file.to_string_lossy(), "content": "hello\n"});
+        let result = executor.execute("fs.write", input, ctx.clone()).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output.as_ref().unwrap()["plan"]["action"], "create");
+        assert!(!file.exists());
+
+        // Without the module the write runs natively
+        let input = serde_json::json!({"path": file.to_string_lossy(), "content": "hello\n"});
+        let result = executor.execute("fs.write", input, ContextFrame::default()).await.unwrap();
+        assert_eq!(result.output.unwrap()["action"], "created");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "hello\n");
+
+        let result = executor
+            .execute("process.execute", serde_json::json!({"command": "ls"}), ctx)
+            .await
+            .unwrap();
+        assert!(result.error.unwrap().contains("does not support dry runs"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_file_payloads_survive_redaction() {
+        use base64::Engine;
+
+        let dir = std::env::temp_dir().join(format!("mcp-exec-payload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let executor = InMemoryToolExecutor::with_allowlist(vec![dir.to_string_lossy().to_string()]);
+        executor.tools.write().await.insert(
+            "fs.read".to_string(),
+            ToolManifest {
+                name: "fs.read".to_string(),
+                version: "1.0.0".to_string(),
+                entry: "wasm://missing.wasm".to_string(),
+                permissions: vec!["read".to_string()],
+                description: String::new(),
+                dry_run: false,
+            },
+        );
+        // Long enough that its base64 is full of high-entropy runs
+        let binary: Vec<u8> = (0..4096u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+        std::fs::write(dir.join("blob.bin"), &binary).unwrap();
+        std::fs::write(dir.join("app.env"), "token=Zk3q9vR2xL7mP0wT5yB8nC4hQ1s\n").unwrap();
+
+        let read = |name: &str| serde_json::json!({"path": dir.join(name).to_string_lossy()});
+        let result = executor.execute("fs.read", read("blob.bin"), ContextFrame::default()).await.unwrap();
+        let output = result.output.unwrap();
+        assert_eq!(output["encoding"], "base64");
+        let content = output["content"].as_str().unwrap();
+        assert_ne!(executor.redactor().redact_str("test", content), content, "plain text redaction would mangle it");
+        assert_eq!(base64::engine::general_purpose::STANDARD.decode(content).unwrap(), binary);
+
+        let result = executor.execute("fs.read", read("app.env"), ContextFrame::default()).await.unwrap();
+        let output = result.output.unwrap();
+        assert_eq!(output["content"], "token=[REDACTED:assignment]\n");
+        assert_eq!(output["redacted"], true);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_execution_errors_are_redacted() {
+        let executor = InMemoryToolExecutor::new();
+        // The unknown tool id is echoed back in the error
+        let tool_id = "token=Zk3q9vR2xL7mP0wT5yB8nC4hQ1s";
+        let error = executor.execute(tool_id, serde_json::json!({}), ContextFrame::default()).await.unwrap_err();
+        assert!(!error.to_string().contains("Zk3q9vR2xL7mP0wT5yB8nC4hQ1s"), "{}", error);
+        assert!(error.to_string().contains("[REDACTED:assignment]"), "{}", error);
+    }
+
+    #[test]
+    fn test_wasi_listing_filtered() {
+        let rules: Vec<FsRule> = serde_json::from_value(serde_json::json!([
+            {"path": "/srv/listing", "mode": "read", "exclude": ["**/.git/**", "**/*.pem"]}
+        ]))
+        .unwrap();
+        let executor = InMemoryToolExecutor::new().with_fs_rules(&rules);
+        let sandbox = executor.sandbox();
+
+        let mut output = serde_json::json!({
+            "entries": [{"name": "README.md"}, {"name": "key.pem"}],
+            "matches": [{"path": "/srv/listing/src/main.rs"}, {"path": "/srv/listing/.git/config"}]
+        });
+        InMemoryToolExecutor::filter_hidden(&sandbox, std::path::Path::new("/srv/listing"), &mut output);
+        assert_eq!(output["entries"].as_array().unwrap().len(), 1);
+        assert_eq!(output["matches"][0]["path"], "/srv/listing/src/main.rs");
+        assert_eq!(output["matches"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_trace_meta_injection() {
+        let carrier = observability::inject_trace_context(
+            &observability::extract_trace_context(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+                None,
+            )
+            .unwrap(),
+        );
+
+        let input = with_trace_meta(
+            serde_json::json!({"url": "http://example.test", "_meta": {"progressToken": 7}}),
+            &carrier,
+        );
+        assert_eq!(input["_meta"]["progressToken"], 7);
+        assert_eq!(
+            input["_meta"]["traceparent"],
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+        assert_eq!(trace_env(&carrier)[0].0, "TRACEPARENT");
+
+        // No active trace leaves the input untouched
+        let plain = with_trace_meta(serde_json::json!({"a": 1}), &HashMap::new());
+        assert!(plain.get("_meta").is_none());
     }
 }
@@ -35,6 +35,17 @@ impl WasiRunner {
         wasm_path: &str,
         input: &Value,
         preopen_dirs: &[&str],
+    ) -> Result<String> {
+        self.exec_with_env(wasm_path, input, preopen_dirs, &[])
+    }
+
+    /// Execute a WASI module, additionally exposing `env` to the guest
+    pub fn exec_with_env(
+        &self,
+        wasm_path: &str,
+        input: &Value,
+        preopen_dirs: &[&str],
+        env: &[(String, String)],
     ) -> Result<String> {
         // Check if wasmtime is available
         if self.wasmtime_bin.is_empty() {
@@ -62,6 +73,11 @@ impl WasiRunner {
                 tracing::debug!("Preopening directory: {}", dir);
             }
         }
+
+        // Guest environment is empty unless passed explicitly
+        for (key, value) in env {
+            cmd.arg(format!("--env={}={}", key, value));
+        }
         
         // Add the wasm file path
         cmd.arg(wasm_path);
@@ -87,7 +103,8 @@ impl WasiRunner {
             Ok(stdout)
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-            tracing::error!("WASI execution failed: {}", stderr);
+            // stderr goes back to the executor, which redacts it before logging
+            tracing::debug!("WASI process exited with {}", output.status);
             anyhow::bail!("WASI execution failed: {}", stderr)
         }
     }
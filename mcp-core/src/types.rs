@@ -10,7 +10,7 @@ pub struct ContextFrame {
     pub tenant_id: String,
     /// Deployment stage — required
     pub stage: Stage,
-    /// Risk level: 0=safe, 1=caution, 2=block autotune — required
+    /// Risk level: 0=safe, 1=caution, 2=block side effects and autotune — required
     pub risk_level: RiskLevel,
     /// Measure of new behavior (0..1)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -24,6 +24,12 @@ pub struct ContextFrame {
     /// Feature flags
     #[serde(skip_serializing_if = "Option::is_none")]
     pub flags: Option<Flags>,
+    /// W3C `traceparent` linking this frame to a distributed trace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
+    /// W3C `tracestate` accompanying `traceparent`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracestate: Option<String>,
     /// ISO timestamp
     pub ts: DateTime<Utc>,
 }
@@ -36,7 +42,18 @@ pub enum Stage {
     Prod,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl Stage {
+    /// Lowercase name, matching the serialized form
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Dev => "dev",
+            Stage::Staging => "staging",
+            Stage::Prod => "prod",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum RiskLevel {
     Safe = 0,
     Caution = 1,
@@ -86,6 +103,9 @@ pub struct Flags {
     pub allow_autotune: bool,
     #[serde(default)]
     pub read_only: bool,
+    /// Side-effecting tools describe what they would do instead of doing it
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 impl Default for ContextFrame {
@@ -99,6 +119,8 @@ impl Default for ContextFrame {
             context_confidence: Some(0.7),
             budgets: None,
             flags: None,
+            traceparent: None,
+            tracestate: None,
             ts: Utc::now(),
         }
     }
@@ -123,6 +145,11 @@ impl ContextFrame {
                 return Err("context_confidence must be between 0.0 and 1.0".to_string());
             }
         }
+        if let Some(tp) = &self.traceparent {
+            if !is_valid_traceparent(tp) {
+                return Err("traceparent must be a W3C trace context (version-traceid-spanid-flags)".to_string());
+            }
+        }
         Ok(())
     }
 
@@ -130,8 +157,29 @@ impl ContextFrame {
     pub fn can_autotune(&self) -> bool {
         self.risk_level == RiskLevel::Safe
             && self.context_confidence.unwrap_or(0.0) >= 0.6
-            && self.flags.as_ref().map_or(true, |f| f.allow_autotune)
+            && self.flags.as_ref().is_none_or(|f| f.allow_autotune)
+    }
+}
+
+/// Check the W3C `traceparent` shape: `2hex-32hex-16hex-2hex`, non-zero ids, version != ff
+pub fn is_valid_traceparent(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    if parts.len() < 4 {
+        return false;
     }
+    let hex = |s: &str, len: usize| {
+        s.len() == len && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    };
+    let (version, trace_id, span_id, flags) = (parts[0], parts[1], parts[2], parts[3]);
+    // Version 00 has exactly four fields; future versions may append more
+    (parts.len() == 4 || version != "00")
+        && hex(version, 2)
+        && version != "ff"
+        && hex(trace_id, 32)
+        && trace_id.bytes().any(|b| b != b'0')
+        && hex(span_id, 16)
+        && span_id.bytes().any(|b| b != b'0')
+        && hex(flags, 2)
 }
 
 /// Event metadata for persistence layer
@@ -187,6 +235,26 @@ mod tests {
         assert!(invalid_ctx.validate().is_err());
     }
 
+    #[test]
+    fn test_traceparent_validation() {
+        let mut ctx = ContextFrame {
+            traceparent: Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string()),
+            ..ContextFrame::default()
+        };
+        assert!(ctx.validate().is_ok());
+
+        for bad in [
+            "not-a-traceparent",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+        ] {
+            ctx.traceparent = Some(bad.to_string());
+            assert!(ctx.validate().is_err(), "{} should be rejected", bad);
+        }
+    }
+
     #[test]
     fn test_autotune_safety() {
         let mut ctx = ContextFrame::default();
@@ -13,6 +13,8 @@ fn context_required_for_writes() {
         ts: chrono::Utc::now().to_rfc3339(),
         budgets: None,
         flags: None,
+        traceparent: None,
+        tracestate: None,
     };
 
     let result = ctx.validate();
@@ -31,6 +33,8 @@ fn context_validation_enforces_stage() {
         ts: chrono::Utc::now().to_rfc3339(),
         budgets: None,
         flags: None,
+        traceparent: None,
+        tracestate: None,
     };
 
     assert!(ctx.validate().is_err(), "Invalid stage should fail validation");
@@ -51,6 +55,8 @@ fn context_validation_enforces_risk_level() {
         ts: chrono::Utc::now().to_rfc3339(),
         budgets: None,
         flags: None,
+        traceparent: None,
+        tracestate: None,
     };
 
     assert!(ctx.validate().is_err(), "Invalid risk_level should fail");
@@ -68,6 +74,8 @@ fn autotune_bounds_respected() {
         context_confidence: Some(0.7),
         budgets: None,
         flags: None,
+        traceparent: None,
+        tracestate: None,
         ts: chrono::Utc::now(),
     };
 
@@ -82,6 +90,29 @@ fn autotune_bounds_respected() {
     assert!(!ctx_block.can_autotune(), "Blocked risk level should prevent autotune");
 }
 
+#[test]
+fn context_traceparent_round_trips() {
+    let json = r#"{
+        "reason_trace_id": "step-42",
+        "tenant_id": "default",
+        "stage": "dev",
+        "risk_level": 0,
+        "ts": "2025-11-03T00:00:00Z",
+        "traceparent": "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "tracestate": "vendor=abc"
+    }"#;
+
+    let contract: ContextFrame = serde_json::from_str(json).expect("Should deserialize");
+    assert!(contract.validate().is_ok());
+
+    let frame: TypesContextFrame = serde_json::from_str(json).expect("Should deserialize");
+    assert!(frame.validate().is_ok());
+    assert_eq!(frame.tracestate.as_deref(), Some("vendor=abc"));
+
+    let out = serde_json::to_value(&frame).unwrap();
+    assert_eq!(out["traceparent"], "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+}
+
 #[test]
 fn event_metadata_validation() {
     let metadata = EventMetadata {
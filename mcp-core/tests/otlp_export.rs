@@ -0,0 +1,84 @@
+use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+use nurones_mcp::observability::{self, ObservabilityService, TraceStatus};
+use nurones_mcp::tool_executor::{InMemoryToolExecutor, ToolExecutor};
+use nurones_mcp::types::ContextFrame;
+use opentelemetry::trace::FutureExt;
+use std::time::Duration;
+
+/// Minimal OTLP/HTTP collector stand-in: records every body posted to /v1/traces
+async fn start_collector() -> (String, tokio::sync::mpsc::UnboundedReceiver<(String, Vec<u8>)>) {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let app = Router::new().route(
+        "/v1/traces",
+        post(move |headers: HeaderMap, body: Bytes| {
+            let tx = tx.clone();
+            async move {
+                let content_type = headers
+                    .get("content-type")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                let _ = tx.send((content_type, body.to_vec()));
+                ""
+            }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{}", addr), rx)
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle.as_bytes())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn request_and_tool_spans_exported_over_otlp_http() {
+    let (endpoint, mut received) = start_collector().await;
+    observability::init_otlp_tracing(&endpoint).expect("OTLP pipeline should install");
+
+    let manifest = std::env::temp_dir().join(format!("otlp-telemetry-{}.json", uuid::Uuid::new_v4()));
+    std::fs::write(
+        &manifest,
+        r#"{"name": "telemetry.push", "version": "1.0.0", "entry": "native://telemetry", "permissions": ["emit"]}"#,
+    )
+    .unwrap();
+    let executor = InMemoryToolExecutor::new();
+    executor.register_tool(manifest.to_str().unwrap()).await.unwrap();
+    std::fs::remove_file(&manifest).ok();
+
+    let service = ObservabilityService::new();
+    let ctx = ContextFrame {
+        tenant_id: "acme".to_string(),
+        ..ContextFrame::default()
+    };
+    let trace_id = service.start_trace("mcp.tools/execute telemetry.push", Some(ctx.clone())).await;
+    let cx = service.trace_context(&trace_id).await.unwrap();
+    let result = executor
+        .execute("telemetry.push", serde_json::json!({"event": "test"}), ctx)
+        .with_context(cx)
+        .await
+        .unwrap();
+    assert!(result.success);
+    service.end_trace(&trace_id, TraceStatus::Ok).await;
+
+    // Shutdown flushes the batch processor
+    tokio::task::spawn_blocking(observability::shutdown_tracing).await.unwrap();
+
+    let mut bodies = Vec::new();
+    while let Ok(Some((content_type, body))) =
+        tokio::time::timeout(Duration::from_secs(5), received.recv()).await
+    {
+        assert_eq!(content_type, "application/x-protobuf");
+        bodies.extend(body);
+        if contains(&bodies, "tool.execute telemetry.push") && contains(&bodies, "mcp.tools/execute") {
+            break;
+        }
+    }
+
+    assert!(contains(&bodies, "mcp.tools/execute telemetry.push"), "request span exported");
+    assert!(contains(&bodies, "tool.execute telemetry.push"), "tool span exported");
+    assert!(contains(&bodies, "mcp.tenant_id") && contains(&bodies, "acme"), "ContextFrame attributes exported");
+}
@@ -16,6 +16,8 @@ export const ContextFrameSchema = {
     context_confidence: { type: "number", minimum: 0, maximum: 1 },
     budgets: { type: "object", additionalProperties: true },
     flags: { type: "object", additionalProperties: true },
+    traceparent: { type: "string", pattern: "^[0-9a-f]{2}-[0-9a-f]{32}-[0-9a-f]{16}-[0-9a-f]{2}(-.*)?$" },
+    tracestate: { type: "string" },
     ts: { type: "string", format: "date-time" }
   },
   additionalProperties: false
@@ -64,6 +66,8 @@ export type ContextFrame = {
   context_confidence?: number;
   budgets?: Record<string, number>;
   flags?: Record<string, boolean>;
+  traceparent?: string;
+  tracestate?: string;
   ts: string;
 };
 
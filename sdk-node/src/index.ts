@@ -29,7 +29,13 @@ export interface ContextFrame {
   flags?: {
     allow_autotune?: boolean;
     read_only?: boolean;
+    /** Side-effecting tools return a plan instead of acting */
+    dry_run?: boolean;
   };
+  /** W3C trace context linking this frame to a distributed trace */
+  traceparent?: string;
+  /** W3C tracestate accompanying traceparent */
+  tracestate?: string;
   /** ISO timestamp */
   ts: string;
 }